@@ -4,7 +4,7 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use crate::{AnsiFlags, Color, ToColor};
+use crate::{AnsiBytes, AnsiFlags, Color, PackedAnsi, ToColor};
 
 /// Type for storing the configuration of an ANSI color code.
 ///
@@ -62,6 +62,12 @@ impl Ansi {
         }
     }
 
+    /// Creates a new Ansi from its individual parts.
+    #[must_use]
+    pub const fn from_parts(fg: Option<Color>, bg: Option<Color>, flags: AnsiFlags) -> Self {
+        Self { fg, bg, flags }
+    }
+
     /// Creates a new Ansi from the given foreground color.
     #[allow(clippy::needless_pass_by_value)]
     #[must_use]
@@ -86,6 +92,20 @@ impl Ansi {
         }
     }
 
+    /// Creates a new Ansi from the given foreground [`Color`]. Unlike [`Ansi::from_fg`] this
+    /// is usable in `const` contexts.
+    #[must_use]
+    pub const fn from_fg_color(fg: Color) -> Self {
+        Self::new().fg_color(fg)
+    }
+
+    /// Creates a new Ansi from the given background [`Color`]. Unlike [`Ansi::from_bg`] this
+    /// is usable in `const` contexts.
+    #[must_use]
+    pub const fn from_bg_color(bg: Color) -> Self {
+        Self::new().bg_color(bg)
+    }
+
     /// Creates a new Ansi with a red foreground color.
     #[must_use]
     #[cfg_attr(feature = "trace", tracing::instrument)]
@@ -212,6 +232,7 @@ impl Ansi {
         for num in ansi_nums {
             match num {
                 1 => ansi = ansi.bold(),
+                2 => ansi = ansi.dim(),
                 3 => ansi = ansi.italic(),
                 4 => ansi = ansi.underline(),
                 5 => ansi = ansi.blink(),
@@ -245,6 +266,51 @@ impl Ansi {
         self.fg.is_none() && self.bg.is_none() && self.flags.is_empty()
     }
 
+    /// Gets the foreground color of this `Ansi`, if one is set.
+    #[must_use]
+    pub const fn foreground(&self) -> Option<Color> {
+        self.fg
+    }
+
+    /// Gets the background color of this `Ansi`, if one is set.
+    #[must_use]
+    pub const fn background(&self) -> Option<Color> {
+        self.bg
+    }
+
+    /// Gets the [`AnsiFlags`] currently set on this `Ansi`.
+    #[must_use]
+    pub const fn flags(&self) -> AnsiFlags {
+        self.flags
+    }
+
+    /// Packs this `Ansi` into its compact [`PackedAnsi`] representation.
+    #[must_use]
+    pub const fn pack(&self) -> PackedAnsi {
+        PackedAnsi::pack(*self)
+    }
+
+    /// Gets the rendered escape string for this `Ansi` from the process-wide interning
+    /// cache, rendering and storing it on first use.
+    ///
+    /// Prefer this over [`ToString::to_string`] when the same handful of styles are
+    /// rendered over and over, since each distinct style is only ever rendered once. The
+    /// returned [`DefaultAtom`](string_cache::DefaultAtom) derefs to `&str` and is cheap to clone.
+    ///
+    /// The cache holds at most a few thousand styles and starts over once it is full, so
+    /// output that creates a new style for nearly every character (e.g. gradients) does not
+    /// grow it without bound. See [`Ansi::clear_interned_strings`] to empty it manually.
+    #[must_use]
+    pub fn to_interned_string(&self) -> string_cache::DefaultAtom {
+        crate::ansi::cache::global_lookup(self)
+    }
+
+    /// Empties the process-wide cache used by [`Ansi::to_interned_string`]. Strings that
+    /// were already handed out stay valid.
+    pub fn clear_interned_strings() {
+        crate::ansi::cache::global_clear();
+    }
+
     /// Builder function to set the foreground color.
     #[allow(clippy::needless_pass_by_value)]
     #[must_use]
@@ -256,12 +322,39 @@ impl Ansi {
         }
     }
 
+    /// Builder function to set the foreground color directly from a [`Color`]. Unlike
+    /// [`Ansi::fg`] this is usable in `const` contexts.
+    #[must_use]
+    pub const fn fg_color(self, fg: Color) -> Self {
+        Self {
+            fg: Some(fg),
+            ..self
+        }
+    }
+
     /// Builder function to clear the foreground color.
     #[must_use]
     pub const fn clear_fg(self) -> Self {
         Self { fg: None, ..self }
     }
 
+    /// Builder function to change the foreground color with `f`, if there is one.
+    ///
+    /// ```
+    /// # use ansirs::{Ansi, Color};
+    /// let swap = |c: Color| Color::from_rgb(c.b(), c.g(), c.r());
+    /// let style = Ansi::from_fg((0x40, 0x80, 0xff)).map_fg(swap);
+    /// assert_eq!(style.foreground(), Some(Color::from_rgb(0xff, 0x80, 0x40)));
+    /// assert_eq!(Ansi::new().map_fg(swap), Ansi::new());
+    /// ```
+    #[must_use]
+    pub fn map_fg(self, f: impl FnOnce(Color) -> Color) -> Self {
+        Self {
+            fg: self.fg.map(f),
+            ..self
+        }
+    }
+
     /// Builder function to set the background color.
     #[allow(clippy::needless_pass_by_value)]
     #[must_use]
@@ -273,12 +366,50 @@ impl Ansi {
         }
     }
 
-    /// Builder function to clear the foreground color.
+    /// Builder function to set the background color directly from a [`Color`]. Unlike
+    /// [`Ansi::bg`] this is usable in `const` contexts.
+    #[must_use]
+    pub const fn bg_color(self, bg: Color) -> Self {
+        Self {
+            bg: Some(bg),
+            ..self
+        }
+    }
+
+    /// Builder function to clear the background color.
     #[must_use]
     pub const fn clear_bg(self) -> Self {
         Self { bg: None, ..self }
     }
 
+    /// Builder function to change the background color with `f`, if there is one.
+    #[must_use]
+    pub fn map_bg(self, f: impl FnOnce(Color) -> Color) -> Self {
+        Self {
+            bg: self.bg.map(f),
+            ..self
+        }
+    }
+
+    /// Builder function to turn on the given flags. Unlike the individual flag builders this
+    /// always *sets* the flags instead of toggling them.
+    #[must_use]
+    pub const fn with_flags(self, flags: AnsiFlags) -> Self {
+        Self {
+            flags: self.flags.insert_to(flags),
+            ..self
+        }
+    }
+
+    /// Builder function to turn off the given flags.
+    #[must_use]
+    pub const fn without_flags(self, flags: AnsiFlags) -> Self {
+        Self {
+            flags: self.flags.remove_to(flags),
+            ..self
+        }
+    }
+
     /// Builder function to toggle whether the color is bold.
     #[must_use]
     pub const fn bold(self) -> Self {
@@ -288,6 +419,15 @@ impl Ansi {
         }
     }
 
+    /// Builder function to toggle whether the color is dimmed / faint.
+    #[must_use]
+    pub const fn dim(self) -> Self {
+        Self {
+            flags: self.flags.toggle_to(AnsiFlags::DIM),
+            ..self
+        }
+    }
+
     /// Builder function to toggle whether the color is underlined.
     #[must_use]
     pub const fn underline(self) -> Self {
@@ -333,100 +473,20 @@ impl Ansi {
         }
     }
 
-    /// Creates a string from this `Ansi` using a `String` to store temporary data.
+    /// Encodes the escape sequence for this `Ansi` into a fixed-size buffer. This is a
+    /// `const fn`, so it can be used to compute escape sequences at compile time.
+    ///
+    /// See also the [`ansi_str!`](crate::ansi_str) macro.
     #[must_use]
-    #[cfg_attr(feature = "trace", tracing::instrument)]
-    fn build_ansi_string(&self) -> String {
-        use std::fmt::Write;
-
-        if self.is_default() {
-            return String::new();
-        }
-
-        let mut modified = false;
-        let mut ansi = String::with_capacity(20);
-
-        if self.flags.contains(AnsiFlags::BOLD) {
-            ansi.push('1');
-            modified = true;
-        }
-
-        if self.flags.contains(AnsiFlags::ITALIC) {
-            if modified {
-                ansi.push_str(";3");
-            } else {
-                ansi.push('3');
-                modified = true;
-            }
-        }
-
-        if self.flags.contains(AnsiFlags::UNDERLINE) {
-            if modified {
-                ansi.push_str(";4");
-            } else {
-                ansi.push('4');
-                modified = true;
-            }
-        }
-
-        if self.flags.contains(AnsiFlags::BLINK) {
-            if modified {
-                ansi.push_str(";5");
-            } else {
-                ansi.push('5');
-                modified = true;
-            }
-        }
-
-        if self.flags.contains(AnsiFlags::REVERSE) {
-            if modified {
-                ansi.push_str(";7");
-            } else {
-                ansi.push('7');
-                modified = true;
-            }
-        }
-
-        if self.flags.contains(AnsiFlags::STRIKE) {
-            if modified {
-                ansi.push_str(";9");
-            } else {
-                ansi.push('9');
-                modified = true;
-            }
-        }
-
-        if let Some(color) = self.fg {
-            let (r, g, b) = color.rgb();
-            if modified {
-                ansi.push_str(";38;2;");
-            } else {
-                ansi.push_str("38;2;");
-            }
-
-            write!(ansi, "{r};{g};{b}").expect("Failed to write! to string");
-            // ansi.push_str(&format!("{};{};{}", r, g, b));
-            modified = true;
-        }
-
-        if let Some(c) = self.bg {
-            let (r, g, b) = c.rgb();
-            if modified {
-                ansi.push_str(";48;2;");
-            } else {
-                ansi.push_str("48;2;");
-            }
-            write!(ansi, "{r};{g};{b}").expect("Failed to write! to string");
-            // ansi.push_str(&format!("{};{};{}", r, g, b));
-            modified = true;
-        }
-
-        // This seems like it will be unnecessary, I can't even get the branch to hit during testing.
-        if !modified {
-            return String::new();
-        }
+    pub const fn encode(&self) -> AnsiBytes {
+        AnsiBytes::encode(self)
+    }
 
-        format!("{}{}{}", Self::PREFIX, ansi, Self::SUFFIX)
+    /// Creates a string from this `Ansi`.
+    #[must_use]
+    #[cfg_attr(feature = "trace", tracing::instrument)]
+    pub(crate) fn build_ansi_string(&self) -> String {
+        self.encode().as_str().to_string()
     }
 
     /// Convenience function that uses this [`Ansi`] to style the given [`text`],
@@ -439,7 +499,7 @@ impl Ansi {
             return text.to_string();
         }
 
-        format!("{}{}{}", self.build_ansi_string(), text, Self::reset())
+        format!("{}{}{}", self.encode(), text, Self::reset())
     }
 }
 
@@ -450,8 +510,14 @@ impl Default for Ansi {
 }
 
 impl std::fmt::Display for Ansi {
+    /// Writes the escape sequence for this style. The alternate flag (`{:#}`) writes the
+    /// visible form instead, see [`Ansi::escaped`].
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.build_ansi_string())
+        if f.alternate() {
+            f.write_str(&self.escaped())
+        } else {
+            f.write_str(self.encode().as_str())
+        }
     }
 }
 
@@ -487,6 +553,10 @@ mod tests {
             ansi.to_string(),
             "\u{1b}[1;3;4;5;7;9;38;2;50;250;150;48;2;25;25;25m"
         );
+        assert_eq!(
+            ansi.dim().to_string(),
+            "\u{1b}[1;2;3;4;5;7;9;38;2;50;250;150;48;2;25;25;25m"
+        );
 
         ansi = ansi.clear();
         assert!(ansi.to_string().is_empty());
@@ -504,6 +574,8 @@ mod tests {
         assert_eq!(a.to_string(), "\u{1b}[5m");
         let a = Ansi::new().bold();
         assert_eq!(a.to_string(), "\u{1b}[1m");
+        let a = Ansi::new().dim();
+        assert_eq!(a.to_string(), "\u{1b}[2m");
         let a = Ansi::new().italic();
         assert_eq!(a.to_string(), "\u{1b}[3m");
         let a = Ansi::new().underline();
@@ -0,0 +1,180 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::{
+    collections::HashMap,
+    sync::{OnceLock, PoisonError, RwLock},
+};
+
+use string_cache::DefaultAtom;
+use string_interner::{DefaultSymbol, StringInterner};
+
+use crate::{Ansi, PackedAnsi};
+
+/// A cache that memoizes the rendered escape string for each distinct [`Ansi`] style.
+///
+/// Rendering an [`Ansi`] allocates a fresh [`String`] every time, which adds up when the
+/// same few styles are used for every line of a high-volume log. [`AnsiCache`] renders
+/// each distinct style once, keyed on its [`PackedAnsi`] encoding, and hands out borrowed
+/// `&str`s from then on.
+///
+/// See [`Ansi::to_interned_string`] for a process-wide, thread-safe alternative.
+///
+/// ## Example
+/// ```
+/// # use ansirs::{Ansi, AnsiCache};
+/// let mut cache = AnsiCache::new();
+/// let style = Ansi::new().fg((255, 0, 0)).bold();
+/// assert_eq!(cache.get_or_render(&style), "\x1b[1;38;2;255;0;0m");
+/// assert_eq!(cache.get_or_render(&style), style.to_string());
+/// assert_eq!(cache.len(), 1);
+/// ```
+#[derive(Debug, Default)]
+pub struct AnsiCache {
+    interner: StringInterner,
+    symbols: HashMap<PackedAnsi, DefaultSymbol>,
+}
+
+impl AnsiCache {
+    /// Creates a new, empty [`AnsiCache`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gets the rendered escape string for `style`, rendering and storing it if this is
+    /// the first time it has been requested.
+    pub fn get_or_render(&mut self, style: impl Into<PackedAnsi>) -> &str {
+        let packed = style.into();
+        let interner = &mut self.interner;
+        let symbol = *self
+            .symbols
+            .entry(packed)
+            .or_insert_with(|| interner.get_or_intern(packed.unpack().build_ansi_string()));
+
+        // Every symbol stored in `symbols` came from `interner`, so this always resolves.
+        self.interner.resolve(symbol).unwrap_or_default()
+    }
+
+    /// Gets the rendered escape string for `style` if it has already been cached.
+    #[must_use]
+    pub fn get(&self, style: impl Into<PackedAnsi>) -> Option<&str> {
+        self.symbols
+            .get(&style.into())
+            .and_then(|symbol| self.interner.resolve(*symbol))
+    }
+
+    /// Gets the number of distinct styles stored in this cache.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    /// Returns `true` if no styles have been cached yet.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// Removes all cached styles.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// The most styles kept in the process-wide cache before it starts over.
+const GLOBAL_CAPACITY: usize = 4096;
+
+fn global_cache() -> &'static RwLock<HashMap<PackedAnsi, DefaultAtom>> {
+    static CACHE: OnceLock<RwLock<HashMap<PackedAnsi, DefaultAtom>>> = OnceLock::new();
+    CACHE.get_or_init(RwLock::default)
+}
+
+/// Looks up (or renders and stores) `style` in the process-wide cache.
+pub(crate) fn global_lookup(style: &Ansi) -> DefaultAtom {
+    let packed = style.pack();
+    // A poisoned lock only means another thread panicked mid-insert, the map itself is still fine.
+    if let Some(atom) = global_cache()
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(&packed)
+    {
+        return atom.clone();
+    }
+
+    let atom = DefaultAtom::from(style.build_ansi_string());
+    let mut cache = global_cache()
+        .write()
+        .unwrap_or_else(PoisonError::into_inner);
+    if cache.len() >= GLOBAL_CAPACITY {
+        cache.clear();
+    }
+    cache.entry(packed).or_insert(atom).clone()
+}
+
+/// Empties the process-wide cache.
+pub(crate) fn global_clear() {
+    global_cache()
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn local_cache() {
+        let mut cache = AnsiCache::new();
+        let red = Ansi::from_fg((255, 0, 0));
+        let blue = Ansi::from_bg((0, 0, 255)).underline();
+        assert!(cache.is_empty());
+        assert_eq!(cache.get(red), None);
+
+        assert_eq!(cache.get_or_render(red), red.to_string());
+        assert_eq!(cache.get_or_render(blue), blue.to_string());
+        assert_eq!(cache.get_or_render(red.pack()), red.to_string());
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(blue), Some(blue.to_string().as_str()));
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn default_style_is_empty_string() {
+        let mut cache = AnsiCache::new();
+        assert_eq!(cache.get_or_render(Ansi::new()), "");
+    }
+
+    #[test]
+    fn global_cache() {
+        let style = Ansi::new().fg((10, 20, 30)).italic();
+        let first = style.to_interned_string();
+        let second = style.to_interned_string();
+        assert_eq!(&*first, style.to_string());
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn global_cache_is_bounded() {
+        for i in 0..=GLOBAL_CAPACITY {
+            let [r, g, b, _] = u32::try_from(i).unwrap().to_le_bytes();
+            let style = Ansi::from_fg((r, g, b)).bg((1, 2, 3));
+            assert_eq!(&*style.to_interned_string(), style.to_string());
+        }
+        let len = || super::global_cache().read().unwrap().len();
+        assert!(len() <= GLOBAL_CAPACITY);
+
+        let style = Ansi::new().strike();
+        let before = style.to_interned_string();
+        Ansi::clear_interned_strings();
+        assert_eq!(&*before, style.to_string());
+        assert_eq!(style.to_interned_string(), before);
+    }
+}
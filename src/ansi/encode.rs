@@ -0,0 +1,234 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::{Ansi, AnsiFlags, Color};
+
+/// A fixed-size, stack allocated buffer holding the rendered escape sequence of an [`Ansi`].
+///
+/// This is produced by the `const` function [`Ansi::encode`], which means the escape
+/// sequence for a style can be computed entirely at compile time. See the [`ansi_str!`](crate::ansi_str)
+/// macro for a shortcut that yields a `&'static str`.
+///
+/// ## Example
+/// ```
+/// # use ansirs::{Ansi, AnsiBytes, Color};
+/// const HEADER: Ansi = Ansi::new().fg_color(Color::hex("#1e90ff")).bold();
+/// const HEADER_BYTES: AnsiBytes = HEADER.encode();
+///
+/// assert_eq!(HEADER_BYTES.as_str(), "\x1b[1;38;2;30;144;255m");
+/// assert_eq!(HEADER_BYTES.as_str(), HEADER.to_string());
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AnsiBytes {
+    bytes: [u8; Self::CAPACITY],
+    len: usize,
+}
+
+impl AnsiBytes {
+    /// The maximum number of bytes an encoded [`Ansi`] can take up.
+    ///
+    /// The longest possible sequence (every flag plus an RGB foreground and background)
+    /// is a little over 50 bytes, this leaves some breathing room.
+    pub const CAPACITY: usize = 64;
+
+    const fn new() -> Self {
+        Self {
+            bytes: [0; Self::CAPACITY],
+            len: 0,
+        }
+    }
+
+    /// Gets the encoded escape sequence as a string slice.
+    // The buffer is only ever written to with ASCII so the panic is unreachable.
+    #[allow(clippy::missing_panics_doc)]
+    #[must_use]
+    pub const fn as_str(&self) -> &str {
+        match std::str::from_utf8(self.as_bytes()) {
+            Ok(s) => s,
+            Err(_) => panic!("AnsiBytes only ever contains ASCII"),
+        }
+    }
+
+    /// Gets the encoded escape sequence as a byte slice.
+    #[must_use]
+    pub const fn as_bytes(&self) -> &[u8] {
+        self.bytes.split_at(self.len).0
+    }
+
+    /// Gets the length of the encoded escape sequence in bytes.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if nothing was encoded, i.e. the [`Ansi`] had no styling.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    const fn push(mut self, byte: u8) -> Self {
+        self.bytes[self.len] = byte;
+        self.len += 1;
+        self
+    }
+
+    const fn push_str(mut self, s: &str) -> Self {
+        let bytes = s.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            self = self.push(bytes[i]);
+            i += 1;
+        }
+        self
+    }
+
+    const fn push_u8(mut self, n: u8) -> Self {
+        if n >= 100 {
+            self = self.push(b'0' + n / 100);
+        }
+        if n >= 10 {
+            self = self.push(b'0' + (n / 10) % 10);
+        }
+        self.push(b'0' + n % 10)
+    }
+
+    const fn push_param(self, param: &str, first: bool) -> Self {
+        if first {
+            self.push_str(param)
+        } else {
+            self.push(b';').push_str(param)
+        }
+    }
+
+    const fn push_color(self, prefix: &str, color: Color, first: bool) -> Self {
+        self.push_param(prefix, first)
+            .push_u8(color.r())
+            .push(b';')
+            .push_u8(color.g())
+            .push(b';')
+            .push_u8(color.b())
+    }
+
+    /// Encodes `ansi` into a new buffer. This is the implementation behind [`Ansi::encode`].
+    pub(crate) const fn encode(ansi: &Ansi) -> Self {
+        const FLAG_PARAMS: [(AnsiFlags, &str); 7] = [
+            (AnsiFlags::BOLD, "1"),
+            (AnsiFlags::DIM, "2"),
+            (AnsiFlags::ITALIC, "3"),
+            (AnsiFlags::UNDERLINE, "4"),
+            (AnsiFlags::BLINK, "5"),
+            (AnsiFlags::REVERSE, "7"),
+            (AnsiFlags::STRIKE, "9"),
+        ];
+
+        let mut out = Self::new();
+        if ansi.is_default() {
+            return out;
+        }
+
+        out = out.push_str("\x1b[");
+        let mut first = true;
+        let flags = ansi.flags();
+
+        let mut i = 0;
+        while i < FLAG_PARAMS.len() {
+            let (flag, param) = FLAG_PARAMS[i];
+            if flags.contains(flag) {
+                out = out.push_param(param, first);
+                first = false;
+            }
+            i += 1;
+        }
+
+        if let Some(fg) = ansi.foreground() {
+            out = out.push_color("38;2;", fg, first);
+            first = false;
+        }
+
+        if let Some(bg) = ansi.background() {
+            out = out.push_color("48;2;", bg, first);
+        }
+
+        out.push(b'm')
+    }
+}
+
+impl Default for AnsiBytes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::ops::Deref for AnsiBytes {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for AnsiBytes {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl std::fmt::Debug for AnsiBytes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("AnsiBytes").field(&self.as_str()).finish()
+    }
+}
+
+impl std::fmt::Display for AnsiBytes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn const_encoding() {
+        const EMPTY: AnsiBytes = Ansi::new().encode();
+        const THE_WORKS: AnsiBytes = Ansi::new()
+            .fg_color(Color::from_rgb(50, 250, 150))
+            .bg_color(Color::from_rgb(25, 25, 25))
+            .bold()
+            .blink()
+            .italic()
+            .reverse()
+            .strike()
+            .underline()
+            .encode();
+        const BG_ONLY: &str = crate::ansi_str!(Ansi::from_bg_color(Color::from_rgb(0, 0, 0)));
+
+        assert!(EMPTY.is_empty());
+        assert_eq!(EMPTY.as_str(), "");
+        assert_eq!(
+            THE_WORKS.as_str(),
+            "\u{1b}[1;3;4;5;7;9;38;2;50;250;150;48;2;25;25;25m"
+        );
+        assert_eq!(BG_ONLY, "\u{1b}[48;2;0;0;0m");
+    }
+
+    #[test]
+    fn number_encoding() {
+        let values = [0u8, 1, 9, 10, 99, 100, 199, 255];
+        for r in values {
+            for g in values {
+                let style = Ansi::new().fg((r, g, 7)).bg((g, r, 200)).underline();
+                assert_eq!(
+                    style.encode().as_str(),
+                    format!("\u{1b}[4;38;2;{r};{g};7;48;2;{g};{r};200m")
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,116 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+/// Error type used when parsing an [`Ansi`](crate::Ansi) from a style spec string such as
+/// `"bold red on blue"`.
+///
+/// Every variant carries the offending `token` and its byte `position` within the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnsiParseError {
+    /// The token is neither a known attribute nor a color.
+    UnknownToken {
+        /// The offending token.
+        token: String,
+        /// Byte offset of the token in the input.
+        position: usize,
+    },
+    /// The token was expected to be a color but could not be parsed as one.
+    InvalidColor {
+        /// The offending token.
+        token: String,
+        /// Byte offset of the token in the input.
+        position: usize,
+    },
+    /// A color was given but both the foreground and background were already set.
+    TooManyColors {
+        /// The offending token.
+        token: String,
+        /// Byte offset of the token in the input.
+        position: usize,
+    },
+    /// A keyword that must be followed by a color (`on`, `fg=`, `bg=`) was not.
+    MissingColor {
+        /// The keyword missing its color.
+        token: String,
+        /// Byte offset of the token in the input.
+        position: usize,
+    },
+}
+
+impl AnsiParseError {
+    /// Gets the token that caused this error.
+    #[must_use]
+    pub fn token(&self) -> &str {
+        match self {
+            AnsiParseError::UnknownToken { token, .. }
+            | AnsiParseError::InvalidColor { token, .. }
+            | AnsiParseError::TooManyColors { token, .. }
+            | AnsiParseError::MissingColor { token, .. } => token,
+        }
+    }
+
+    /// Gets the byte offset within the input of the token that caused this error.
+    #[must_use]
+    pub fn position(&self) -> usize {
+        match self {
+            AnsiParseError::UnknownToken { position, .. }
+            | AnsiParseError::InvalidColor { position, .. }
+            | AnsiParseError::TooManyColors { position, .. }
+            | AnsiParseError::MissingColor { position, .. } => *position,
+        }
+    }
+}
+
+impl std::fmt::Display for AnsiParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnsiParseError::UnknownToken { token, position } => write!(
+                f,
+                "Unknown attribute or color `{token}` at position {position}"
+            ),
+            AnsiParseError::InvalidColor { token, position } => {
+                write!(f, "Invalid color `{token}` at position {position}")
+            }
+            AnsiParseError::TooManyColors { token, position } => write!(
+                f,
+                "Unexpected color `{token}` at position {position}, foreground and background are already set"
+            ),
+            AnsiParseError::MissingColor { token, position } => {
+                write!(f, "Expected a color after `{token}` at position {position}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for AnsiParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn ansi_parse_error() {
+        let err = AnsiParseError::UnknownToken {
+            token: "wobbly".to_string(),
+            position: 5,
+        };
+        assert_eq!(err.token(), "wobbly");
+        assert_eq!(err.position(), 5);
+        assert_eq!(
+            err.to_string(),
+            "Unknown attribute or color `wobbly` at position 5"
+        );
+        assert_eq!(
+            AnsiParseError::MissingColor {
+                token: "on".to_string(),
+                position: 9
+            }
+            .to_string(),
+            "Expected a color after `on` at position 9"
+        );
+    }
+}
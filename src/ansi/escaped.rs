@@ -0,0 +1,72 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::fmt::Write;
+
+use crate::Ansi;
+
+/// Makes escape sequences in `input` visible by replacing `ESC` with `\e`, `BEL` with `\a`
+/// and any other control character (except newlines and tabs) with `\xNN`.
+///
+/// This is mostly useful for logs and test failures, where the raw escape bytes are invisible.
+///
+/// ```
+/// # use ansirs::escape_ansi;
+/// assert_eq!(
+///     escape_ansi("\x1b[1mbold\x1b[0m\n\x1b]0;title\x07"),
+///     "\\e[1mbold\\e[0m\n\\e]0;title\\a"
+/// );
+/// ```
+#[must_use]
+pub fn escape_ansi(input: &str) -> String {
+    let mut out = String::with_capacity(input.len() + input.len() / 4);
+    for c in input.chars() {
+        match c {
+            '\x1b' => out.push_str("\\e"),
+            '\x07' => out.push_str("\\a"),
+            '\n' | '\t' => out.push(c),
+            c if c.is_ascii_control() => {
+                let _ = write!(out, "\\x{:02X}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+impl Ansi {
+    /// Gets the escape sequence for this style in a visible form, e.g. `\e[1;38;2;255;0;0m`.
+    /// This is the same as formatting with the alternate flag (`{:#}`).
+    ///
+    /// ```
+    /// # use ansirs::Ansi;
+    /// let style = Ansi::new().bold().fg((255, 0, 0));
+    /// assert_eq!(style.escaped(), "\\e[1;38;2;255;0;0m");
+    /// assert_eq!(format!("{style:#}"), style.escaped());
+    /// ```
+    #[must_use]
+    pub fn escaped(&self) -> String {
+        escape_ansi(self.encode().as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn escaping() {
+        assert_eq!(escape_ansi(""), "");
+        assert_eq!(escape_ansi("plain ü\ttext\n"), "plain ü\ttext\n");
+        assert_eq!(escape_ansi("\r\x08\x7f"), "\\x0D\\x08\\x7F");
+        assert_eq!(Ansi::new().escaped(), "");
+        assert_eq!(
+            format!("{:#}", Ansi::from_bg((1, 2, 3)).dim()),
+            "\\e[2;48;2;1;2;3m"
+        );
+    }
+}
@@ -50,6 +50,8 @@ impl AnsiFlags {
     pub const REVERSE: Self = Self { bits: (1 << 4) };
     /// Striken text.
     pub const STRIKE: Self = Self { bits: (1 << 5) };
+    /// Dimmed / faint text.
+    pub const DIM: Self = Self { bits: (1 << 6) };
 
     /// Returns an empty set of flags.
     #[inline]
@@ -68,7 +70,8 @@ impl AnsiFlags {
                 | <Self as BitFlags>::ITALIC
                 | <Self as BitFlags>::BLINK
                 | <Self as BitFlags>::REVERSE
-                | <Self as BitFlags>::STRIKE,
+                | <Self as BitFlags>::STRIKE
+                | <Self as BitFlags>::DIM,
         }
     }
 
@@ -438,6 +441,13 @@ impl std::fmt::Debug for AnsiFlags {
             first = false;
             f.write_str("STRIKE")?;
         }
+        if <Self as BoolFlags>::DIM(self) {
+            if !first {
+                f.write_str(" | ")?;
+            }
+            first = false;
+            f.write_str("DIM")?;
+        }
         let extra_bits = self.bits & !Self::all().bits();
         if extra_bits != 0 {
             if !first {
@@ -481,6 +491,7 @@ trait BitFlags {
     const BLINK: u8 = 0;
     const REVERSE: u8 = 0;
     const STRIKE: u8 = 0;
+    const DIM: u8 = 0;
 }
 impl BitFlags for AnsiFlags {
     const BOLD: u8 = Self::BOLD.bits;
@@ -489,6 +500,7 @@ impl BitFlags for AnsiFlags {
     const BLINK: u8 = Self::BLINK.bits;
     const REVERSE: u8 = Self::REVERSE.bits;
     const STRIKE: u8 = Self::STRIKE.bits;
+    const DIM: u8 = Self::DIM.bits;
 }
 
 #[allow(non_snake_case)]
@@ -517,6 +529,10 @@ trait BoolFlags {
     fn STRIKE(&self) -> bool {
         false
     }
+    #[inline]
+    fn DIM(&self) -> bool {
+        false
+    }
 }
 #[allow(non_snake_case)]
 impl BoolFlags for AnsiFlags {
@@ -574,6 +590,15 @@ impl BoolFlags for AnsiFlags {
             self.bits & Self::STRIKE.bits == Self::STRIKE.bits
         }
     }
+    #[allow(deprecated)]
+    #[inline]
+    fn DIM(&self) -> bool {
+        if Self::DIM.bits == 0 && self.bits != 0 {
+            false
+        } else {
+            self.bits & Self::DIM.bits == Self::DIM.bits
+        }
+    }
 }
 
 #[cfg(test)]
@@ -591,18 +616,19 @@ mod tests {
                 | AnsiFlags::BLINK
                 | AnsiFlags::REVERSE
                 | AnsiFlags::STRIKE
+                | AnsiFlags::DIM
         );
         assert!(AnsiFlags::is_all(&AnsiFlags::all()));
         assert_eq!(
             format!("{:?}", AnsiFlags::all()),
-            "BOLD | UNDERLINE | ITALIC | BLINK | REVERSE | STRIKE"
+            "BOLD | UNDERLINE | ITALIC | BLINK | REVERSE | STRIKE | DIM"
         );
         assert!(!AnsiFlags::is_empty(&AnsiFlags::all()));
         assert!(AnsiFlags::all().contains(AnsiFlags::UNDERLINE));
         assert_eq!(AnsiFlags::from_bits(0x0), Some(AnsiFlags::empty()));
         assert_eq!(AnsiFlags::from_bits(0x1), Some(AnsiFlags::BOLD));
-        assert_eq!(AnsiFlags::from_bits(0x64), None);
-        assert_eq!(AnsiFlags::from_bits_truncate(200), AnsiFlags::BLINK);
+        assert_eq!(AnsiFlags::from_bits(0xA4), None);
+        assert_eq!(AnsiFlags::from_bits_truncate(136), AnsiFlags::BLINK);
         unsafe {
             assert_eq!(AnsiFlags::from_bits_unchecked(1), AnsiFlags::BOLD);
         }
@@ -702,14 +728,15 @@ mod tests {
         assert!(!AnsiFlags::ITALIC.STRIKE());
         assert!(!AnsiFlags::ITALIC.UNDERLINE());
         assert!(!AnsiFlags::ITALIC.REVERSE());
+        assert!(!AnsiFlags::ITALIC.DIM());
         assert!(AnsiFlags::ITALIC.ITALIC());
     }
 
     #[test]
     fn format() {
-        assert_eq!(format!("{:02x}", AnsiFlags::all()), "3f");
-        assert_eq!(format!("{:02X}", AnsiFlags::all()), "3F");
-        assert_eq!(format!("{:02o}", AnsiFlags::all()), "77");
-        assert_eq!(format!("{:02b}", AnsiFlags::all()), "111111");
+        assert_eq!(format!("{:02x}", AnsiFlags::all()), "7f");
+        assert_eq!(format!("{:02X}", AnsiFlags::all()), "7F");
+        assert_eq!(format!("{:02o}", AnsiFlags::all()), "177");
+        assert_eq!(format!("{:02b}", AnsiFlags::all()), "1111111");
     }
 }
@@ -0,0 +1,182 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::fmt::{self, Write};
+
+/// A terminal hyperlink (`OSC 8`), which makes the text it surrounds clickable in
+/// supporting terminals.
+///
+/// Displaying a [`Hyperlink`] writes the sequence that starts the link, [`Hyperlink::CLOSE`]
+/// ends it. See [`Span::with_link`](crate::Span::with_link) for attaching a link to text.
+///
+/// ## Example
+/// ```
+/// # use ansirs::Hyperlink;
+/// let link = Hyperlink::new("https://example.com/a b").with_id("docs");
+/// assert_eq!(
+///     format!("{link}docs{}", Hyperlink::CLOSE),
+///     "\x1b]8;id=docs;https://example.com/a%20b\x1b\\docs\x1b]8;;\x1b\\"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Hyperlink {
+    uri: String,
+    id: Option<String>,
+}
+
+/// What an `OSC 8` sequence does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum LinkAction {
+    /// Starts a link.
+    Open(Hyperlink),
+    /// Ends the current link.
+    Close,
+}
+
+/// Writes `value`, percent-encoding anything that is not allowed in an `OSC 8` sequence,
+/// i.e. bytes outside of printable ASCII plus any of `extra`.
+fn write_escaped(f: &mut impl Write, value: &str, extra: &[u8]) -> fmt::Result {
+    for byte in value.bytes() {
+        if (0x21..=0x7e).contains(&byte) && !extra.contains(&byte) {
+            f.write_char(char::from(byte))?;
+        } else {
+            write!(f, "%{byte:02X}")?;
+        }
+    }
+    Ok(())
+}
+
+impl Hyperlink {
+    /// The sequence that ends a hyperlink.
+    pub const CLOSE: &'static str = "\x1b]8;;\x1b\\";
+
+    /// Creates a new link to `uri`.
+    #[must_use]
+    pub fn new(uri: impl Into<String>) -> Self {
+        Self {
+            uri: uri.into(),
+            id: None,
+        }
+    }
+
+    /// Builder function to set the link's `id`. Terminals highlight all text with the same
+    /// `id` and URI together, which is useful when a link is split over multiple lines.
+    #[must_use]
+    pub fn with_id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Gets the URI this link points to.
+    #[must_use]
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    /// Gets the scheme of the URI, e.g. `https`, or `None` if it doesn't start with a valid
+    /// one.
+    ///
+    /// ```
+    /// # use ansirs::Hyperlink;
+    /// assert_eq!(Hyperlink::new("HTTPS://example.com").scheme(), Some("HTTPS"));
+    /// assert_eq!(Hyperlink::new("/relative/path").scheme(), None);
+    /// assert_eq!(Hyperlink::new(" javascript:alert(1)").scheme(), None);
+    /// ```
+    #[must_use]
+    pub fn scheme(&self) -> Option<&str> {
+        let (scheme, _) = self.uri.split_once(':')?;
+        let mut chars = scheme.chars();
+        let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+            && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+        valid.then_some(scheme)
+    }
+
+    /// Gets the link's `id`, if any.
+    #[must_use]
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// Parses the data of an `OSC` sequence (what is between `ESC ]` and the terminator),
+    /// returning `None` if it is not an `OSC 8` sequence.
+    pub(crate) fn parse_osc(data: &str) -> Option<LinkAction> {
+        let rest = data.strip_prefix("8;")?;
+        let (params, uri) = rest.split_once(';')?;
+        if uri.is_empty() {
+            return Some(LinkAction::Close);
+        }
+
+        let id = params
+            .split(':')
+            .find_map(|param| param.strip_prefix("id="))
+            .filter(|id| !id.is_empty())
+            .map(str::to_string);
+        Some(LinkAction::Open(Self {
+            uri: uri.to_string(),
+            id,
+        }))
+    }
+}
+
+impl From<&str> for Hyperlink {
+    fn from(uri: &str) -> Self {
+        Self::new(uri)
+    }
+}
+
+impl From<String> for Hyperlink {
+    fn from(uri: String) -> Self {
+        Self::new(uri)
+    }
+}
+
+impl fmt::Display for Hyperlink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("\x1b]8;")?;
+        if let Some(id) = &self.id {
+            f.write_str("id=")?;
+            write_escaped(f, id, b":;")?;
+        }
+        f.write_char(';')?;
+        write_escaped(f, &self.uri, b"")?;
+        f.write_str("\x1b\\")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn escaping() {
+        assert_eq!(
+            Hyperlink::new("file:///tmp/ü x\x1b").to_string(),
+            "\x1b]8;;file:///tmp/%C3%BC%20x%1B\x1b\\"
+        );
+        assert_eq!(
+            Hyperlink::new("https://a.b/?q=1;2")
+                .with_id("a:b;c")
+                .to_string(),
+            "\x1b]8;id=a%3Ab%3Bc;https://a.b/?q=1;2\x1b\\"
+        );
+    }
+
+    #[test]
+    fn parsing() {
+        assert_eq!(Hyperlink::parse_osc("0;title"), None);
+        assert_eq!(Hyperlink::parse_osc("8;;"), Some(LinkAction::Close));
+        assert_eq!(
+            Hyperlink::parse_osc("8;;https://x.y/a;b"),
+            Some(LinkAction::Open(Hyperlink::new("https://x.y/a;b")))
+        );
+        assert_eq!(
+            Hyperlink::parse_osc("8;foo=bar:id=7;file:///x"),
+            Some(LinkAction::Open(Hyperlink::new("file:///x").with_id("7")))
+        );
+    }
+}
@@ -7,9 +7,30 @@
 // Private module so who cares
 #[allow(clippy::module_inception)]
 mod ansi;
+mod cache;
+mod encode;
+mod error;
+mod escaped;
 mod flags;
+mod hyperlink;
+mod normalize;
+mod packed;
+mod sgr;
+mod spec;
+mod tokens;
 mod traits;
+mod translucent;
 
 pub use ansi::Ansi;
+pub use cache::AnsiCache;
+pub use encode::AnsiBytes;
+pub use error::AnsiParseError;
+pub use escaped::escape_ansi;
 pub use flags::AnsiFlags;
+pub use hyperlink::Hyperlink;
+pub(crate) use hyperlink::LinkAction;
+pub use normalize::{normalize_ansi, semantically_eq};
+pub use packed::PackedAnsi;
+pub use tokens::{strip_ansi, tokenize, Csi, Token, Tokens};
 pub use traits::*;
+pub use translucent::TranslucentAnsi;
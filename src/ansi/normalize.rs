@@ -0,0 +1,269 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::{tokenize, Ansi, AnsiFlags, Color, Palette, Token};
+
+/// Flags and the SGR parameters that turn them on, in emission order.
+const FLAG_ON: [(AnsiFlags, &str); 7] = [
+    (AnsiFlags::BOLD, "1"),
+    (AnsiFlags::DIM, "2"),
+    (AnsiFlags::ITALIC, "3"),
+    (AnsiFlags::UNDERLINE, "4"),
+    (AnsiFlags::BLINK, "5"),
+    (AnsiFlags::REVERSE, "7"),
+    (AnsiFlags::STRIKE, "9"),
+];
+
+/// Flags and the SGR parameters that turn them off. Bold and dim share `22`.
+const FLAG_OFF: [(AnsiFlags, &str); 5] = [
+    (AnsiFlags::ITALIC, "23"),
+    (AnsiFlags::UNDERLINE, "24"),
+    (AnsiFlags::BLINK, "25"),
+    (AnsiFlags::REVERSE, "27"),
+    (AnsiFlags::STRIKE, "29"),
+];
+
+fn color_param(prefix: &str, color: Color) -> String {
+    format!("{prefix};2;{};{};{}", color.r(), color.g(), color.b())
+}
+
+impl Ansi {
+    /// Gets the shortest escape sequence that changes the terminal from this style to `next`.
+    ///
+    /// This is either a list of individual changes (e.g. `22` to turn off bold, `39` to reset
+    /// the foreground) or a full reset followed by `next`, whichever is shorter. An empty
+    /// string is returned if the styles are the same.
+    ///
+    /// ```
+    /// # use ansirs::Ansi;
+    /// let red = Ansi::from_fg((255, 0, 0));
+    /// assert_eq!(red.transition_to(&red.bold()), "\x1b[1m");
+    /// assert_eq!(red.bold().transition_to(&red), "\x1b[22m");
+    /// assert_eq!(red.transition_to(&Ansi::new()), "\x1b[0m");
+    /// assert_eq!(red.transition_to(&red), "");
+    /// ```
+    #[must_use]
+    pub fn transition_to(&self, next: &Ansi) -> String {
+        if self == next {
+            return String::new();
+        }
+        if next.is_default() {
+            return Ansi::reset().to_string();
+        }
+
+        let (from, to) = (self.flags(), next.flags());
+        let mut removed = from.difference(to);
+        let mut added = to.difference(from);
+        let mut diff = Vec::new();
+
+        // `22` turns off both bold and dim, so whichever should stay on is turned on again.
+        if removed.intersects(AnsiFlags::BOLD | AnsiFlags::DIM) {
+            diff.push("22".to_string());
+            removed.remove(AnsiFlags::BOLD | AnsiFlags::DIM);
+            added.insert(to.intersection(AnsiFlags::BOLD | AnsiFlags::DIM));
+        }
+        diff.extend(
+            FLAG_OFF
+                .iter()
+                .filter(|(flag, _)| removed.contains(*flag))
+                .map(|(_, param)| (*param).to_string()),
+        );
+        diff.extend(
+            FLAG_ON
+                .iter()
+                .filter(|(flag, _)| added.contains(*flag))
+                .map(|(_, param)| (*param).to_string()),
+        );
+        if self.foreground() != next.foreground() {
+            diff.push(
+                next.foreground()
+                    .map_or_else(|| "39".to_string(), |c| color_param("38", c)),
+            );
+        }
+        if self.background() != next.background() {
+            diff.push(
+                next.background()
+                    .map_or_else(|| "49".to_string(), |c| color_param("48", c)),
+            );
+        }
+
+        let diff = diff.join(";");
+        let encoded = next.encode();
+        // Strip the `ESC [` and `m` from the full sequence.
+        let full = format!("0;{}", &encoded.as_str()[2..encoded.len() - 1]);
+
+        if diff.len() <= full.len() {
+            format!("\x1b[{diff}m")
+        } else {
+            format!("\x1b[{full}m")
+        }
+    }
+}
+
+/// Rewrites `input` into a canonical, minimal form that looks exactly the same in a terminal.
+///
+/// SGR sequences are merged and only emitted right before they take effect, using the
+/// shortest transition between styles (see [`Ansi::transition_to`]). Redundant and repeated
+/// sequences are dropped, and the output always ends with the default style (a reset is
+/// added if the input leaves a style active). Indexed colors are resolved using the default
+/// [`Palette`]. All other escape sequences are kept as is.
+///
+/// ```
+/// # use ansirs::normalize_ansi;
+/// assert_eq!(
+///     normalize_ansi("\x1b[0m\x1b[4;1mA\x1b[1m\x1b[1;4mB\x1b[22;24m\x1b[0mC"),
+///     "\x1b[1;4mAB\x1b[0mC"
+/// );
+/// ```
+#[must_use]
+pub fn normalize_ansi(input: &str) -> String {
+    let palette = Palette::default();
+    let mut out = String::with_capacity(input.len());
+    // The style the input has asked for so far, and the style actually emitted.
+    let mut wanted = Ansi::new();
+    let mut emitted = Ansi::new();
+
+    let mut tokens = tokenize(input);
+    loop {
+        let before = tokens.remainder();
+        let Some(token) = tokens.next() else {
+            break;
+        };
+        let raw = &before[..before.len() - tokens.remainder().len()];
+
+        if let Token::Csi(csi) = token {
+            if csi.is_sgr() {
+                wanted = wanted.apply_sgr(csi.params(), &palette);
+                continue;
+            }
+        }
+
+        out.push_str(&emitted.transition_to(&wanted));
+        emitted = wanted;
+        out.push_str(raw);
+    }
+
+    out.push_str(&emitted.transition_to(&Ansi::new()));
+    out
+}
+
+/// Returns `true` if `a` and `b` look exactly the same in a terminal, i.e. if they have the
+/// same [normalized](normalize_ansi) form.
+///
+/// ```
+/// # use ansirs::semantically_eq;
+/// assert!(semantically_eq("\x1b[1;4mhi\x1b[0m", "\x1b[4m\x1b[1mh\x1b[1mi\x1b[m"));
+/// assert!(!semantically_eq("\x1b[1mhi", "hi"));
+/// ```
+#[must_use]
+pub fn semantically_eq(a: &str, b: &str) -> bool {
+    a == b || normalize_ansi(a) == normalize_ansi(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn transitions() {
+        let base = Ansi::new().bold().dim().fg((1, 2, 3));
+        assert_eq!(
+            base.transition_to(&Ansi::new().dim().fg((1, 2, 3))),
+            "\x1b[22;2m"
+        );
+        assert_eq!(
+            Ansi::new().italic().transition_to(&Ansi::new().underline()),
+            "\x1b[0;4m"
+        );
+        assert_eq!(
+            Ansi::from_fg((1, 2, 3)).transition_to(&Ansi::from_bg((1, 2, 3))),
+            "\x1b[0;48;2;1;2;3m"
+        );
+        assert_eq!(
+            Ansi::from_fg((1, 2, 3))
+                .bold()
+                .transition_to(&Ansi::from_fg((1, 2, 3)).bg((4, 5, 6))),
+            "\x1b[22;48;2;4;5;6m"
+        );
+        assert_eq!(
+            Ansi::new()
+                .with_flags(AnsiFlags::all())
+                .transition_to(&Ansi::new().strike()),
+            "\x1b[0;9m"
+        );
+        assert_eq!(Ansi::new().transition_to(&Ansi::new().reverse()), "\x1b[7m");
+        assert_eq!(
+            Ansi::new()
+                .italic()
+                .bold()
+                .transition_to(&Ansi::new().bold()),
+            "\x1b[23m"
+        );
+    }
+
+    #[test]
+    fn transitions_are_correct() {
+        let palette = Palette::default();
+        let styles = [
+            Ansi::new(),
+            Ansi::new().bold(),
+            Ansi::new().dim().italic(),
+            Ansi::new().bold().dim().underline().fg((9, 9, 9)),
+            Ansi::from_fg((1, 2, 3)),
+            Ansi::from_bg((4, 5, 6)).strike().blink(),
+            Ansi::new()
+                .with_flags(AnsiFlags::all())
+                .fg((7, 8, 9))
+                .bg((0, 0, 0)),
+        ];
+
+        for from in styles {
+            for to in styles {
+                let seq = from.transition_to(&to);
+                let applied = if seq.is_empty() {
+                    from
+                } else {
+                    from.apply_sgr(&seq[2..seq.len() - 1], &palette)
+                };
+                assert_eq!(applied, to, "{from:?} -> {to:?} via {seq:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn normalization() {
+        assert_eq!(normalize_ansi(""), "");
+        assert_eq!(normalize_ansi("plain"), "plain");
+        assert_eq!(normalize_ansi("\x1b[0m\x1b[1m\x1b[0m"), "");
+        assert_eq!(
+            normalize_ansi("\x1b[1mleft open"),
+            "\x1b[1mleft open\x1b[0m"
+        );
+        assert_eq!(
+            normalize_ansi("\x1b[31mred\x1b[0m\x1b[31m again"),
+            "\x1b[38;2;128;0;0mred again\x1b[0m"
+        );
+        // Other sequences are kept where they were.
+        assert_eq!(
+            normalize_ansi("\x1b[1m\x1b[2Kx\x1b]0;t\x07\x1b[0m"),
+            "\x1b[1m\x1b[2Kx\x1b]0;t\x07\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn idempotent() {
+        let inputs = [
+            "\x1b[1;31mError:\x1b[0m something \x1b[4;1mbad\x1b[24m happened\n",
+            "\x1b[7m\x1b[38;5;208mx\x1b[27;39;49my\x1b[m",
+        ];
+        for input in inputs {
+            let once = normalize_ansi(input);
+            assert_eq!(normalize_ansi(&once), once);
+            assert!(semantically_eq(input, &once));
+        }
+    }
+}
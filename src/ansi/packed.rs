@@ -0,0 +1,198 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::{Ansi, AnsiFlags, Color};
+
+/// A compact, `u64`-backed encoding of an [`Ansi`].
+///
+/// [`Ansi`] itself is two `Option<Color>`s and an [`AnsiFlags`], which is cheap but not
+/// *that* cheap to hash and compare when it is used as a key millions of times. A
+/// [`PackedAnsi`] squeezes the whole style into a single integer so hashing and equality
+/// are a single word operation.
+///
+/// Bit layout (least significant first):
+/// - `0..24`  foreground RGB (`0xRRGGBB`)
+/// - `24`     foreground present
+/// - `25..49` background RGB (`0xRRGGBB`)
+/// - `49`     background present
+/// - `56..64` [`AnsiFlags`] bits
+///
+/// ## Example
+/// ```
+/// # use ansirs::{Ansi, PackedAnsi};
+/// let style = Ansi::new().fg((255, 0, 0)).bold();
+/// let packed = style.pack();
+/// assert_eq!(packed.unpack(), style);
+/// assert_eq!(PackedAnsi::from_bits(packed.bits()), Some(packed));
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PackedAnsi(u64);
+
+impl PackedAnsi {
+    const RGB_MASK: u64 = 0x00FF_FFFF;
+    const FG_SHIFT: u32 = 0;
+    const FG_PRESENT: u64 = 1 << 24;
+    const BG_SHIFT: u32 = 25;
+    const BG_PRESENT: u64 = 1 << 49;
+    const FLAGS_SHIFT: u32 = 56;
+    const USED_BITS: u64 = (Self::RGB_MASK << Self::FG_SHIFT)
+        | Self::FG_PRESENT
+        | (Self::RGB_MASK << Self::BG_SHIFT)
+        | Self::BG_PRESENT
+        | (0xFF << Self::FLAGS_SHIFT);
+
+    /// Packs the given [`Ansi`].
+    #[must_use]
+    pub const fn pack(ansi: Ansi) -> Self {
+        let mut bits = (ansi.flags().bits() as u64) << Self::FLAGS_SHIFT;
+
+        if let Some(fg) = ansi.foreground() {
+            bits |= Self::FG_PRESENT | (Self::color_bits(fg) << Self::FG_SHIFT);
+        }
+
+        if let Some(bg) = ansi.background() {
+            bits |= Self::BG_PRESENT | (Self::color_bits(bg) << Self::BG_SHIFT);
+        }
+
+        Self(bits)
+    }
+
+    /// Unpacks this value back into an [`Ansi`].
+    #[must_use]
+    pub const fn unpack(self) -> Ansi {
+        let fg = if self.0 & Self::FG_PRESENT == 0 {
+            None
+        } else {
+            Some(Self::bits_color(self.0 >> Self::FG_SHIFT))
+        };
+        let bg = if self.0 & Self::BG_PRESENT == 0 {
+            None
+        } else {
+            Some(Self::bits_color(self.0 >> Self::BG_SHIFT))
+        };
+        #[allow(clippy::cast_possible_truncation)]
+        let flags = AnsiFlags::from_bits_truncate((self.0 >> Self::FLAGS_SHIFT) as u8);
+
+        Ansi::from_parts(fg, bg, flags)
+    }
+
+    /// Returns the raw packed value.
+    #[must_use]
+    pub const fn bits(self) -> u64 {
+        self.0
+    }
+
+    /// Creates a [`PackedAnsi`] from a raw value previously obtained from [`PackedAnsi::bits`].
+    ///
+    /// Returns `None` if `bits` has any unused bits set, if it contains unknown flags, or
+    /// if it stores color data for a color that is marked as absent.
+    #[must_use]
+    pub const fn from_bits(bits: u64) -> Option<Self> {
+        if bits & !Self::USED_BITS != 0 {
+            return None;
+        }
+        #[allow(clippy::cast_possible_truncation)]
+        let flags = (bits >> Self::FLAGS_SHIFT) as u8;
+        if AnsiFlags::from_bits(flags).is_none() {
+            return None;
+        }
+        if bits & Self::FG_PRESENT == 0 && (bits >> Self::FG_SHIFT) & Self::RGB_MASK != 0 {
+            return None;
+        }
+        if bits & Self::BG_PRESENT == 0 && (bits >> Self::BG_SHIFT) & Self::RGB_MASK != 0 {
+            return None;
+        }
+
+        Some(Self(bits))
+    }
+
+    /// Returns `true` if the packed style has no styling.
+    #[must_use]
+    pub const fn is_default(self) -> bool {
+        self.0 == 0
+    }
+
+    const fn color_bits(color: Color) -> u64 {
+        ((color.r() as u64) << 16) | ((color.g() as u64) << 8) | (color.b() as u64)
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    const fn bits_color(bits: u64) -> Color {
+        Color::from_rgb((bits >> 16) as u8, (bits >> 8) as u8, bits as u8)
+    }
+}
+
+impl From<Ansi> for PackedAnsi {
+    fn from(ansi: Ansi) -> Self {
+        Self::pack(ansi)
+    }
+}
+
+impl From<&Ansi> for PackedAnsi {
+    fn from(ansi: &Ansi) -> Self {
+        Self::pack(*ansi)
+    }
+}
+
+impl From<PackedAnsi> for Ansi {
+    fn from(packed: PackedAnsi) -> Self {
+        packed.unpack()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::{assert_eq, assert_ne};
+
+    #[test]
+    fn round_trip() {
+        let styles = [
+            Ansi::new(),
+            Ansi::new().bold(),
+            Ansi::from_fg((0, 0, 0)),
+            Ansi::from_bg((0, 0, 0)),
+            Ansi::from_fg((255, 255, 255))
+                .bg((1, 2, 3))
+                .italic()
+                .strike(),
+            Ansi::new()
+                .fg((50, 250, 150))
+                .bg((25, 25, 25))
+                .bold()
+                .blink()
+                .italic()
+                .reverse()
+                .strike()
+                .underline(),
+        ];
+
+        for style in styles {
+            let packed = style.pack();
+            assert_eq!(packed.unpack(), style);
+            assert_eq!(PackedAnsi::from_bits(packed.bits()), Some(packed));
+        }
+    }
+
+    #[test]
+    fn black_is_not_absent() {
+        assert_ne!(Ansi::from_fg((0, 0, 0)).pack(), Ansi::new().pack());
+        assert_ne!(
+            Ansi::from_fg((0, 0, 0)).pack(),
+            Ansi::from_bg((0, 0, 0)).pack()
+        );
+        assert!(Ansi::new().pack().is_default());
+    }
+
+    #[test]
+    fn from_bits_rejects_garbage() {
+        assert_eq!(PackedAnsi::from_bits(1 << 50), None);
+        assert_eq!(PackedAnsi::from_bits(0xFF << 56), None);
+        // Color data without the "present" bit.
+        assert_eq!(PackedAnsi::from_bits(0x00FF_0000), None);
+    }
+}
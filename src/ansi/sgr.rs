@@ -0,0 +1,219 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::{Ansi, AnsiFlags, Color, Palette};
+
+/// Parses an extended color (the part after `38`/`48`), given as either `5;n` or `2;r;g;b`.
+/// Returns the color (if valid) and the number of parameters consumed.
+fn extended_color(params: &[Option<u16>], palette: &Palette) -> (Option<Color>, usize) {
+    let byte = |idx: usize| {
+        params
+            .get(idx)
+            .copied()
+            .flatten()
+            .and_then(|n| u8::try_from(n).ok())
+    };
+
+    match params.first().copied().flatten() {
+        Some(5) => (byte(1).map(|idx| palette.get(idx)), 2),
+        Some(2) => {
+            let color = match (byte(1), byte(2), byte(3)) {
+                (Some(r), Some(g), Some(b)) => Some(Color::from_rgb(r, g, b)),
+                _ => None,
+            };
+            (color, 4)
+        }
+        _ => (None, 1),
+    }
+}
+
+impl Ansi {
+    /// Applies the parameters of a Select Graphic Rendition sequence (the `1;31` in
+    /// `ESC[1;31m`) on top of this style, the same way a terminal would.
+    ///
+    /// Indexed colors (`30`-`37`, `90`-`97`, `38;5;n`, etc.) are resolved through `palette`.
+    /// Both the `;` separated (`38;2;r;g;b`) and `:` separated (`38:2::r:g:b`) forms of
+    /// extended colors are supported. Unknown parameters are ignored.
+    ///
+    /// ## Example
+    /// ```
+    /// # use ansirs::{Ansi, Palette};
+    /// let palette = Palette::default();
+    /// let style = Ansi::new().apply_sgr("1;31", &palette);
+    /// assert_eq!(style, Ansi::new().bold().fg((0x80, 0, 0)));
+    ///
+    /// let style = style.apply_sgr("22;48;2;1;2;3", &palette);
+    /// assert_eq!(style, Ansi::new().fg((0x80, 0, 0)).bg((1, 2, 3)));
+    ///
+    /// assert_eq!(style.apply_sgr("0", &palette), Ansi::new());
+    /// ```
+    #[must_use]
+    pub fn apply_sgr(self, params: &str, palette: &Palette) -> Self {
+        let mut fg = self.foreground();
+        let mut bg = self.background();
+        let mut flags = self.flags();
+
+        let groups = params.split(';').collect::<Vec<_>>();
+        let numbers = groups
+            .iter()
+            .map(|g| g.parse::<u16>().ok())
+            .collect::<Vec<_>>();
+
+        let mut i = 0;
+        while i < groups.len() {
+            let group = groups[i];
+            i += 1;
+
+            if group.contains(':') {
+                let sub = group
+                    .split(':')
+                    .map(|s| s.parse::<u16>().ok())
+                    .collect::<Vec<_>>();
+                match sub[0] {
+                    Some(4) if sub.get(1) == Some(&Some(0)) => flags.remove(AnsiFlags::UNDERLINE),
+                    Some(4) => flags.insert(AnsiFlags::UNDERLINE),
+                    Some(code @ (38 | 48)) => {
+                        // Standard form includes a (usually empty) color space id: `38:2::r:g:b`.
+                        let rest = if sub.get(1) == Some(&Some(2)) && sub.len() >= 6 {
+                            [&sub[1..2], &sub[3..]].concat()
+                        } else {
+                            sub[1..].to_vec()
+                        };
+                        if let (Some(color), _) = extended_color(&rest, palette) {
+                            if code == 38 {
+                                fg = Some(color);
+                            } else {
+                                bg = Some(color);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+
+            // An empty parameter is the same as 0.
+            let code = if group.is_empty() {
+                0
+            } else {
+                match numbers[i - 1] {
+                    Some(code) => code,
+                    None => continue,
+                }
+            };
+
+            match code {
+                0 => {
+                    fg = None;
+                    bg = None;
+                    flags = AnsiFlags::empty();
+                }
+                1 => flags.insert(AnsiFlags::BOLD),
+                2 => flags.insert(AnsiFlags::DIM),
+                3 => flags.insert(AnsiFlags::ITALIC),
+                4 | 21 => flags.insert(AnsiFlags::UNDERLINE),
+                5 | 6 => flags.insert(AnsiFlags::BLINK),
+                7 => flags.insert(AnsiFlags::REVERSE),
+                9 => flags.insert(AnsiFlags::STRIKE),
+                22 => flags.remove(AnsiFlags::BOLD | AnsiFlags::DIM),
+                23 => flags.remove(AnsiFlags::ITALIC),
+                24 => flags.remove(AnsiFlags::UNDERLINE),
+                25 => flags.remove(AnsiFlags::BLINK),
+                27 => flags.remove(AnsiFlags::REVERSE),
+                29 => flags.remove(AnsiFlags::STRIKE),
+                #[allow(clippy::cast_possible_truncation)]
+                30..=37 => fg = Some(palette.get((code - 30) as u8)),
+                #[allow(clippy::cast_possible_truncation)]
+                40..=47 => bg = Some(palette.get((code - 40) as u8)),
+                #[allow(clippy::cast_possible_truncation)]
+                90..=97 => fg = Some(palette.get((code - 90 + 8) as u8)),
+                #[allow(clippy::cast_possible_truncation)]
+                100..=107 => bg = Some(palette.get((code - 100 + 8) as u8)),
+                39 => fg = None,
+                49 => bg = None,
+                38 | 48 | 58 => {
+                    let (color, consumed) = extended_color(&numbers[i..], palette);
+                    i += consumed;
+                    match (code, color) {
+                        (38, Some(color)) => fg = Some(color),
+                        (48, Some(color)) => bg = Some(color),
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Self::from_parts(fg, bg, flags)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn sgr(params: &str) -> Ansi {
+        Ansi::new().apply_sgr(params, &Palette::default())
+    }
+
+    #[test]
+    fn round_trips_own_output() {
+        let the_works = Ansi::new()
+            .fg((50, 250, 150))
+            .bg((25, 25, 25))
+            .with_flags(AnsiFlags::all());
+        let rendered = the_works.to_string();
+        let params = &rendered[2..rendered.len() - 1];
+        assert_eq!(sgr(params), the_works);
+    }
+
+    #[test]
+    fn basic_colors() {
+        assert_eq!(sgr("31"), Ansi::from_fg(Color::ansi_256_to_color(1)));
+        assert_eq!(sgr("47"), Ansi::from_bg(Color::ansi_256_to_color(7)));
+        assert_eq!(sgr("91"), Ansi::from_fg(Color::ansi_256_to_color(9)));
+        assert_eq!(sgr("107"), Ansi::from_bg(Color::ansi_256_to_color(15)));
+        assert_eq!(
+            sgr("38;5;208;48;5;17"),
+            Ansi::from_fg(Color::ansi_256_to_color(208)).bg(Color::ansi_256_to_color(17))
+        );
+
+        let mut palette = Palette::default();
+        palette.set(1, Color::from_rgb(1, 2, 3));
+        assert_eq!(
+            Ansi::new().apply_sgr("31", &palette),
+            Ansi::from_fg((1, 2, 3))
+        );
+    }
+
+    #[test]
+    fn resets() {
+        let all = sgr("1;2;3;4;5;7;9;31;41");
+        assert_eq!(all.apply_sgr("", &Palette::default()), Ansi::new());
+        assert_eq!(
+            all.apply_sgr("22;23;24;25;27;29;39;49", &Palette::default()),
+            Ansi::new()
+        );
+    }
+
+    #[test]
+    fn colon_forms() {
+        assert_eq!(sgr("38:2::1:2:3"), Ansi::from_fg((1, 2, 3)));
+        assert_eq!(sgr("38:2:1:2:3"), Ansi::from_fg((1, 2, 3)));
+        assert_eq!(sgr("48:5:16"), Ansi::from_bg((0, 0, 0)));
+        assert_eq!(sgr("4:3"), Ansi::new().underline());
+        assert_eq!(sgr("4;4:0"), Ansi::new());
+    }
+
+    #[test]
+    fn malformed() {
+        assert_eq!(sgr("38;2;1;2"), Ansi::new());
+        assert_eq!(sgr("38;5;300;1"), Ansi::new().bold());
+        assert_eq!(sgr("1;abc;3"), Ansi::new().bold().italic());
+        assert_eq!(sgr("58;2;1;2;3;1"), Ansi::new().bold());
+    }
+}
@@ -0,0 +1,426 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Parsing and formatting of human-readable style specs, in the spirit of git's `color.*`
+//! config values, e.g. `"bold red on blue"` or `"fg=DodgerBlue bg=236 dim"`.
+
+use std::str::FromStr;
+
+use crate::{Ansi, AnsiFlags, AnsiParseError, Color, Colors};
+
+/// Attribute keywords, in the order they are written by [`Ansi::to_spec`]. The first
+/// spelling of each is the canonical one.
+const ATTRIBUTES: [(AnsiFlags, &[&str]); 7] = [
+    (AnsiFlags::BOLD, &["bold"]),
+    (AnsiFlags::DIM, &["dim", "faint"]),
+    (AnsiFlags::ITALIC, &["italic"]),
+    (AnsiFlags::UNDERLINE, &["ul", "underline"]),
+    (AnsiFlags::BLINK, &["blink"]),
+    (AnsiFlags::REVERSE, &["reverse"]),
+    (AnsiFlags::STRIKE, &["strike", "strikethrough"]),
+];
+
+fn attribute(token: &str) -> Option<AnsiFlags> {
+    ATTRIBUTES
+        .iter()
+        .find(|(_, names)| names.iter().any(|name| token.eq_ignore_ascii_case(name)))
+        .map(|(flag, _)| *flag)
+}
+
+/// A color as written in a spec, which may be the terminal's default color.
+#[derive(Clone, Copy)]
+enum SpecColor {
+    Default,
+    Color(Color),
+}
+
+impl SpecColor {
+    fn color(self) -> Option<Color> {
+        match self {
+            SpecColor::Default => None,
+            SpecColor::Color(color) => Some(color),
+        }
+    }
+}
+
+/// Parses a single color token.
+fn spec_color(token: &str) -> Option<SpecColor> {
+    if token.eq_ignore_ascii_case("default") || token.eq_ignore_ascii_case("normal") {
+        return Some(SpecColor::Default);
+    }
+
+    let color = if token.starts_with('#') {
+        Color::from_hex(token).ok()
+    } else if token.bytes().all(|b| b.is_ascii_digit()) {
+        token.parse::<u8>().ok().map(Color::ansi_256_to_color)
+    } else {
+        Colors::from_name_ignore_case(token).map(Colors::into_color)
+    };
+
+    color.map(SpecColor::Color)
+}
+
+/// Splits the input into whitespace separated tokens along with their byte offsets.
+fn tokens(input: &str) -> impl Iterator<Item = (usize, &str)> {
+    input
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|token| !token.is_empty())
+        // `split` only yields subslices of `input`, separators can be several bytes long.
+        .map(move |token| (token.as_ptr() as usize - input.as_ptr() as usize, token))
+}
+
+#[derive(Default)]
+struct SpecBuilder {
+    fg: Option<SpecColor>,
+    bg: Option<SpecColor>,
+    flags: AnsiFlags,
+}
+
+impl SpecBuilder {
+    fn color(token: &str, position: usize) -> Result<SpecColor, AnsiParseError> {
+        spec_color(token).ok_or_else(|| AnsiParseError::InvalidColor {
+            token: token.to_string(),
+            position,
+        })
+    }
+
+    fn positional(
+        &mut self,
+        color: SpecColor,
+        token: &str,
+        position: usize,
+    ) -> Result<(), AnsiParseError> {
+        if self.fg.is_none() {
+            self.fg = Some(color);
+        } else if self.bg.is_none() {
+            self.bg = Some(color);
+        } else {
+            return Err(AnsiParseError::TooManyColors {
+                token: token.to_string(),
+                position,
+            });
+        }
+        Ok(())
+    }
+
+    fn build(self) -> Ansi {
+        Ansi::from_parts(
+            self.fg.and_then(SpecColor::color),
+            self.bg.and_then(SpecColor::color),
+            self.flags,
+        )
+    }
+}
+
+impl FromStr for Ansi {
+    type Err = AnsiParseError;
+
+    /// Parses a style spec.
+    ///
+    /// A spec is a list of whitespace (or comma) separated tokens, all case-insensitive:
+    /// - Attributes: `bold`, `dim`, `italic`, `ul` / `underline`, `blink`, `reverse`, `strike`
+    /// - Colors: [`Colors`] names, `#rgb` / `#rrggbb` hex, `0`-`255` (ANSI-256 index),
+    ///   or `default` for the terminal's default color
+    /// - `on <color>`, `fg=<color>` and `bg=<color>` to set a specific layer
+    ///
+    /// As in git, the first bare color is the foreground and the second the background.
+    ///
+    /// ```
+    /// # use ansirs::{Ansi, Colors};
+    /// let a: Ansi = "bold red on blue".parse().unwrap();
+    /// assert_eq!(a, Ansi::new().bold().fg(Colors::Red).bg(Colors::Blue));
+    ///
+    /// let b: Ansi = "italic #ff8800 ul".parse().unwrap();
+    /// assert_eq!(b, Ansi::new().italic().underline().fg((0xff, 0x88, 0x00)));
+    ///
+    /// let c: Ansi = "fg=DodgerBlue bg=236 dim".parse().unwrap();
+    /// assert_eq!(c, Ansi::new().dim().fg(Colors::DodgerBlue).bg((0x30, 0x30, 0x30)));
+    ///
+    /// let err = "bold wobbly".parse::<Ansi>().unwrap_err();
+    /// assert_eq!(err.token(), "wobbly");
+    /// assert_eq!(err.position(), 5);
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut builder = SpecBuilder::default();
+        let mut tokens = tokens(s);
+
+        while let Some((position, token)) = tokens.next() {
+            if let Some(flag) = attribute(token) {
+                builder.flags.insert(flag);
+                continue;
+            }
+
+            if token.eq_ignore_ascii_case("on") {
+                let Some((color_position, color)) = tokens.next() else {
+                    return Err(AnsiParseError::MissingColor {
+                        token: token.to_string(),
+                        position,
+                    });
+                };
+                builder.bg = Some(SpecBuilder::color(color, color_position)?);
+                continue;
+            }
+
+            if let Some((key, value)) = token.split_once('=') {
+                let value_position = position + key.len() + 1;
+                let layer = if key.eq_ignore_ascii_case("fg") {
+                    &mut builder.fg
+                } else if key.eq_ignore_ascii_case("bg") {
+                    &mut builder.bg
+                } else {
+                    return Err(AnsiParseError::UnknownToken {
+                        token: token.to_string(),
+                        position,
+                    });
+                };
+                if value.is_empty() {
+                    return Err(AnsiParseError::MissingColor {
+                        token: token.to_string(),
+                        position,
+                    });
+                }
+                *layer = Some(SpecBuilder::color(value, value_position)?);
+                continue;
+            }
+
+            match spec_color(token) {
+                Some(color) => builder.positional(color, token, position)?,
+                None if token.starts_with('#') || token.bytes().all(|b| b.is_ascii_digit()) => {
+                    return Err(AnsiParseError::InvalidColor {
+                        token: token.to_string(),
+                        position,
+                    });
+                }
+                None => {
+                    return Err(AnsiParseError::UnknownToken {
+                        token: token.to_string(),
+                        position,
+                    });
+                }
+            }
+        }
+
+        Ok(builder.build())
+    }
+}
+
+impl TryFrom<&str> for Ansi {
+    type Error = AnsiParseError;
+
+    /// Attempts to parse the given style spec into an [`Ansi`]. See [`Ansi::from_str`].
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        input.parse()
+    }
+}
+
+fn spec_color_name(color: Color) -> String {
+    Colors::all()
+        .find(|c| c.rgb() == color.rgb())
+        .map_or_else(|| color.as_hex_lower(), |c| c.name().to_lowercase())
+}
+
+impl Ansi {
+    /// Formats this `Ansi` as a human-readable style spec, the inverse of [`Ansi::from_str`].
+    ///
+    /// Colors that exactly match a named [`Colors`] are written by name, anything else is
+    /// written as lowercase hex.
+    ///
+    /// ```
+    /// # use ansirs::{Ansi, Colors};
+    /// let style = Ansi::new().bold().fg(Colors::Red).bg((1, 2, 3));
+    /// assert_eq!(style.to_spec(), "bold red on #010203");
+    /// assert_eq!(style.to_spec().parse::<Ansi>().unwrap(), style);
+    /// ```
+    #[must_use]
+    pub fn to_spec(&self) -> String {
+        let mut parts = ATTRIBUTES
+            .iter()
+            .filter(|(flag, _)| self.flags().contains(*flag))
+            .map(|(_, names)| names[0].to_string())
+            .collect::<Vec<_>>();
+
+        if let Some(fg) = self.foreground() {
+            parts.push(spec_color_name(fg));
+        }
+
+        if let Some(bg) = self.background() {
+            parts.push("on".to_string());
+            parts.push(spec_color_name(bg));
+        }
+
+        parts.join(" ")
+    }
+
+    /// Formats this `Ansi` as a markup-like tag, e.g. `<bold fg=#FF0000>`, which is much
+    /// easier to read in logs and test diffs than the raw escape sequence. The contents of
+    /// the tag are a valid spec (see [`Ansi::from_str`]). The default style is written as `<>`.
+    ///
+    /// ```
+    /// # use ansirs::Ansi;
+    /// let style = Ansi::new().bold().fg((255, 0, 0)).bg((1, 2, 3));
+    /// assert_eq!(style.to_semantic(), "<bold fg=#FF0000 bg=#010203>");
+    /// assert_eq!(Ansi::new().to_semantic(), "<>");
+    /// ```
+    #[must_use]
+    pub fn to_semantic(&self) -> String {
+        let mut parts = ATTRIBUTES
+            .iter()
+            .filter(|(flag, _)| self.flags().contains(*flag))
+            .map(|(_, names)| names[0].to_string())
+            .collect::<Vec<_>>();
+
+        if let Some(fg) = self.foreground() {
+            parts.push(format!("fg={}", fg.as_hex()));
+        }
+
+        if let Some(bg) = self.background() {
+            parts.push(format!("bg={}", bg.as_hex()));
+        }
+
+        format!("<{}>", parts.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn parse(s: &str) -> Result<Ansi, AnsiParseError> {
+        s.parse()
+    }
+
+    #[test]
+    fn attributes() {
+        assert_eq!(parse(""), Ok(Ansi::new()));
+        assert_eq!(parse("   "), Ok(Ansi::new()));
+        assert_eq!(parse("bold"), Ok(Ansi::new().bold()));
+        assert_eq!(parse("BOLD bold"), Ok(Ansi::new().bold()));
+        assert_eq!(
+            parse("bold dim italic ul blink reverse strike"),
+            Ok(Ansi::new().with_flags(AnsiFlags::all()))
+        );
+        assert_eq!(
+            parse("underline, faint, strikethrough"),
+            Ok(Ansi::new().underline().dim().strike())
+        );
+    }
+
+    #[test]
+    fn colors() {
+        assert_eq!(parse("red"), Ok(Ansi::from_fg(Colors::Red)));
+        assert_eq!(
+            parse("red blue"),
+            Ok(Ansi::from_fg(Colors::Red).bg(Colors::Blue))
+        );
+        assert_eq!(parse("on BLUE"), Ok(Ansi::from_bg(Colors::Blue)));
+        assert_eq!(parse("default blue"), Ok(Ansi::from_bg(Colors::Blue)));
+        assert_eq!(parse("#f00"), Ok(Ansi::from_fg((255, 0, 0))));
+        assert_eq!(
+            parse("196 on 16"),
+            Ok(Ansi::from_fg((255, 0, 0)).bg((0, 0, 0)))
+        );
+        assert_eq!(
+            parse("bg=#123456 fg=default"),
+            Ok(Ansi::from_bg((0x12, 0x34, 0x56)))
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            parse("bold wobbly"),
+            Err(AnsiParseError::UnknownToken {
+                token: "wobbly".to_string(),
+                position: 5
+            })
+        );
+        assert_eq!(
+            parse("#12345"),
+            Err(AnsiParseError::InvalidColor {
+                token: "#12345".to_string(),
+                position: 0
+            })
+        );
+        assert_eq!(
+            parse("256"),
+            Err(AnsiParseError::InvalidColor {
+                token: "256".to_string(),
+                position: 0
+            })
+        );
+        assert_eq!(
+            parse("red on"),
+            Err(AnsiParseError::MissingColor {
+                token: "on".to_string(),
+                position: 4
+            })
+        );
+        assert_eq!(
+            parse("fg="),
+            Err(AnsiParseError::MissingColor {
+                token: "fg=".to_string(),
+                position: 0
+            })
+        );
+        assert_eq!(
+            parse("fg=nope"),
+            Err(AnsiParseError::InvalidColor {
+                token: "nope".to_string(),
+                position: 3
+            })
+        );
+        assert_eq!(
+            parse("red blue green"),
+            Err(AnsiParseError::TooManyColors {
+                token: "green".to_string(),
+                position: 9
+            })
+        );
+        assert_eq!(
+            parse("ul=red"),
+            Err(AnsiParseError::UnknownToken {
+                token: "ul=red".to_string(),
+                position: 0
+            })
+        );
+        // Multi-byte whitespace still reports byte offsets into the input.
+        assert_eq!(
+            parse("bold\u{a0}red\u{3000}wobbly"),
+            Err(AnsiParseError::UnknownToken {
+                token: "wobbly".to_string(),
+                position: 12
+            })
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let styles = [
+            Ansi::new(),
+            Ansi::new().bold().underline(),
+            Ansi::from_fg(Colors::DodgerBlue),
+            Ansi::from_bg(Colors::DarkSlateGray).italic(),
+            Ansi::from_fg((1, 2, 3)).bg((250, 251, 252)).dim().strike(),
+            Ansi::new().with_flags(AnsiFlags::all()).fg((0, 0, 0)),
+        ];
+
+        for style in styles {
+            let spec = style.to_spec();
+            assert_eq!(parse(&spec), Ok(style), "failed to round trip `{spec}`");
+
+            let semantic = style.to_semantic();
+            let inner = &semantic[1..semantic.len() - 1];
+            assert_eq!(parse(inner), Ok(style), "failed to round trip `{semantic}`");
+        }
+
+        assert_eq!(
+            Ansi::from_fg(Colors::DodgerBlue).dim().to_spec(),
+            "dim dodgerblue"
+        );
+        assert_eq!(Ansi::from_bg((1, 2, 3)).to_spec(), "on #010203");
+    }
+}
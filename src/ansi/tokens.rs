@@ -0,0 +1,367 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+const ESC: char = '\x1b';
+const BEL: char = '\x07';
+
+/// A single piece of a string containing escape sequences, as produced by [`tokenize`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Token<'a> {
+    /// Plain text. Never contains control characters.
+    Text(&'a str),
+    /// A single C0 control character (other than `ESC`) or `DEL`, e.g. `\n`, `\r`, `\t` or `\x08`.
+    Control(char),
+    /// A Control Sequence Introducer sequence, `ESC [ ...`. This includes SGR (`ESC [ ... m`).
+    Csi(Csi<'a>),
+    /// An Operating System Command, `ESC ] ...` terminated by `BEL` or `ESC \`. Holds the
+    /// data between the introducer and the terminator.
+    Osc(&'a str),
+    /// A Device Control String, `ESC P ...` terminated by `ESC \`. Holds the data between the
+    /// introducer and the terminator.
+    Dcs(&'a str),
+    /// Any other escape sequence, `ESC` followed by a single character.
+    Escape(char),
+    /// A malformed sequence, holding the raw text that was consumed.
+    Invalid(&'a str),
+    /// An escape sequence that was cut off by the end of the input, holding the raw text.
+    Incomplete(&'a str),
+}
+
+/// A parsed Control Sequence Introducer sequence, e.g. `ESC [ 1 ; 31 m` or `ESC [ ? 25 l`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Csi<'a> {
+    params: &'a str,
+    intermediates: &'a str,
+    final_byte: char,
+}
+
+impl<'a> Csi<'a> {
+    /// Gets the private marker (one of `<`, `=`, `>` or `?`) at the start of the parameters, if any.
+    #[must_use]
+    pub fn private_marker(&self) -> Option<char> {
+        self.params
+            .chars()
+            .next()
+            .filter(|c| matches!(c, '<' | '=' | '>' | '?'))
+    }
+
+    /// Gets the raw parameter string, without any private marker.
+    #[must_use]
+    pub fn params(&self) -> &'a str {
+        match self.private_marker() {
+            Some(marker) => &self.params[marker.len_utf8()..],
+            None => self.params,
+        }
+    }
+
+    /// Iterates over the `;` separated numeric parameters. Empty (or unparsable)
+    /// parameters are yielded as `None`, which usually means "use the default".
+    pub fn numeric_params(&self) -> impl Iterator<Item = Option<u16>> + 'a {
+        let params = self.params();
+        let params = if params.is_empty() {
+            None
+        } else {
+            Some(params)
+        };
+        params
+            .into_iter()
+            .flat_map(|p| p.split(';'))
+            .map(|p| p.parse().ok())
+    }
+
+    /// Gets the numeric parameter at `index`, if present and non-empty.
+    #[must_use]
+    pub fn param(&self, index: usize) -> Option<u16> {
+        self.numeric_params().nth(index).flatten()
+    }
+
+    /// Gets the intermediate bytes (e.g. the `$` in `ESC [ ? 2026 $ p`).
+    #[must_use]
+    pub fn intermediates(&self) -> &'a str {
+        self.intermediates
+    }
+
+    /// Gets the final byte, which determines what the sequence does.
+    #[must_use]
+    pub fn final_byte(&self) -> char {
+        self.final_byte
+    }
+
+    /// Returns `true` if this is a Select Graphic Rendition (styling) sequence.
+    #[must_use]
+    pub fn is_sgr(&self) -> bool {
+        self.final_byte == 'm' && self.intermediates.is_empty() && self.private_marker().is_none()
+    }
+}
+
+/// Iterator over the [`Token`]s of a string. Created with [`tokenize`].
+#[derive(Debug, Clone)]
+pub struct Tokens<'a> {
+    input: &'a str,
+}
+
+/// Splits `input` into plain text, control characters and escape sequences.
+///
+/// ## Example
+/// ```
+/// # use ansirs::{tokenize, Token};
+/// let tokens = tokenize("\x1b[1mHi\x1b[0m\n").collect::<Vec<_>>();
+/// assert!(matches!(tokens[0], Token::Csi(csi) if csi.is_sgr() && csi.params() == "1"));
+/// assert_eq!(tokens[1], Token::Text("Hi"));
+/// assert!(matches!(tokens[2], Token::Csi(csi) if csi.params() == "0"));
+/// assert_eq!(tokens[3], Token::Control('\n'));
+/// ```
+#[must_use]
+pub fn tokenize(input: &str) -> Tokens<'_> {
+    Tokens { input }
+}
+
+/// Removes all escape sequences from `input`, keeping text and control characters.
+///
+/// ```
+/// # use ansirs::strip_ansi;
+/// assert_eq!(strip_ansi("\x1b[1;31mError:\x1b[0m oops\n"), "Error: oops\n");
+/// ```
+#[must_use]
+pub fn strip_ansi(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    for token in tokenize(input) {
+        match token {
+            Token::Text(text) => out.push_str(text),
+            Token::Control(c) => out.push(c),
+            _ => {}
+        }
+    }
+    out
+}
+
+fn is_control(c: char) -> bool {
+    c != ESC && (c.is_ascii_control())
+}
+
+impl<'a> Tokens<'a> {
+    /// Gets the part of the input that has not been tokenized yet.
+    #[must_use]
+    pub fn remainder(&self) -> &'a str {
+        self.input
+    }
+
+    fn take(&mut self, len: usize) -> &'a str {
+        let (taken, rest) = self.input.split_at(len);
+        self.input = rest;
+        taken
+    }
+
+    fn csi(&mut self) -> Token<'a> {
+        // Skip "ESC ["
+        let body = &self.input[2..];
+        let params_len = body
+            .find(|c: char| !('\x30'..='\x3f').contains(&c))
+            .unwrap_or(body.len());
+        let after_params = &body[params_len..];
+        let inter_len = after_params
+            .find(|c: char| !('\x20'..='\x2f').contains(&c))
+            .unwrap_or(after_params.len());
+
+        match after_params[inter_len..].chars().next() {
+            None => Token::Incomplete(self.take(self.input.len())),
+            Some(final_byte @ '\x40'..='\x7e') => {
+                let params = &body[..params_len];
+                let intermediates = &after_params[..inter_len];
+                self.take(2 + params_len + inter_len + 1);
+                Token::Csi(Csi {
+                    params,
+                    intermediates,
+                    final_byte,
+                })
+            }
+            // Anything else aborts the sequence, the offending character is left for the next token.
+            Some(_) => Token::Invalid(self.take(2 + params_len + inter_len)),
+        }
+    }
+
+    /// Handles string sequences (OSC and DCS), which run until a terminator.
+    fn string_sequence(&mut self, allow_bel: bool, wrap: fn(&'a str) -> Token<'a>) -> Token<'a> {
+        let body = &self.input[2..];
+        let mut chars = body.char_indices();
+        while let Some((idx, c)) = chars.next() {
+            if c == BEL && allow_bel {
+                let data = &body[..idx];
+                self.take(2 + idx + 1);
+                return wrap(data);
+            }
+            if c == ESC {
+                return match chars.next() {
+                    Some((_, '\\')) => {
+                        let data = &body[..idx];
+                        self.take(2 + idx + 2);
+                        wrap(data)
+                    }
+                    None => Token::Incomplete(self.take(self.input.len())),
+                    // A new escape sequence interrupts the string, which is then discarded.
+                    Some(_) => Token::Invalid(self.take(2 + idx)),
+                };
+            }
+        }
+
+        Token::Incomplete(self.take(self.input.len()))
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut chars = self.input.chars();
+        let first = chars.next()?;
+
+        if is_control(first) {
+            self.take(first.len_utf8());
+            return Some(Token::Control(first));
+        }
+
+        if first != ESC {
+            let len = self
+                .input
+                .find(|c: char| c == ESC || is_control(c))
+                .unwrap_or(self.input.len());
+            return Some(Token::Text(self.take(len)));
+        }
+
+        let token = match chars.next() {
+            None => Token::Incomplete(self.take(1)),
+            Some('[') => self.csi(),
+            Some(']') => self.string_sequence(true, Token::Osc),
+            Some('P') => self.string_sequence(false, Token::Dcs),
+            Some(c) => {
+                self.take(1 + c.len_utf8());
+                Token::Escape(c)
+            }
+        };
+
+        Some(token)
+    }
+}
+
+impl std::iter::FusedIterator for Tokens<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn csi(params: &'static str, intermediates: &'static str, final_byte: char) -> Token<'static> {
+        Token::Csi(Csi {
+            params,
+            intermediates,
+            final_byte,
+        })
+    }
+
+    #[test]
+    fn text_and_controls() {
+        let tokens = tokenize("ab\r\ncd\tü\x7f").collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Text("ab"),
+                Token::Control('\r'),
+                Token::Control('\n'),
+                Token::Text("cd"),
+                Token::Control('\t'),
+                Token::Text("ü"),
+                Token::Control('\x7f'),
+            ]
+        );
+        assert_eq!(tokenize("").next(), None);
+    }
+
+    #[test]
+    fn csi_sequences() {
+        let tokens =
+            tokenize("\x1b[1;38;2;1;2;3mX\x1b[m\x1b[?25l\x1b[?2026$p\x1b[2K").collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            vec![
+                csi("1;38;2;1;2;3", "", 'm'),
+                Token::Text("X"),
+                csi("", "", 'm'),
+                csi("?25", "", 'l'),
+                csi("?2026", "$", 'p'),
+                csi("2", "", 'K'),
+            ]
+        );
+
+        let Token::Csi(mode) = tokens[3] else {
+            unreachable!()
+        };
+        assert_eq!(mode.private_marker(), Some('?'));
+        assert_eq!(mode.params(), "25");
+        assert_eq!(mode.param(0), Some(25));
+        assert!(!mode.is_sgr());
+
+        let Token::Csi(sgr) = tokens[0] else {
+            unreachable!()
+        };
+        assert!(sgr.is_sgr());
+        assert_eq!(sgr.numeric_params().count(), 6);
+        assert_eq!(sgr.param(5), Some(3));
+        assert_eq!(sgr.param(6), None);
+
+        let Token::Csi(empty) = tokens[2] else {
+            unreachable!()
+        };
+        assert_eq!(empty.numeric_params().count(), 0);
+    }
+
+    #[test]
+    fn string_sequences() {
+        let tokens = tokenize("\x1b]0;title\x07\x1b]8;;http://x\x1b\\\x1bP>|xterm\x1b\\\x1b7")
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Osc("0;title"),
+                Token::Osc("8;;http://x"),
+                Token::Dcs(">|xterm"),
+                Token::Escape('7'),
+            ]
+        );
+    }
+
+    #[test]
+    fn incomplete_and_invalid() {
+        assert_eq!(
+            tokenize("ab\x1b[1;3").collect::<Vec<_>>(),
+            vec![Token::Text("ab"), Token::Incomplete("\x1b[1;3")]
+        );
+        assert_eq!(
+            tokenize("\x1b]0;ti").collect::<Vec<_>>(),
+            vec![Token::Incomplete("\x1b]0;ti")]
+        );
+        assert_eq!(
+            tokenize("\x1b").collect::<Vec<_>>(),
+            vec![Token::Incomplete("\x1b")]
+        );
+        assert_eq!(
+            tokenize("\x1b[1\nx").collect::<Vec<_>>(),
+            vec![
+                Token::Invalid("\x1b[1"),
+                Token::Control('\n'),
+                Token::Text("x")
+            ]
+        );
+    }
+
+    #[test]
+    fn strip() {
+        assert_eq!(strip_ansi("plain"), "plain");
+        assert_eq!(
+            strip_ansi("\x1b[1mbold\x1b[0m \x1b]8;;u\x07link\x1b]8;;\x07"),
+            "bold link"
+        );
+    }
+}
@@ -0,0 +1,158 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::{Ansi, AnsiFlags, Color, Palette, RenderOptions, Rgba};
+
+/// A style whose colors may be translucent, e.g. a selection highlight from a theme file.
+///
+/// Terminals only understand opaque colors, so this has to be resolved into an [`Ansi`]
+/// against the terminal's background color (see [`query_background`](crate::query::query_background))
+/// before it can be written. The background is composited over the terminal background, and
+/// the foreground over the result.
+///
+/// ## Example
+/// ```
+/// # use ansirs::{Ansi, Color, RenderOptions, Rgba, TranslucentAnsi};
+/// let highlight = TranslucentAnsi::new().bg(Rgba::new(255, 255, 0, 64)).bold();
+///
+/// let on_black = highlight.resolve(Color::from_rgb(0, 0, 0));
+/// assert_eq!(on_black, Ansi::new().bg((64, 64, 0)).bold());
+///
+/// let options = RenderOptions::new().background(Color::from_rgb(255, 255, 255));
+/// assert_eq!(highlight.resolve_with(&options), Ansi::new().bg((255, 255, 191)).bold());
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TranslucentAnsi {
+    fg: Option<Rgba>,
+    bg: Option<Rgba>,
+    flags: AnsiFlags,
+}
+
+impl TranslucentAnsi {
+    /// Creates a new, empty, [`TranslucentAnsi`].
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            fg: None,
+            bg: None,
+            flags: AnsiFlags::empty(),
+        }
+    }
+
+    /// Sets the foreground color.
+    #[must_use]
+    pub fn fg(self, fg: impl Into<Rgba>) -> Self {
+        Self {
+            fg: Some(fg.into()),
+            ..self
+        }
+    }
+
+    /// Sets the background color.
+    #[must_use]
+    pub fn bg(self, bg: impl Into<Rgba>) -> Self {
+        Self {
+            bg: Some(bg.into()),
+            ..self
+        }
+    }
+
+    /// Adds the given flags.
+    #[must_use]
+    pub const fn with_flags(self, flags: AnsiFlags) -> Self {
+        Self {
+            flags: self.flags.union(flags),
+            ..self
+        }
+    }
+
+    /// Adds the bold flag.
+    #[must_use]
+    pub const fn bold(self) -> Self {
+        self.with_flags(AnsiFlags::BOLD)
+    }
+
+    /// Gets the foreground color, if one is set.
+    #[must_use]
+    pub const fn foreground(&self) -> Option<Rgba> {
+        self.fg
+    }
+
+    /// Gets the background color, if one is set.
+    #[must_use]
+    pub const fn background(&self) -> Option<Rgba> {
+        self.bg
+    }
+
+    /// Gets the flags.
+    #[must_use]
+    pub const fn flags(&self) -> AnsiFlags {
+        self.flags
+    }
+
+    /// Resolves this style into an [`Ansi`] for a terminal with the given background color.
+    ///
+    /// A fully transparent background is dropped, so the terminal's own background shows.
+    #[must_use]
+    pub fn resolve(&self, terminal_background: Color) -> Ansi {
+        let bg = self
+            .bg
+            .filter(|bg| bg.a > 0)
+            .map(|bg| bg.over(terminal_background));
+        let fg = self.fg.map(|fg| fg.over(bg.unwrap_or(terminal_background)));
+        Ansi::from_parts(fg, bg, self.flags)
+    }
+
+    /// Resolves this style using the background from `options`, or the default [`Palette`]'s
+    /// background if it isn't set.
+    #[must_use]
+    pub fn resolve_with(&self, options: &RenderOptions) -> Ansi {
+        let background = options
+            .get_background()
+            .unwrap_or_else(|| Palette::default().background());
+        self.resolve(background)
+    }
+}
+
+impl From<Ansi> for TranslucentAnsi {
+    fn from(ansi: Ansi) -> Self {
+        Self {
+            fg: ansi.foreground().map(Rgba::from),
+            bg: ansi.background().map(Rgba::from),
+            flags: ansi.flags(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn resolving() {
+        let black = Color::from_rgb(0, 0, 0);
+        let opaque = Ansi::new().fg((1, 2, 3)).bg((4, 5, 6)).italic();
+        assert_eq!(TranslucentAnsi::from(opaque).resolve(black), opaque);
+
+        let style = TranslucentAnsi::new()
+            .fg(Rgba::new(255, 255, 255, 128))
+            .bg(Rgba::new(0, 0, 255, 0));
+        assert_eq!(style.resolve(black), Ansi::from_fg((128, 128, 128)));
+
+        // The foreground is composited over the resolved background.
+        let style = style.bg(Rgba::new(0, 0, 255, 255));
+        assert_eq!(
+            style.resolve(black),
+            Ansi::new().fg((128, 128, 255)).bg((0, 0, 255))
+        );
+        assert_eq!(
+            style.resolve_with(&RenderOptions::new()),
+            style.resolve(Palette::default().background())
+        );
+    }
+}
@@ -0,0 +1,271 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Perceptual adjustments. Lightness, chroma and hue are changed in OKLCH, so e.g. lightening
+//! yellow and blue by the same amount makes them look equally much lighter. Results outside of
+//! the sRGB gamut are brought back in by reducing their chroma, which keeps lightness and hue.
+//!
+//! Styles are adjusted with [`Ansi::lighten_fg`] and friends, or with [`Ansi::map_fg`] and
+//! [`Ansi::map_bg`] for everything else.
+
+use super::space::{linear_to_srgb, oklab_to_linear, polar_to_rect};
+use crate::{Ansi, Color, ColorSpace, HuePath};
+
+pub(super) const OKLCH: ColorSpace = ColorSpace::Oklch(HuePath::Shorter);
+
+impl Color {
+    /// Creates a color from OKLCH coordinates (lightness, chroma, hue), reducing the chroma
+    /// until it fits into the sRGB gamut.
+    pub(crate) fn from_oklch([l, c, h]: [f64; 3]) -> Color {
+        let (l, h) = (l.clamp(0.0, 1.0), h.rem_euclid(360.0));
+        let in_gamut = |c: f64| {
+            oklab_to_linear(polar_to_rect([l, c, h]))
+                .iter()
+                .all(|v| (-1e-6..=1.0 + 1e-6).contains(v))
+        };
+        let mut c = c.max(0.0);
+        if !in_gamut(c) {
+            let (mut lo, mut hi) = (0.0, c);
+            for _ in 0..24 {
+                let mid = f64::midpoint(lo, hi);
+                if in_gamut(mid) {
+                    lo = mid;
+                } else {
+                    hi = mid;
+                }
+            }
+            c = lo;
+        }
+        OKLCH.to_color([l, c, h])
+    }
+
+    /// Changes the OKLCH coordinates (lightness, chroma, hue) of this color with `f`.
+    fn map_oklch(self, f: impl FnOnce([f64; 3]) -> [f64; 3]) -> Color {
+        let [l, c, h] = OKLCH.to_coords(self);
+        // Grays have no hue, any hue works with a chroma of zero.
+        Color::from_oklch(f([l, c, if h.is_nan() { 0.0 } else { h }]))
+    }
+
+    /// Makes this color lighter by adding `amount` to its OKLCH lightness, which goes from
+    /// `0.0` (black) to `1.0` (white).
+    ///
+    /// ```
+    /// # use ansirs::Color;
+    /// let gray = Color::from_rgb(0x77, 0x77, 0x77);
+    /// assert_eq!(gray.lighten(0.1), Color::from_rgb(149, 149, 149));
+    /// assert_eq!(gray.lighten(1.0), Color::from_rgb(255, 255, 255));
+    /// ```
+    #[must_use]
+    pub fn lighten(&self, amount: f64) -> Color {
+        self.map_oklch(|[l, c, h]| [l + amount, c, h])
+    }
+
+    /// Makes this color darker by subtracting `amount` from its OKLCH lightness, see
+    /// [`Color::lighten`].
+    #[must_use]
+    pub fn darken(&self, amount: f64) -> Color {
+        self.lighten(-amount)
+    }
+
+    /// Makes this color more colorful by scaling its OKLCH chroma by `1.0 + amount`.
+    ///
+    /// ```
+    /// # use ansirs::Color;
+    /// let muted = Color::from_rgb(0x80, 0x60, 0x60);
+    /// let vivid = muted.saturate(0.5);
+    /// assert!(vivid.r() > muted.r() && vivid.g() < muted.g());
+    /// ```
+    #[must_use]
+    pub fn saturate(&self, amount: f64) -> Color {
+        self.map_oklch(|[l, c, h]| [l, c * (1.0 + amount), h])
+    }
+
+    /// Makes this color less colorful by scaling its OKLCH chroma by `1.0 - amount`, so an
+    /// `amount` of `1.0` or more gives a gray of the same lightness.
+    #[must_use]
+    pub fn desaturate(&self, amount: f64) -> Color {
+        self.saturate(-amount)
+    }
+
+    /// Rotates the OKLCH hue of this color by `degrees`.
+    ///
+    /// ```
+    /// # use ansirs::Color;
+    /// let orange = Color::from_rgb(0xff, 0x80, 0x00);
+    /// assert_eq!(orange.rotate_hue(360.0), orange);
+    /// let rotated = orange.rotate_hue(120.0);
+    /// assert!(rotated.g() > rotated.r());
+    /// ```
+    #[must_use]
+    pub fn rotate_hue(&self, degrees: f64) -> Color {
+        self.map_oklch(|[l, c, h]| [l, c, h + degrees])
+    }
+
+    /// Gets the complementary color, i.e. the color with the opposite OKLCH hue.
+    #[must_use]
+    pub fn complement(&self) -> Color {
+        self.rotate_hue(180.0)
+    }
+
+    /// Inverts every channel, like a photographic negative.
+    ///
+    /// ```
+    /// # use ansirs::Color;
+    /// assert_eq!(Color::from_rgb(0, 100, 255).invert(), Color::from_rgb(255, 155, 0));
+    /// ```
+    #[must_use]
+    pub fn invert(&self) -> Color {
+        Color::from_rgb(255 - self.r(), 255 - self.g(), 255 - self.b())
+    }
+
+    /// Converts this color to the gray with the same
+    /// [relative luminance](Color::relative_luminance), so contrast against other colors is
+    /// preserved.
+    ///
+    /// ```
+    /// # use ansirs::Color;
+    /// assert_eq!(Color::from_rgb(0, 0, 255).grayscale(), Color::from_rgb(76, 76, 76));
+    /// assert_eq!(Color::from_rgb(0, 255, 0).grayscale(), Color::from_rgb(220, 220, 220));
+    /// ```
+    #[must_use]
+    pub fn grayscale(&self) -> Color {
+        Color::from_unit_rgb([linear_to_srgb(self.relative_luminance()); 3])
+    }
+
+    /// Mixes this color with white, `amount` going from `0.0` (unchanged) to `1.0` (white).
+    /// Mixing happens in [`ColorSpace::Oklab`].
+    #[must_use]
+    pub fn tint(&self, amount: f64) -> Color {
+        self.mix(Color::from_rgb(255, 255, 255), amount)
+    }
+
+    /// Mixes this color with black, `amount` going from `0.0` (unchanged) to `1.0` (black).
+    /// Mixing happens in [`ColorSpace::Oklab`].
+    #[must_use]
+    pub fn shade(&self, amount: f64) -> Color {
+        self.mix(Color::from_rgb(0, 0, 0), amount)
+    }
+}
+
+impl Ansi {
+    /// Builder function to lighten the foreground color, if there is one, see
+    /// [`Color::lighten`]. The other color adjustments, like [`Color::desaturate`], work on styles through
+    /// [`Ansi::map_fg`] and [`Ansi::map_bg`].
+    ///
+    /// ```
+    /// # use ansirs::Ansi;
+    /// let base = Ansi::new().fg((0, 0x80, 0x80)).bg((0x20, 0x20, 0x20));
+    /// let hover = base.lighten_fg(0.1).darken_bg(0.05);
+    /// assert_ne!(hover, base);
+    /// let muted = base.map_fg(|c| c.desaturate(0.5));
+    /// assert_eq!(muted.background(), base.background());
+    /// ```
+    #[must_use]
+    pub fn lighten_fg(self, amount: f64) -> Self {
+        self.map_fg(|c| c.lighten(amount))
+    }
+
+    /// Builder function to darken the foreground color, if there is one, see
+    /// [`Color::darken`].
+    #[must_use]
+    pub fn darken_fg(self, amount: f64) -> Self {
+        self.map_fg(|c| c.darken(amount))
+    }
+
+    /// Builder function to lighten the background color, if there is one, see
+    /// [`Color::lighten`].
+    #[must_use]
+    pub fn lighten_bg(self, amount: f64) -> Self {
+        self.map_bg(|c| c.lighten(amount))
+    }
+
+    /// Builder function to darken the background color, if there is one, see
+    /// [`Color::darken`].
+    #[must_use]
+    pub fn darken_bg(self, amount: f64) -> Self {
+        self.map_bg(|c| c.darken(amount))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn lightness() {
+        let black = Color::from_rgb(0, 0, 0);
+        let white = Color::from_rgb(255, 255, 255);
+        let teal = Color::from_rgb(0, 0x80, 0x80);
+        assert_eq!(teal.lighten(0.0), teal);
+        assert_eq!(teal.darken(1.0), black);
+        assert_eq!(teal.lighten(1.0), white);
+        assert_eq!(black.lighten(0.5).darken(0.5), black);
+        assert_eq!(teal.tint(1.0), white);
+        assert_eq!(teal.shade(1.0), black);
+        assert_eq!(teal.shade(0.0), teal);
+
+        // Lighter in the perceptual sense, keeping roughly the same hue.
+        let light = teal.lighten(0.2);
+        assert!(light.relative_luminance() > teal.relative_luminance());
+        assert!(light.r() < light.g());
+    }
+
+    #[test]
+    fn chroma_and_hue() {
+        let red = Color::from_rgb(200, 30, 30);
+        let gray = red.desaturate(1.0);
+        assert!(gray.r().abs_diff(gray.g()) <= 1 && gray.g().abs_diff(gray.b()) <= 1);
+        assert_eq!(red.desaturate(0.0), red);
+        assert_eq!(gray.saturate(1.0), gray);
+        assert_eq!(
+            Color::from_rgb(50, 50, 50).rotate_hue(90.0),
+            Color::from_rgb(50, 50, 50)
+        );
+
+        let complement = red.complement();
+        assert!(complement.g() > complement.r() && complement.b() > complement.r());
+        let muted = Color::from_rgb(150, 100, 90);
+        assert_eq!(muted.complement().complement(), muted);
+    }
+
+    #[test]
+    fn channels() {
+        let color = Color::from_rgb(12, 200, 99);
+        assert_eq!(color.invert().invert(), color);
+        let gray = color.grayscale();
+        assert!((gray.relative_luminance() - color.relative_luminance()).abs() < 0.01);
+        assert_eq!(gray.grayscale(), gray);
+    }
+
+    #[test]
+    fn ansi_chaining() {
+        let base = Ansi::new()
+            .bold()
+            .fg((0, 0x80, 0x80))
+            .bg((0x20, 0x20, 0x20));
+        let dim = base
+            .map_fg(|c| c.desaturate(0.5).darken(0.1))
+            .map_bg(|c| c.lighten(0.05));
+        assert_eq!(dim.flags(), base.flags());
+        assert_eq!(
+            dim.foreground(),
+            base.foreground().map(|c| c.desaturate(0.5).darken(0.1))
+        );
+        assert_eq!(dim.background(), base.background().map(|c| c.lighten(0.05)));
+
+        let adjusted = base.lighten_fg(0.1).darken_bg(0.05);
+        assert_eq!(
+            adjusted,
+            base.map_fg(|c| c.lighten(0.1)).map_bg(|c| c.darken(0.05))
+        );
+        assert_eq!(base.darken_fg(0.1).lighten_bg(0.1).flags(), base.flags());
+        assert_eq!(
+            Ansi::new().bold().lighten_fg(0.5).darken_bg(0.5),
+            Ansi::new().bold()
+        );
+    }
+}
@@ -69,6 +69,68 @@ impl Color {
         Ok(Self(rgb[0], rgb[1], rgb[2]))
     }
 
+    /// `const` version of [`Color::from_hex`]. Accepts the same inputs (`#rgb`, `#rrggbb`,
+    /// with or without the leading `#`) but returns `None` instead of a [`ColorParseError`]
+    /// so it can be used in `const` contexts.
+    #[must_use]
+    pub const fn from_hex_const(input: &str) -> Option<Self> {
+        const fn digit(c: u8) -> Option<u8> {
+            match c {
+                b'0'..=b'9' => Some(c - b'0'),
+                b'a'..=b'f' => Some(c - b'a' + 10),
+                b'A'..=b'F' => Some(c - b'A' + 10),
+                _ => None,
+            }
+        }
+
+        let mut bytes = input.as_bytes();
+        if let [b'#', rest @ ..] = bytes {
+            bytes = rest;
+        }
+
+        let is_double = match bytes.len() {
+            3 => false,
+            6 => true,
+            _ => return None,
+        };
+
+        let mut rgb = [0u8; 3];
+        let mut i = 0;
+        while i < 3 {
+            let (hi, lo) = if is_double {
+                (digit(bytes[i * 2]), digit(bytes[i * 2 + 1]))
+            } else {
+                (digit(bytes[i]), digit(bytes[i]))
+            };
+            rgb[i] = match (hi, lo) {
+                (Some(hi), Some(lo)) => hi * 16 + lo,
+                _ => return None,
+            };
+            i += 1;
+        }
+
+        Some(Self(rgb[0], rgb[1], rgb[2]))
+    }
+
+    /// Creates a color from the given hexadecimal string, panicking if it is invalid.
+    ///
+    /// This is meant for declaring constants, where the panic becomes a compile error:
+    /// ```
+    /// # use ansirs::Color;
+    /// const DODGER_BLUE: Color = Color::hex("#1e90ff");
+    /// assert_eq!(DODGER_BLUE.rgb(), (30, 144, 255));
+    /// ```
+    ///
+    /// ## Panics
+    /// - If `input` is not a valid `#rgb` or `#rrggbb` hex string.
+    #[must_use]
+    pub const fn hex(input: &str) -> Self {
+        match Self::from_hex_const(input) {
+            Some(color) => color,
+            None => panic!("invalid hex color, expected `#rgb` or `#rrggbb`"),
+        }
+    }
+
     /// Create a hex string from this color.
     #[must_use]
     #[cfg_attr(feature = "trace", tracing::instrument)]
@@ -381,6 +443,20 @@ impl Color {
         Self::from_rgb(r, g, b)
     }
 
+    /// Gets the relative luminance of this color as defined by WCAG 2, from `0.0` for black
+    /// to `1.0` for white.
+    ///
+    /// ```
+    /// # use ansirs::Color;
+    /// assert_eq!(Color::from_rgb(255, 255, 255).relative_luminance(), 1.0);
+    /// assert!(Color::from_rgb(0, 0, 255).relative_luminance() < 0.1);
+    /// ```
+    #[must_use]
+    pub fn relative_luminance(&self) -> f64 {
+        let [r, g, b] = self.to_unit_rgb().map(super::space::srgb_to_linear);
+        0.2126 * r + 0.7152 * g + 0.0722 * b
+    }
+
     /// Converts this color into an [`Ansi`] instance by using it as the **foreground** color.
     #[cfg_attr(feature = "trace", tracing::instrument)]
     #[must_use]
@@ -409,6 +485,18 @@ mod tests {
         assert_eq!(color.as_hex_lower(), "#1964fa");
     }
 
+    #[test]
+    fn hex_const() {
+        const FULL: Color = Color::hex("#1E90ff");
+        const SHORT: Color = Color::hex("f00");
+        assert_eq!(FULL, Color::from_hex("#1E90ff").unwrap());
+        assert_eq!(SHORT, Color::from_rgb(255, 0, 0));
+
+        for bad in ["", "#", "#FF00", "#FF00000", "#FF000G", "üßü", "##fff"] {
+            assert_eq!(Color::from_hex_const(bad), None, "{bad} should not parse");
+        }
+    }
+
     #[test]
     fn color_from_non_ascii() {
         assert!(Color::from_hex("üßü").is_err());
@@ -4,6 +4,8 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+#![allow(clippy::doc_markdown, clippy::manual_abs_diff)]
+
 use std::str::FromStr;
 
 use crate::Color;
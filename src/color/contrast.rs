@@ -0,0 +1,356 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use super::adjust::OKLCH;
+use crate::{Ansi, Color, ToColor};
+
+const BLACK: Color = Color::from_rgb(0, 0, 0);
+const WHITE: Color = Color::from_rgb(255, 255, 255);
+
+/// A minimum contrast between text and its background.
+///
+/// WCAG 2 contrast ratios go from `1.0` to `21.0`. APCA lightness contrast (Lc) goes from
+/// `0.0` to about `106.0`, with `75.0` recommended for body text, `60.0` for other content
+/// text and `45.0` for large or bold text.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ContrastTarget {
+    /// A minimum WCAG 2 contrast ratio.
+    Wcag(f64),
+    /// A minimum absolute APCA lightness contrast.
+    Apca(f64),
+}
+
+impl ContrastTarget {
+    /// WCAG level AA for normal text, a ratio of `4.5`.
+    pub const AA: Self = Self::Wcag(4.5);
+    /// WCAG level AA for large text, a ratio of `3.0`.
+    pub const AA_LARGE: Self = Self::Wcag(3.0);
+    /// WCAG level AAA for normal text, a ratio of `7.0`.
+    pub const AAA: Self = Self::Wcag(7.0);
+    /// APCA's recommendation for body text, an Lc of `75.0`.
+    pub const APCA_BODY: Self = Self::Apca(75.0);
+
+    /// Measures the contrast of `fg` on `bg` in this target's metric.
+    #[must_use]
+    pub fn measure(self, fg: Color, bg: Color) -> f64 {
+        match self {
+            ContrastTarget::Wcag(_) => fg.contrast_ratio(bg),
+            ContrastTarget::Apca(_) => fg.apca_contrast(bg).abs(),
+        }
+    }
+
+    /// Returns `true` if `fg` on `bg` reaches this target.
+    #[must_use]
+    pub fn is_met(self, fg: Color, bg: Color) -> bool {
+        let (ContrastTarget::Wcag(min) | ContrastTarget::Apca(min)) = self;
+        self.measure(fg, bg) >= min
+    }
+}
+
+impl Default for ContrastTarget {
+    /// WCAG level AA for normal text.
+    fn default() -> Self {
+        Self::AA
+    }
+}
+
+impl Color {
+    /// Gets the WCAG 2 contrast ratio between this color and `other`, from `1.0` for equal
+    /// luminance to `21.0` for black and white. The order of the colors doesn't matter.
+    ///
+    /// ```
+    /// # use ansirs::Color;
+    /// let black = Color::from_rgb(0, 0, 0);
+    /// let white = Color::from_rgb(255, 255, 255);
+    /// assert_eq!(black.contrast_ratio(white), 21.0);
+    /// assert!((Color::from_rgb(0x76, 0x76, 0x76).contrast_ratio(white) - 4.54).abs() < 0.01);
+    /// ```
+    #[must_use]
+    pub fn contrast_ratio(&self, other: Color) -> f64 {
+        let a = self.relative_luminance();
+        let b = other.relative_luminance();
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+
+    /// Gets the APCA lightness contrast (Lc, APCA 0.0.98G) of this color as text on
+    /// `background`. The result is positive for dark text on a light background and negative
+    /// for light text on a dark background.
+    ///
+    /// ```
+    /// # use ansirs::Color;
+    /// let black = Color::from_rgb(0, 0, 0);
+    /// let white = Color::from_rgb(255, 255, 255);
+    /// assert_eq!(black.apca_contrast(white).round(), 106.0);
+    /// assert_eq!(white.apca_contrast(black).round(), -108.0);
+    /// assert_eq!(Color::from_rgb(0x88, 0x88, 0x88).apca_contrast(white).round(), 63.0);
+    /// ```
+    #[must_use]
+    pub fn apca_contrast(&self, background: Color) -> f64 {
+        fn screen_luminance(color: Color) -> f64 {
+            let [r, g, b] = color.to_unit_rgb().map(|c| c.powf(2.4));
+            let y = 0.212_672_9 * r + 0.715_152_2 * g + 0.072_175 * b;
+            // Soft clamp near black.
+            if y < 0.022 {
+                y + (0.022 - y).powf(1.414)
+            } else {
+                y
+            }
+        }
+
+        let text = screen_luminance(*self);
+        let bg = screen_luminance(background);
+        if (bg - text).abs() < 0.0005 {
+            return 0.0;
+        }
+
+        let contrast = if bg > text {
+            let sapc = (bg.powf(0.56) - text.powf(0.57)) * 1.14;
+            if sapc < 0.1 {
+                0.0
+            } else {
+                sapc - 0.027
+            }
+        } else {
+            let sapc = (bg.powf(0.65) - text.powf(0.62)) * 1.14;
+            if sapc > -0.1 {
+                0.0
+            } else {
+                sapc + 0.027
+            }
+        };
+        contrast * 100.0
+    }
+
+    /// Picks the candidate with the highest WCAG contrast against this color as a background.
+    /// Without candidates, picks black or white.
+    ///
+    /// ```
+    /// # use ansirs::{Color, Colors, ToColor};
+    /// let background = Color::from_rgb(0x20, 0x20, 0x30);
+    /// let candidates = [Colors::Navy, Colors::Khaki, Colors::DarkRed];
+    /// assert_eq!(background.best_text_color(candidates), Colors::Khaki.to_color());
+    /// assert_eq!(background.best_text_color::<Color>([]), Color::from_rgb(255, 255, 255));
+    /// ```
+    #[must_use]
+    pub fn best_text_color<C: ToColor>(&self, candidates: impl IntoIterator<Item = C>) -> Color {
+        let mut candidates = candidates.into_iter().map(|c| c.to_color()).peekable();
+        if candidates.peek().is_none() {
+            return self.best_text_color([BLACK, WHITE]);
+        }
+        candidates
+            .map(|c| (self.contrast_ratio(c), c))
+            .fold(
+                (0.0, BLACK),
+                |best, next| if next.0 > best.0 { next } else { best },
+            )
+            .1
+    }
+
+    /// Adjusts the OKLCH lightness of this color until it reaches the `target` contrast as
+    /// text on `background`, keeping its hue and chroma (reducing the chroma only where the
+    /// result would be outside of the sRGB gamut). The color is returned unchanged if it already reaches
+    /// the target, and black or white if no lighter or darker version of it does.
+    ///
+    /// ```
+    /// # use ansirs::{Color, ContrastTarget};
+    /// let background = Color::from_rgb(0x33, 0x33, 0x33);
+    /// let dim = Color::from_rgb(0x55, 0x44, 0xaa);
+    /// let readable = dim.ensure_contrast(background, ContrastTarget::AA);
+    /// assert!(ContrastTarget::AA.is_met(readable, background));
+    /// assert!(readable.b() > readable.r());
+    /// ```
+    #[must_use]
+    pub fn ensure_contrast(&self, background: Color, target: ContrastTarget) -> Color {
+        if target.is_met(*self, background) {
+            return *self;
+        }
+
+        // Only OKLCH lightness changes, hue and chroma are kept as far as the gamut allows.
+        // Search for the smallest change that reaches the target in both directions.
+        let [l, c, h] = OKLCH.to_coords(*self);
+        let h = if h.is_nan() { 0.0 } else { h };
+        let search = |end: f64| {
+            let at = |t: f64| Color::from_oklch([l + (end - l) * t, c, h]);
+            if !target.is_met(at(1.0), background) {
+                return None;
+            }
+            let (mut lo, mut hi) = (0.0, 1.0);
+            for _ in 0..24 {
+                let mid = f64::midpoint(lo, hi);
+                if target.is_met(at(mid), background) {
+                    hi = mid;
+                } else {
+                    lo = mid;
+                }
+            }
+            Some(((end - l).abs() * hi, at(hi)))
+        };
+
+        match (search(0.0), search(1.0)) {
+            (Some(dark), Some(light)) if dark.0 <= light.0 => dark.1,
+            (_, Some(light)) => light.1,
+            (Some(dark), None) => dark.1,
+            (None, None) => background.best_text_color([BLACK, WHITE]),
+        }
+    }
+}
+
+impl Ansi {
+    /// Sets the background color, and makes sure the foreground stays readable on it, see
+    /// [`Ansi::on_with_contrast`]. Uses [`ContrastTarget::AA`].
+    ///
+    /// ```
+    /// # use ansirs::{Ansi, Color};
+    /// let black = Color::from_rgb(0, 0, 0);
+    /// let white = Color::from_rgb(255, 255, 255);
+    /// assert_eq!(Ansi::new().on(black).foreground(), Some(white));
+    /// assert_eq!(Ansi::from_fg(white).on(black).foreground(), Some(white));
+    /// assert_ne!(Ansi::from_fg((40, 40, 40)).on(black).foreground(), Some(Color::from_rgb(40, 40, 40)));
+    /// ```
+    #[must_use]
+    pub fn on<C: ToColor>(self, bg: C) -> Self {
+        self.on_with_contrast(bg, ContrastTarget::AA)
+    }
+
+    /// Sets the background color, and makes sure the foreground reaches the `target` contrast
+    /// on it. A foreground color that is too dim is adjusted with [`Color::ensure_contrast`],
+    /// and without a foreground color black or white is picked.
+    #[allow(clippy::needless_pass_by_value)]
+    #[must_use]
+    pub fn on_with_contrast<C: ToColor>(self, bg: C, target: ContrastTarget) -> Self {
+        let bg = bg.to_color();
+        let fg = match self.foreground() {
+            Some(fg) => fg.ensure_contrast(bg, target),
+            None => bg.best_text_color([BLACK, WHITE]),
+        };
+        self.fg_color(fg).bg_color(bg)
+    }
+
+    /// Makes sure the foreground color reaches the `target` contrast on this style's
+    /// background, or on `terminal_background` if it has none. A foreground that is too dim
+    /// is nudged in lightness, keeping its hue, see [`Color::ensure_contrast`]. Styles
+    /// without a foreground color are returned unchanged.
+    ///
+    /// ```
+    /// # use ansirs::{Ansi, Color, ContrastTarget};
+    /// let dark_terminal = Color::from_rgb(0x1e, 0x1e, 0x1e);
+    /// let navy = Ansi::from_fg((0, 0, 0x80)).bold();
+    /// let adjusted = navy.with_min_contrast(dark_terminal, ContrastTarget::AA);
+    /// assert!(ContrastTarget::AA.is_met(adjusted.foreground().unwrap(), dark_terminal));
+    /// assert_eq!(adjusted.flags(), navy.flags());
+    ///
+    /// // The style's own background wins over the terminal's.
+    /// let on_white = navy.bg((255, 255, 255));
+    /// assert_eq!(on_white.with_min_contrast(dark_terminal, ContrastTarget::AA), on_white);
+    /// ```
+    #[must_use]
+    pub fn with_min_contrast(self, terminal_background: Color, target: ContrastTarget) -> Self {
+        match self.foreground() {
+            Some(fg) => {
+                let bg = self.background().unwrap_or(terminal_background);
+                self.fg_color(fg.ensure_contrast(bg, target))
+            }
+            None => self,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn ratios() {
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+        let gray = Color::from_rgb(128, 128, 128);
+        assert!(close(gray.contrast_ratio(gray), 1.0));
+        assert!(close(
+            gray.contrast_ratio(WHITE),
+            WHITE.contrast_ratio(gray)
+        ));
+        assert!(close(gray.apca_contrast(gray), 0.0));
+        // APCA is not symmetric, light text on dark backgrounds needs more contrast.
+        let yellow = Color::from_rgb(255, 255, 0);
+        assert!(close(BLACK.apca_contrast(yellow).round(), 101.0));
+        assert!(close(yellow.apca_contrast(BLACK).round(), -103.0));
+    }
+
+    #[test]
+    fn targets() {
+        assert_eq!(ContrastTarget::default(), ContrastTarget::AA);
+        assert!(ContrastTarget::AAA.is_met(BLACK, WHITE));
+        assert!(!ContrastTarget::AA.is_met(Color::from_rgb(0x80, 0x80, 0x80), WHITE));
+        assert!(ContrastTarget::AA_LARGE.is_met(Color::from_rgb(0x80, 0x80, 0x80), WHITE));
+        assert!(ContrastTarget::APCA_BODY.is_met(WHITE, BLACK));
+    }
+
+    #[test]
+    fn adjusting() {
+        let backgrounds = [BLACK, WHITE, Color::from_rgb(0x77, 0x77, 0x77)];
+        let colors = [
+            Color::from_rgb(255, 0, 0),
+            Color::from_rgb(0x77, 0x77, 0x78),
+            Color::from_rgb(0, 0, 80),
+        ];
+        let targets = [
+            ContrastTarget::AA,
+            ContrastTarget::AAA,
+            ContrastTarget::APCA_BODY,
+        ];
+        for bg in backgrounds {
+            for color in colors {
+                for target in targets {
+                    let adjusted = color.ensure_contrast(bg, target);
+                    let best = bg.best_text_color([BLACK, WHITE]);
+                    assert!(
+                        target.is_met(adjusted, bg) || adjusted == best,
+                        "{color:?} on {bg:?} for {target:?} gave {adjusted:?}"
+                    );
+                }
+            }
+        }
+
+        // Colors that already have enough contrast are left alone.
+        let red = Color::from_rgb(255, 0, 0);
+        assert_eq!(red.ensure_contrast(BLACK, ContrastTarget::AA), red);
+    }
+
+    #[test]
+    fn ansi_on() {
+        let ansi = Ansi::new().bold().fg((255, 0, 0)).on(WHITE);
+        assert_eq!(ansi.background(), Some(WHITE));
+        assert!(ansi.flags().contains(crate::AnsiFlags::BOLD));
+        let fg = ansi.foreground().unwrap();
+        assert!(ContrastTarget::AA.is_met(fg, WHITE));
+        assert!(fg.r() > fg.g());
+    }
+
+    #[test]
+    fn keeps_hue_and_chroma() {
+        let dark = Color::from_rgb(0x1e, 0x1e, 0x1e);
+        for color in [
+            Color::from_rgb(0, 0, 0x80),
+            Color::from_rgb(0x80, 0, 0),
+            Color::from_rgb(0x30, 0x60, 0x30),
+        ] {
+            let [l, c, h] = OKLCH.to_coords(color);
+            let adjusted = color.ensure_contrast(dark, ContrastTarget::AA);
+            let [new_l, new_c, new_h] = OKLCH.to_coords(adjusted);
+            assert!(new_l > l, "{color:?} gave {adjusted:?}");
+            assert!((new_c - c).abs() < 0.005, "{color:?} gave {adjusted:?}");
+            assert!((new_h - h).abs() < 1.0, "{color:?} gave {adjusted:?}");
+        }
+
+        // Where the gamut doesn't allow the full chroma, only chroma is given up.
+        let navy = Color::from_rgb(0, 0, 0x80);
+        let adjusted = navy.ensure_contrast(dark, ContrastTarget::AAA);
+        let [_, c, h] = OKLCH.to_coords(adjusted);
+        assert!(c > 0.13 && c < OKLCH.to_coords(navy)[1]);
+        assert!((h - OKLCH.to_coords(navy)[2]).abs() < 1.0);
+    }
+}
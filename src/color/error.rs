@@ -15,6 +15,33 @@ pub enum ColorParseError {
     ParseIntError(std::num::ParseIntError),
     /// Other errors (with message).
     Unknown(String),
+    /// The input is not a valid color, see [`Color::parse`](crate::Color::parse).
+    Invalid {
+        /// Byte offset in the input where the problem was found.
+        position: usize,
+        /// What was expected at `position`, e.g. `"a number or percentage"`.
+        expected: &'static str,
+    },
+}
+
+impl ColorParseError {
+    /// Gets the byte offset within the input where the problem was found, if known.
+    #[must_use]
+    pub fn position(&self) -> Option<usize> {
+        match self {
+            ColorParseError::Invalid { position, .. } => Some(*position),
+            _ => None,
+        }
+    }
+
+    /// Gets a description of what was expected, if known.
+    #[must_use]
+    pub fn expected(&self) -> Option<&'static str> {
+        match self {
+            ColorParseError::Invalid { expected, .. } => Some(expected),
+            _ => None,
+        }
+    }
 }
 
 impl std::fmt::Display for ColorParseError {
@@ -28,6 +55,12 @@ impl std::fmt::Display for ColorParseError {
                 write!(f, "Could not parse color string into a number: {inner}")
             }
             ColorParseError::Unknown(msg) => write!(f, "Unknown error: {msg}"),
+            ColorParseError::Invalid { position, expected } => {
+                write!(
+                    f,
+                    "Invalid color at position {position}, expected {expected}"
+                )
+            }
         }
     }
 }
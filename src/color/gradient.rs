@@ -0,0 +1,299 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::{Color, ColorSpace, ToColor};
+
+/// An easing function, remapping the position along a [`Gradient`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Easing {
+    /// No easing.
+    #[default]
+    Linear,
+    /// Starts slow and speeds up (quadratic).
+    EaseIn,
+    /// Starts fast and slows down (quadratic).
+    EaseOut,
+    /// Slow at both ends and fast in the middle (cubic).
+    EaseInOut,
+    /// The classic `3t² - 2t³` smoothstep.
+    Smoothstep,
+}
+
+impl Easing {
+    /// Applies this easing to `t` in `0.0..=1.0`.
+    #[must_use]
+    pub fn apply(self, t: f64) -> f64 {
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2.0 - t),
+            Easing::EaseInOut if t < 0.5 => 4.0 * t * t * t,
+            Easing::EaseInOut => 1.0 - (2.0 - 2.0 * t).powi(3) / 2.0,
+            Easing::Smoothstep => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+/// A color ramp through any number of color stops.
+///
+/// Stops have positions in `0.0..=1.0`. Before the first stop the gradient has the first
+/// stop's color, after the last stop the last stop's color. Between stops colors are
+/// interpolated in the gradient's [`ColorSpace`], after the position has been remapped by its
+/// [`Easing`].
+///
+/// ## Example
+/// ```
+/// # use ansirs::{Color, Colors, Gradient};
+/// let heat = Gradient::new(Colors::Blue, Colors::Red).with_stop(0.5, Colors::Yellow);
+/// assert_eq!(heat.at(0.0), Color::from_rgb(0, 0, 255));
+/// assert_eq!(heat.at(0.5), Color::from_rgb(255, 255, 0));
+/// assert_eq!(heat.take(5).len(), 5);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "GradientData"))]
+pub struct Gradient {
+    stops: Vec<(f64, Color)>,
+    space: ColorSpace,
+    easing: Easing,
+}
+
+/// The unchecked form of a [`Gradient`], which is validated after deserializing.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct GradientData {
+    stops: Vec<(f64, Color)>,
+    space: ColorSpace,
+    easing: Easing,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<GradientData> for Gradient {
+    type Error = &'static str;
+
+    fn try_from(data: GradientData) -> Result<Self, Self::Error> {
+        if data.stops.is_empty() {
+            return Err("a gradient needs at least one color stop");
+        }
+        if !data.stops.iter().all(|(p, _)| (0.0..=1.0).contains(p)) {
+            return Err("color stop positions must be between 0 and 1");
+        }
+        if !data.stops.windows(2).all(|w| w[0].0 <= w[1].0) {
+            return Err("color stops must be sorted by position");
+        }
+        Ok(Self {
+            stops: data.stops,
+            space: data.space,
+            easing: data.easing,
+        })
+    }
+}
+
+impl Gradient {
+    /// Creates a new gradient from `start` (at `0.0`) to `end` (at `1.0`).
+    #[allow(clippy::needless_pass_by_value)]
+    #[must_use]
+    pub fn new<S: ToColor, E: ToColor>(start: S, end: E) -> Self {
+        Self {
+            stops: vec![(0.0, start.to_color()), (1.0, end.to_color())],
+            space: ColorSpace::default(),
+            easing: Easing::default(),
+        }
+    }
+
+    /// Creates a new gradient through the given colors, evenly spaced. Returns `None` if there
+    /// are no colors.
+    ///
+    /// ```
+    /// # use ansirs::{Color, Gradient};
+    /// let ramp = Gradient::from_colors([(255, 0, 0), (0, 255, 0), (0, 0, 255)]).unwrap();
+    /// assert_eq!(ramp.at(0.5), Color::from_rgb(0, 255, 0));
+    /// ```
+    #[must_use]
+    pub fn from_colors<C: ToColor>(colors: impl IntoIterator<Item = C>) -> Option<Self> {
+        let colors = colors.into_iter().map(|c| c.to_color()).collect::<Vec<_>>();
+        let last = colors.len().checked_sub(1)?;
+        #[allow(clippy::cast_precision_loss)]
+        let stops = colors
+            .into_iter()
+            .enumerate()
+            .map(|(i, c)| {
+                let position = if last == 0 {
+                    0.0
+                } else {
+                    i as f64 / last as f64
+                };
+                (position, c)
+            })
+            .collect();
+        Some(Self {
+            stops,
+            space: ColorSpace::default(),
+            easing: Easing::default(),
+        })
+    }
+
+    /// Adds a color stop at `position`, which is clamped to `0.0..=1.0`. A stop at the same
+    /// position as an existing one is placed after it, which makes a hard edge.
+    #[allow(clippy::needless_pass_by_value)]
+    #[must_use]
+    pub fn with_stop<C: ToColor>(mut self, position: f64, color: C) -> Self {
+        let position = if position.is_nan() {
+            0.0
+        } else {
+            position.clamp(0.0, 1.0)
+        };
+        let index = self.stops.partition_point(|(p, _)| *p <= position);
+        self.stops.insert(index, (position, color.to_color()));
+        self
+    }
+
+    /// Sets the color space colors are interpolated in, [`ColorSpace::Oklab`] by default.
+    #[must_use]
+    pub fn space(mut self, space: ColorSpace) -> Self {
+        self.space = space;
+        self
+    }
+
+    /// Sets the easing applied to positions, [`Easing::Linear`] by default.
+    #[must_use]
+    pub fn easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// Gets the color stops, sorted by position.
+    #[must_use]
+    pub fn stops(&self) -> &[(f64, Color)] {
+        &self.stops
+    }
+
+    /// Gets the color space colors are interpolated in.
+    #[must_use]
+    pub fn get_space(&self) -> ColorSpace {
+        self.space
+    }
+
+    /// Gets the easing applied to positions.
+    #[must_use]
+    pub fn get_easing(&self) -> Easing {
+        self.easing
+    }
+
+    /// Samples the gradient at `t`, which is clamped to `0.0..=1.0`.
+    #[must_use]
+    pub fn at(&self, t: f64) -> Color {
+        let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+        let t = self.easing.apply(t);
+
+        let index = self.stops.partition_point(|(p, _)| *p <= t);
+        if index == 0 {
+            return self.stops[0].1;
+        }
+        let (start, from) = self.stops[index - 1];
+        let Some(&(end, to)) = self.stops.get(index) else {
+            return from;
+        };
+        from.lerp(to, (t - start) / (end - start), self.space)
+    }
+
+    /// Samples `n` evenly spaced colors, including both ends.
+    ///
+    /// ```
+    /// # use ansirs::{Color, ColorSpace, Gradient};
+    /// let fade = Gradient::new((0, 0, 0), (255, 255, 255)).space(ColorSpace::Srgb);
+    /// assert_eq!(
+    ///     fade.take(3),
+    ///     [(0, 0, 0), (128, 128, 128), (255, 255, 255)].map(Color::from)
+    /// );
+    /// ```
+    #[must_use]
+    pub fn take(&self, n: usize) -> Vec<Color> {
+        #[allow(clippy::cast_precision_loss)]
+        match n {
+            0 => Vec::new(),
+            1 => vec![self.at(0.0)],
+            _ => (0..n).map(|i| self.at(i as f64 / (n - 1) as f64)).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn easings() {
+        let easings = [
+            Easing::Linear,
+            Easing::EaseIn,
+            Easing::EaseOut,
+            Easing::EaseInOut,
+            Easing::Smoothstep,
+        ];
+        for easing in easings {
+            assert!(easing.apply(0.0).abs() < 1e-9, "{easing:?}");
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-9, "{easing:?}");
+        }
+        assert!(Easing::EaseIn.apply(0.5) < 0.5);
+        assert!(Easing::EaseOut.apply(0.5) > 0.5);
+        assert!((Easing::EaseInOut.apply(0.5) - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn stops() {
+        let red = Color::from_rgb(255, 0, 0);
+        let green = Color::from_rgb(0, 255, 0);
+        let blue = Color::from_rgb(0, 0, 255);
+
+        let gradient = Gradient::new(red, blue)
+            .with_stop(0.5, green)
+            .with_stop(0.5, blue)
+            .space(ColorSpace::Srgb);
+        assert_eq!(gradient.stops().len(), 4);
+        assert_eq!(gradient.at(-1.0), red);
+        assert_eq!(gradient.at(0.25), Color::from_rgb(128, 128, 0));
+        // The two stops at 0.5 make a hard edge.
+        assert_eq!(gradient.at(0.499_999), Color::from_rgb(0, 255, 0));
+        assert_eq!(gradient.at(0.5), blue);
+        assert_eq!(gradient.at(2.0), blue);
+
+        let single = Gradient::from_colors([red]).unwrap();
+        assert_eq!(single.take(3), vec![red; 3]);
+        assert!(Gradient::from_colors::<Color>([]).is_none());
+        assert!(single.take(0).is_empty());
+    }
+
+    #[test]
+    fn easing_and_space() {
+        let black = Color::from_rgb(0, 0, 0);
+        let white = Color::from_rgb(255, 255, 255);
+        let linear = Gradient::new(black, white).space(ColorSpace::Srgb);
+        let eased = linear.clone().easing(Easing::EaseIn);
+        assert_eq!(linear.at(0.5), Color::from_rgb(128, 128, 128));
+        assert_eq!(eased.at(0.5), Color::from_rgb(64, 64, 64));
+        assert_eq!(eased.get_easing(), Easing::EaseIn);
+        assert_eq!(linear.get_space(), ColorSpace::Srgb);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialized_stops_are_validated() {
+        let red = Color::from_rgb(255, 0, 0);
+        let data = |stops: Vec<(f64, Color)>| GradientData {
+            stops,
+            space: ColorSpace::Oklab,
+            easing: Easing::Linear,
+        };
+        assert!(Gradient::try_from(data(vec![(0.0, red), (1.0, red)])).is_ok());
+        assert!(Gradient::try_from(data(vec![])).is_err());
+        assert!(Gradient::try_from(data(vec![(1.0, red), (0.0, red)])).is_err());
+        assert!(Gradient::try_from(data(vec![(f64::NAN, red)])).is_err());
+        assert!(Gradient::try_from(data(vec![(1.5, red)])).is_err());
+    }
+}
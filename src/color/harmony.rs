@@ -0,0 +1,155 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::Color;
+
+/// A color scheme derived from a seed color, see [`Color::harmony`]. Hues are rotated in
+/// OKLCH, so lightness and chroma stay close to the seed's.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Harmony {
+    /// The seed and the opposite hue.
+    Complementary,
+    /// The seed and the two hues next to its complement, 150° and 210° away.
+    SplitComplementary,
+    /// Three hues evenly spaced around the wheel.
+    Triadic,
+    /// Four hues evenly spaced around the wheel.
+    Tetradic,
+    /// The seed and its neighbouring hues, 30° to either side.
+    Analogous,
+    /// Lighter and darker versions of the seed.
+    Monochromatic,
+}
+
+impl Color {
+    /// Generates a palette from this color as the seed. The seed is always the first color.
+    ///
+    /// ```
+    /// # use ansirs::{style_text, Ansi, Color, Harmony};
+    /// let seed = Color::from_rgb(0x33, 0x66, 0xcc);
+    /// let triad = seed.harmony(Harmony::Triadic);
+    /// assert_eq!(triad.len(), 3);
+    /// assert_eq!(triad[0], seed);
+    ///
+    /// // Preview the palette as swatches.
+    /// let preview = triad.iter().map(|&c| style_text("  ", Ansi::from_bg(c))).collect::<String>();
+    /// assert_eq!(preview.matches("\x1b[48;2;").count(), 3);
+    /// ```
+    #[must_use]
+    pub fn harmony(&self, harmony: Harmony) -> Vec<Color> {
+        let rotations: &[f64] = match harmony {
+            Harmony::Complementary => &[180.0],
+            Harmony::SplitComplementary => &[150.0, 210.0],
+            Harmony::Triadic => &[120.0, 240.0],
+            Harmony::Tetradic => &[90.0, 180.0, 270.0],
+            Harmony::Analogous => &[-30.0, 30.0],
+            Harmony::Monochromatic => {
+                return vec![
+                    *self,
+                    self.darken(0.2),
+                    self.darken(0.1),
+                    self.lighten(0.1),
+                    self.lighten(0.2),
+                ];
+            }
+        };
+        std::iter::once(*self)
+            .chain(rotations.iter().map(|&degrees| self.rotate_hue(degrees)))
+            .collect()
+    }
+
+    /// Generates `n` colors that are easy to tell apart, e.g. for the series of a chart or
+    /// the columns of a table. Hues are evenly spaced in OKLCH at a similar lightness and
+    /// chroma, so no color stands out more than the others. Larger palettes alternate between
+    /// two lightness levels to keep neighbouring colors distinct.
+    ///
+    /// ```
+    /// # use ansirs::Color;
+    /// let series = Color::categorical(4);
+    /// assert_eq!(series.len(), 4);
+    /// assert!(series.iter().all(|c| series.iter().filter(|&o| o == c).count() == 1));
+    /// ```
+    #[must_use]
+    pub fn categorical(n: usize) -> Vec<Color> {
+        #[allow(clippy::cast_precision_loss)]
+        let step = 360.0 / n.max(1) as f64;
+        #[allow(clippy::cast_precision_loss)]
+        (0..n)
+            .map(|i| {
+                let lightness = if n > 6 && i % 2 == 1 { 0.6 } else { 0.72 };
+                // Start at a warm red rather than OKLCH's 0°, which is a pinkish magenta.
+                Color::from_oklch([lightness, 0.14, 25.0 + step * i as f64])
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColorSpace, HuePath};
+    use pretty_assertions::assert_eq;
+
+    fn hue(color: Color) -> f64 {
+        ColorSpace::Oklch(HuePath::Shorter).to_coords(color)[2]
+    }
+
+    fn hue_distance(a: Color, b: Color) -> f64 {
+        let d = (hue(a) - hue(b)).rem_euclid(360.0);
+        d.min(360.0 - d)
+    }
+
+    #[test]
+    fn harmonies() {
+        let seed = Color::from_rgb(150, 100, 90);
+        let sizes = [
+            (Harmony::Complementary, 2),
+            (Harmony::SplitComplementary, 3),
+            (Harmony::Triadic, 3),
+            (Harmony::Tetradic, 4),
+            (Harmony::Analogous, 3),
+            (Harmony::Monochromatic, 5),
+        ];
+        for (harmony, size) in sizes {
+            let palette = seed.harmony(harmony);
+            assert_eq!(palette.len(), size, "{harmony:?}");
+            assert_eq!(palette[0], seed, "{harmony:?}");
+        }
+
+        let triad = seed.harmony(Harmony::Triadic);
+        assert!((hue_distance(triad[0], triad[1]) - 120.0).abs() < 2.0);
+        assert!((hue_distance(triad[1], triad[2]) - 120.0).abs() < 2.0);
+
+        let mono = seed.harmony(Harmony::Monochromatic);
+        let luminance = mono
+            .iter()
+            .map(Color::relative_luminance)
+            .collect::<Vec<_>>();
+        assert!(luminance[1] < luminance[2] && luminance[2] < luminance[0]);
+        assert!(luminance[0] < luminance[3] && luminance[3] < luminance[4]);
+    }
+
+    #[test]
+    fn categorical() {
+        assert!(Color::categorical(0).is_empty());
+        assert_eq!(Color::categorical(1).len(), 1);
+
+        for n in [3, 8, 12] {
+            let palette = Color::categorical(n);
+            assert_eq!(palette.len(), n);
+            for (i, a) in palette.iter().enumerate() {
+                for b in &palette[i + 1..] {
+                    assert_ne!(a, b, "n = {n}");
+                }
+            }
+        }
+
+        let palette = Color::categorical(3);
+        assert!(hue_distance(palette[0], palette[1]) > 100.0);
+        assert!(hue_distance(palette[1], palette[2]) > 100.0);
+    }
+}
@@ -0,0 +1,269 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use super::space::{
+    hsl_to_srgb, linear_to_oklab, linear_to_srgb, oklab_to_linear, polar_to_rect, rect_to_polar,
+    srgb_to_hsl, srgb_to_linear,
+};
+use crate::Color;
+
+/// Which way around the color wheel hues are interpolated, as in CSS.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HuePath {
+    /// The shorter way around, at most 180°.
+    #[default]
+    Shorter,
+    /// The longer way around, at least 180°.
+    Longer,
+    /// Always with increasing hue.
+    Increasing,
+    /// Always with decreasing hue.
+    Decreasing,
+}
+
+impl HuePath {
+    /// Interpolates between the hues `a` and `b` (in degrees). An undefined (`NaN`) hue takes
+    /// the other hue.
+    fn lerp(self, a: f64, b: f64, t: f64) -> f64 {
+        let (a, b) = match (a.is_nan(), b.is_nan()) {
+            (true, true) => return 0.0,
+            (true, false) => return b.rem_euclid(360.0),
+            (false, true) => return a.rem_euclid(360.0),
+            (false, false) => (a.rem_euclid(360.0), b.rem_euclid(360.0)),
+        };
+        let diff = b - a;
+        let diff = match self {
+            HuePath::Shorter if diff > 180.0 => diff - 360.0,
+            HuePath::Shorter if diff < -180.0 => diff + 360.0,
+            HuePath::Longer if diff > 0.0 && diff < 180.0 => diff - 360.0,
+            HuePath::Longer if diff > -180.0 && diff <= 0.0 => diff + 360.0,
+            HuePath::Increasing if diff < 0.0 => diff + 360.0,
+            HuePath::Decreasing if diff > 0.0 => diff - 360.0,
+            _ => diff,
+        };
+        (a + diff * t).rem_euclid(360.0)
+    }
+}
+
+/// The color space colors are interpolated in, see [`Color::lerp`].
+///
+/// Interpolating in sRGB is what most software does, but tends to produce dull, dark
+/// midpoints. [`ColorSpace::Oklab`] is perceptually uniform, which gives the most even
+/// results, while the cylindrical spaces keep saturation up by going around the hue wheel.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ColorSpace {
+    /// Gamma encoded sRGB, i.e. plain channel arithmetic.
+    Srgb,
+    /// Linear light sRGB, which mixes like light does.
+    LinearSrgb,
+    /// The perceptually uniform `OKLab` space.
+    #[default]
+    Oklab,
+    /// The cylindrical form of `OKLab` (lightness, chroma, hue).
+    Oklch(HuePath),
+    /// HSL (hue, saturation, lightness).
+    Hsl(HuePath),
+}
+
+impl ColorSpace {
+    /// Converts a color to this space's coordinates.
+    pub(crate) fn to_coords(self, color: Color) -> [f64; 3] {
+        let rgb = color.to_unit_rgb();
+        match self {
+            ColorSpace::Srgb => rgb,
+            ColorSpace::LinearSrgb => rgb.map(srgb_to_linear),
+            ColorSpace::Oklab => linear_to_oklab(rgb.map(srgb_to_linear)),
+            ColorSpace::Oklch(_) => rect_to_polar(linear_to_oklab(rgb.map(srgb_to_linear))),
+            ColorSpace::Hsl(_) => srgb_to_hsl(rgb),
+        }
+    }
+
+    /// Converts this space's coordinates back to a color, clamping it to the sRGB gamut.
+    pub(crate) fn to_color(self, coords: [f64; 3]) -> Color {
+        let linear_to_color = |linear: [f64; 3]| Color::from_unit_rgb(linear.map(linear_to_srgb));
+        match self {
+            ColorSpace::Srgb => Color::from_unit_rgb(coords),
+            ColorSpace::LinearSrgb => linear_to_color(coords),
+            ColorSpace::Oklab => linear_to_color(oklab_to_linear(coords)),
+            ColorSpace::Oklch(_) => linear_to_color(oklab_to_linear(polar_to_rect(coords))),
+            ColorSpace::Hsl(_) => Color::from_unit_rgb(hsl_to_srgb(coords)),
+        }
+    }
+
+    /// Interpolates between two sets of coordinates in this space.
+    pub(crate) fn lerp_coords(self, a: [f64; 3], b: [f64; 3], t: f64) -> [f64; 3] {
+        let lerp = |a: f64, b: f64| {
+            // An undefined hue also means zero saturation or chroma, which is a valid value.
+            let (a, b) = (
+                if a.is_nan() { 0.0 } else { a },
+                if b.is_nan() { 0.0 } else { b },
+            );
+            a + (b - a) * t
+        };
+        match self {
+            ColorSpace::Oklch(path) => {
+                [lerp(a[0], b[0]), lerp(a[1], b[1]), path.lerp(a[2], b[2], t)]
+            }
+            ColorSpace::Hsl(path) => [path.lerp(a[0], b[0], t), lerp(a[1], b[1]), lerp(a[2], b[2])],
+            _ => [lerp(a[0], b[0]), lerp(a[1], b[1]), lerp(a[2], b[2])],
+        }
+    }
+}
+
+impl Color {
+    /// Mixes this color with `other` in [`ColorSpace::Oklab`], see [`Color::lerp`].
+    ///
+    /// ```
+    /// # use ansirs::Color;
+    /// let error = Color::from_rgb(255, 0, 0);
+    /// let background = Color::from_rgb(0, 0, 0);
+    /// assert_eq!(error.mix(background, 0.0), error);
+    /// assert_eq!(error.mix(background, 0.5), Color::from_rgb(99, 0, 0));
+    /// ```
+    #[must_use]
+    pub fn mix(&self, other: Color, t: f64) -> Color {
+        self.lerp(other, t, ColorSpace::Oklab)
+    }
+
+    /// Interpolates between this color (at `t = 0.0`) and `other` (at `t = 1.0`) in the given
+    /// color space. `t` is clamped to `0.0..=1.0`.
+    ///
+    /// ```
+    /// # use ansirs::{Color, ColorSpace, HuePath};
+    /// let red = Color::from_rgb(255, 0, 0);
+    /// let blue = Color::from_rgb(0, 0, 255);
+    /// assert_eq!(red.lerp(blue, 0.5, ColorSpace::Srgb), Color::from_rgb(128, 0, 128));
+    /// assert_eq!(red.lerp(blue, 0.5, ColorSpace::LinearSrgb), Color::from_rgb(188, 0, 188));
+    /// // Red to blue the short way goes through magenta, the long way through green.
+    /// assert_eq!(
+    ///     red.lerp(blue, 0.5, ColorSpace::Hsl(HuePath::Shorter)),
+    ///     Color::from_rgb(255, 0, 255)
+    /// );
+    /// assert_eq!(
+    ///     red.lerp(blue, 0.5, ColorSpace::Hsl(HuePath::Longer)),
+    ///     Color::from_rgb(0, 255, 0)
+    /// );
+    /// ```
+    #[must_use]
+    pub fn lerp(&self, other: Color, t: f64, space: ColorSpace) -> Color {
+        let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+        let a = space.to_coords(*self);
+        let b = space.to_coords(other);
+        space.to_color(space.lerp_coords(a, b, t))
+    }
+}
+
+impl std::ops::Add for Color {
+    type Output = Self;
+    /// Adds the channels, saturating at 255.
+    fn add(self, other: Color) -> Self {
+        Color::from_rgb(
+            self.r().saturating_add(other.r()),
+            self.g().saturating_add(other.g()),
+            self.b().saturating_add(other.b()),
+        )
+    }
+}
+impl std::ops::AddAssign for Color {
+    /// Adds the channels, saturating at 255.
+    fn add_assign(&mut self, other: Color) {
+        *self = *self + other;
+    }
+}
+impl std::ops::Sub for Color {
+    type Output = Self;
+    /// Subtracts the channels, saturating at 0.
+    fn sub(self, other: Color) -> Self {
+        Color::from_rgb(
+            self.r().saturating_sub(other.r()),
+            self.g().saturating_sub(other.g()),
+            self.b().saturating_sub(other.b()),
+        )
+    }
+}
+impl std::ops::SubAssign for Color {
+    /// Subtracts the channels, saturating at 0.
+    fn sub_assign(&mut self, other: Color) {
+        *self = *self - other;
+    }
+}
+impl std::ops::Mul<f64> for Color {
+    type Output = Self;
+    /// Scales the channels, rounding and saturating at 0 and 255.
+    fn mul(self, factor: f64) -> Self {
+        Color::from_unit_rgb(self.to_unit_rgb().map(|c| c * factor))
+    }
+}
+impl std::ops::MulAssign<f64> for Color {
+    /// Scales the channels, rounding and saturating at 0 and 255.
+    fn mul_assign(&mut self, factor: f64) {
+        *self = *self * factor;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn hue_paths() {
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+        assert!(close(HuePath::Shorter.lerp(350.0, 10.0, 0.5), 0.0));
+        assert!(close(HuePath::Longer.lerp(350.0, 10.0, 0.5), 180.0));
+        assert!(close(HuePath::Increasing.lerp(10.0, 350.0, 0.25), 95.0));
+        assert!(close(HuePath::Decreasing.lerp(10.0, 350.0, 0.5), 0.0));
+        assert!(close(HuePath::Longer.lerp(f64::NAN, 90.0, 0.5), 90.0));
+    }
+
+    #[test]
+    fn lerping() {
+        let black = Color::from_rgb(0, 0, 0);
+        let white = Color::from_rgb(255, 255, 255);
+        let spaces = [
+            ColorSpace::Srgb,
+            ColorSpace::LinearSrgb,
+            ColorSpace::Oklab,
+            ColorSpace::Oklch(HuePath::Longer),
+            ColorSpace::Hsl(HuePath::Shorter),
+        ];
+        for space in spaces {
+            assert_eq!(black.lerp(white, 0.0, space), black, "{space:?}");
+            assert_eq!(black.lerp(white, 1.0, space), white, "{space:?}");
+            assert_eq!(black.lerp(white, 7.0, space), white, "{space:?}");
+        }
+
+        // Gray to a color keeps the color's hue in the cylindrical spaces.
+        let gray = Color::from_rgb(128, 128, 128);
+        let red = Color::from_rgb(255, 0, 0);
+        let mid = gray.lerp(red, 0.5, ColorSpace::Hsl(HuePath::Longer));
+        assert_eq!(mid, Color::from_rgb(191, 64, 64));
+        let mid = gray.lerp(red, 0.5, ColorSpace::Oklch(HuePath::Shorter));
+        assert!(mid.r() > mid.g() + 40 && mid.r() > mid.b() + 40);
+    }
+
+    #[test]
+    fn operators() {
+        let mut color = Color::from_rgb(200, 100, 0);
+        assert_eq!(
+            color + Color::from_rgb(100, 100, 100),
+            Color::from_rgb(255, 200, 100)
+        );
+        assert_eq!(
+            color - Color::from_rgb(100, 200, 1),
+            Color::from_rgb(100, 0, 0)
+        );
+        assert_eq!(color * 1.5, Color::from_rgb(255, 150, 0));
+        assert_eq!(color * -1.0, Color::from_rgb(0, 0, 0));
+
+        color += Color::from_rgb(1, 1, 1);
+        color -= Color::from_rgb(0, 0, 2);
+        color *= 0.5;
+        assert_eq!(color, Color::from_rgb(101, 51, 0));
+    }
+}
@@ -5,11 +5,21 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 // Private module so who cares
+mod adjust;
 #[allow(clippy::module_inception)]
 mod color;
 mod colors;
+mod contrast;
 mod error;
+mod gradient;
+mod harmony;
+mod mix;
+mod palette;
+mod parse;
+mod rgba;
+mod space;
 mod traits;
+mod vision;
 
 pub mod iter {
     pub use super::colors::iter::*;
@@ -17,8 +27,16 @@ pub mod iter {
 
 pub use color::Color;
 pub use colors::Colors;
+pub use contrast::ContrastTarget;
 pub use error::ColorParseError;
+pub use gradient::{Easing, Gradient};
+pub use harmony::Harmony;
+pub use mix::{ColorSpace, HuePath};
+pub use palette::Palette;
+pub(crate) use parse::parse_x11;
+pub use rgba::{Rgba, RgbaF};
 pub use traits::*;
+pub use vision::ColorDeficiency;
 
 #[cfg(test)]
 mod tests {
@@ -46,18 +64,21 @@ mod tests {
 
     #[test]
     #[should_panic]
+    #[allow(clippy::should_panic_without_expect)]
     fn hex_convert_too_small_panics() {
         let _ = Color::from_hex("#FF00").unwrap();
     }
 
     #[test]
     #[should_panic]
+    #[allow(clippy::should_panic_without_expect)]
     fn hex_convert_too_big_panics() {
         let _ = Color::from_hex("#FF00000").unwrap();
     }
 
     #[test]
     #[should_panic]
+    #[allow(clippy::should_panic_without_expect)]
     fn hex_convert_bad_char_panics() {
         let _ = Color::from_hex("#FF000G").unwrap();
     }
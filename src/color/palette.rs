@@ -0,0 +1,142 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::Color;
+
+/// A terminal color palette, mapping the 256 indexed colors (and the terminal's default
+/// foreground and background) to actual RGB [`Color`]s.
+///
+/// [`Ansi`](crate::Ansi) always stores true colors, so whenever indexed colors are read from
+/// existing escape sequences (e.g. `ESC[31m` or `ESC[38;5;208m`) they are resolved through
+/// a [`Palette`]. The default palette is the standard xterm one, see [`Color::ansi_256_to_color`].
+///
+/// ## Example
+/// ```
+/// # use ansirs::{Color, Palette};
+/// let mut palette = Palette::default();
+/// assert_eq!(palette.get(1), Color::from_rgb(0x80, 0, 0));
+///
+/// palette.set(1, Color::from_rgb(0xcd, 0x31, 0x31));
+/// assert_eq!(palette.get(1), Color::from_rgb(0xcd, 0x31, 0x31));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Palette {
+    colors: [Color; 256],
+    foreground: Color,
+    background: Color,
+}
+
+impl Palette {
+    /// Creates the standard xterm palette, with a light gray foreground on black.
+    #[must_use]
+    pub const fn xterm() -> Self {
+        let mut colors = [Color::from_rgb(0, 0, 0); 256];
+        let mut i = 0;
+        while i < 256 {
+            #[allow(clippy::cast_possible_truncation)]
+            let index = i as u8;
+            colors[i] = Color::ansi_256_to_color(index);
+            i += 1;
+        }
+
+        Self {
+            colors,
+            foreground: Color::from_rgb(0xc0, 0xc0, 0xc0),
+            background: Color::from_rgb(0x00, 0x00, 0x00),
+        }
+    }
+
+    /// Creates a palette using the given 16 basic colors, filling in the remaining 240
+    /// with the standard color cube and grayscale ramp. The default foreground and
+    /// background are taken from colors `7` and `0` respectively.
+    #[must_use]
+    pub const fn from_basic(basic: [Color; 16]) -> Self {
+        let mut palette = Self::xterm();
+        let mut i = 0;
+        while i < 16 {
+            palette.colors[i] = basic[i];
+            i += 1;
+        }
+        palette.foreground = basic[7];
+        palette.background = basic[0];
+        palette
+    }
+
+    /// Gets the color at the given palette index.
+    #[must_use]
+    pub const fn get(&self, index: u8) -> Color {
+        self.colors[index as usize]
+    }
+
+    /// Sets the color at the given palette index.
+    pub fn set(&mut self, index: u8, color: Color) {
+        self.colors[index as usize] = color;
+    }
+
+    /// Gets the terminal's default foreground color.
+    #[must_use]
+    pub const fn foreground(&self) -> Color {
+        self.foreground
+    }
+
+    /// Gets the terminal's default background color.
+    #[must_use]
+    pub const fn background(&self) -> Color {
+        self.background
+    }
+
+    /// Builder function to set the default foreground color.
+    #[must_use]
+    pub const fn with_foreground(mut self, color: Color) -> Self {
+        self.foreground = color;
+        self
+    }
+
+    /// Builder function to set the default background color.
+    #[must_use]
+    pub const fn with_background(mut self, color: Color) -> Self {
+        self.background = color;
+        self
+    }
+
+    /// Gets all 256 indexed colors.
+    #[must_use]
+    pub const fn colors(&self) -> &[Color; 256] {
+        &self.colors
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self::xterm()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn xterm_matches_ansi_256() {
+        let palette = Palette::default();
+        for i in u8::MIN..=u8::MAX {
+            assert_eq!(palette.get(i), Color::ansi_256_to_color(i));
+        }
+    }
+
+    #[test]
+    fn from_basic() {
+        let mut basic = [Color::from_rgb(1, 1, 1); 16];
+        basic[7] = Color::from_rgb(7, 7, 7);
+        let palette = Palette::from_basic(basic);
+        assert_eq!(palette.get(0), Color::from_rgb(1, 1, 1));
+        assert_eq!(palette.get(15), Color::from_rgb(1, 1, 1));
+        assert_eq!(palette.get(16), Color::ansi_256_to_color(16));
+        assert_eq!(palette.foreground(), Color::from_rgb(7, 7, 7));
+        assert_eq!(palette.background(), Color::from_rgb(1, 1, 1));
+    }
+}
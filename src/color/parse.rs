@@ -0,0 +1,495 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::str::FromStr;
+
+use super::space::{
+    hsl_to_srgb, hwb_to_srgb, lab_to_linear, linear_to_srgb, oklab_to_linear, polar_to_rect,
+};
+use crate::{Color, ColorParseError, Colors};
+
+const ANY_COLOR: &str = "a hex color, color function or color name";
+const NUMBER: &str = "a number or percentage";
+const HUE: &str = "a hue (a number or angle)";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Unit {
+    None,
+    Percent,
+    Deg,
+    Rad,
+    Grad,
+    Turn,
+}
+
+/// A single argument of a color function.
+#[derive(Debug, Clone, Copy)]
+struct Component {
+    value: f64,
+    unit: Unit,
+    position: usize,
+}
+
+impl Component {
+    /// Gets the value of a number or percentage, where `100%` is `full`.
+    fn number(self, full: f64) -> Result<f64, ColorParseError> {
+        match self.unit {
+            Unit::None => Ok(self.value),
+            Unit::Percent => Ok(self.value / 100.0 * full),
+            _ => Err(invalid(self.position, NUMBER)),
+        }
+    }
+
+    /// Gets the value of a hue in degrees.
+    fn hue(self) -> Result<f64, ColorParseError> {
+        match self.unit {
+            Unit::None | Unit::Deg => Ok(self.value),
+            Unit::Rad => Ok(self.value.to_degrees()),
+            Unit::Grad => Ok(self.value * 0.9),
+            Unit::Turn => Ok(self.value * 360.0),
+            Unit::Percent => Err(invalid(self.position, HUE)),
+        }
+    }
+}
+
+fn invalid(position: usize, expected: &'static str) -> ColorParseError {
+    ColorParseError::Invalid { position, expected }
+}
+
+/// Scales an X11 color channel of one to four hex digits to `0..=255`.
+pub(crate) fn x11_channel(hex: &str) -> Option<u8> {
+    if hex.is_empty() || hex.len() > 4 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let value = u32::from_str_radix(hex, 16).ok()?;
+    let max = (1u32 << (4 * hex.len())) - 1;
+    u8::try_from((value * 255 + max / 2) / max).ok()
+}
+
+/// Parses the part of an X11 `rgb:R/G/B` color after the `rgb:`.
+pub(crate) fn parse_x11(channels: &str) -> Option<Color> {
+    let mut parts = channels.split('/').map(x11_channel);
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(r), Some(g), Some(b), None) => Some(Color::from_rgb(r?, g?, b?)),
+        _ => None,
+    }
+}
+
+/// Parses `digits` as 3, 4, 6 or 8 hex digits, the last of which may be alpha.
+fn parse_hex(digits: &str, offset: usize) -> Result<(Color, f64), ColorParseError> {
+    const EXPECTED: &str = "3, 4, 6 or 8 hex digits";
+    if let Some(bad) = digits.find(|c: char| !c.is_ascii_hexdigit()) {
+        return Err(invalid(offset + bad, EXPECTED));
+    }
+
+    let channel = |i: usize| -> u8 {
+        let (hi, lo) = if digits.len() <= 4 {
+            (&digits[i..=i], &digits[i..=i])
+        } else {
+            (&digits[2 * i..=2 * i], &digits[2 * i + 1..=2 * i + 1])
+        };
+        // Both are single, already validated, hex digits.
+        u8::from_str_radix(hi, 16).unwrap_or(0) * 16 + u8::from_str_radix(lo, 16).unwrap_or(0)
+    };
+    let alpha = match digits.len() {
+        3 | 6 => 1.0,
+        4 | 8 => f64::from(channel(3)) / 255.0,
+        _ => return Err(invalid(offset, EXPECTED)),
+    };
+
+    Ok((Color::from_rgb(channel(0), channel(1), channel(2)), alpha))
+}
+
+/// A cursor over the input of a color function.
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    /// Skips whitespace, returning `true` if there was any.
+    fn skip_ws(&mut self) -> bool {
+        let rest = self.rest();
+        let trimmed = rest.trim_start();
+        self.pos += rest.len() - trimmed.len();
+        rest.len() != trimmed.len()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.rest().starts_with(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Takes the longest prefix whose characters match `pred`.
+    fn take_while(&mut self, pred: impl Fn(char) -> bool) -> &'a str {
+        let rest = self.rest();
+        let len = rest.find(|c| !pred(c)).unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    /// Parses a number with an optional exponent and unit, or `none` (which is zero). Only
+    /// a `hue` may have an angle unit, which is checked later along with `%`.
+    fn component(&mut self, hue: bool) -> Result<Component, ColorParseError> {
+        let position = self.pos;
+        if self
+            .rest()
+            .get(..4)
+            .is_some_and(|s| s.eq_ignore_ascii_case("none"))
+        {
+            self.pos += 4;
+            return Ok(Component {
+                value: 0.0,
+                unit: Unit::None,
+                position,
+            });
+        }
+
+        let sign = usize::from(self.rest().starts_with(['+', '-']));
+        self.pos += sign;
+        let digits = self.take_while(|c| c.is_ascii_digit() || c == '.');
+        let rest = self.rest();
+        if let Some(exponent) = rest.strip_prefix(['e', 'E']) {
+            let exponent = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+            if exponent.starts_with(|c: char| c.is_ascii_digit()) {
+                self.pos += rest.len() - exponent.len();
+                self.take_while(|c| c.is_ascii_digit());
+            }
+        }
+        let value = self.input[position..self.pos]
+            .parse::<f64>()
+            .ok()
+            .filter(|value| !digits.is_empty() && value.is_finite())
+            .ok_or_else(|| invalid(position, NUMBER))?;
+
+        let expected = if hue { HUE } else { NUMBER };
+        let unit = match self.take_while(|c| c.is_ascii_alphabetic() || c == '%') {
+            "" => Unit::None,
+            "%" => Unit::Percent,
+            unit if unit.eq_ignore_ascii_case("deg") => Unit::Deg,
+            unit if unit.eq_ignore_ascii_case("rad") => Unit::Rad,
+            unit if unit.eq_ignore_ascii_case("grad") => Unit::Grad,
+            unit if unit.eq_ignore_ascii_case("turn") => Unit::Turn,
+            _ => return Err(invalid(position, expected)),
+        };
+
+        Ok(Component {
+            value,
+            unit,
+            position,
+        })
+    }
+
+    /// Parses the arguments of a color function, after the `(`: three components separated
+    /// by whitespace or commas, an optional alpha after `/` (or a comma) and the `)`. `hue` is
+    /// the index of the component that is a hue, if any.
+    fn arguments(
+        &mut self,
+        hue: Option<usize>,
+    ) -> Result<([Component; 3], Option<Component>), ColorParseError> {
+        let mut commas = None;
+        let mut components = [Component {
+            value: 0.0,
+            unit: Unit::None,
+            position: 0,
+        }; 3];
+
+        for (i, component) in components.iter_mut().enumerate() {
+            let spaced = self.skip_ws();
+            if self.rest().starts_with(')') {
+                return Err(invalid(self.pos, "3 components"));
+            }
+            if i > 0 {
+                let separator = self.pos;
+                let comma = self.eat(',');
+                match commas {
+                    None => commas = Some(comma),
+                    Some(expected) if expected != comma => {
+                        return Err(invalid(separator, "consistent separators"));
+                    }
+                    Some(_) => {}
+                }
+                if !comma && !spaced {
+                    return Err(invalid(self.pos, "`,` or whitespace"));
+                }
+                self.skip_ws();
+            }
+            *component = self.component(hue == Some(i))?;
+        }
+
+        self.skip_ws();
+        let alpha = if self.eat('/') || (commas == Some(true) && self.eat(',')) {
+            self.skip_ws();
+            let alpha = self.component(false)?;
+            self.skip_ws();
+            Some(alpha)
+        } else {
+            None
+        };
+
+        if !self.eat(')') {
+            return Err(invalid(self.pos, "`)`"));
+        }
+        Ok((components, alpha))
+    }
+}
+
+/// Parses a color function call, e.g. `rgb(1 2 3)`. `name_end` is the offset of the `(`.
+fn parse_function(input: &str, name_end: usize) -> Result<(Color, f64), ColorParseError> {
+    const FUNCTIONS: [&str; 9] = [
+        "rgb", "rgba", "hsl", "hsla", "hwb", "lab", "lch", "oklab", "oklch",
+    ];
+    let name = input[..name_end].to_ascii_lowercase();
+    if !FUNCTIONS.contains(&name.as_str()) {
+        return Err(invalid(
+            0,
+            "one of `rgb`, `rgba`, `hsl`, `hsla`, `hwb`, `lab`, `lch`, `oklab` or `oklch`",
+        ));
+    }
+    let mut parser = Parser {
+        input,
+        pos: name_end + 1,
+    };
+    let hue = match name.as_str() {
+        "hsl" | "hsla" | "hwb" => Some(0),
+        "lch" | "oklch" => Some(2),
+        _ => None,
+    };
+    let ([a, b, c], alpha) = parser.arguments(hue)?;
+    parser.skip_ws();
+    if !parser.rest().is_empty() {
+        return Err(invalid(parser.pos, "the end of the input"));
+    }
+
+    let linear_to_color = |linear: [f64; 3]| Color::from_unit_rgb(linear.map(linear_to_srgb));
+    let color = match name.as_str() {
+        "rgb" | "rgba" => Color::from_unit_rgb([
+            a.number(255.0)? / 255.0,
+            b.number(255.0)? / 255.0,
+            c.number(255.0)? / 255.0,
+        ]),
+        "hsl" | "hsla" => Color::from_unit_rgb(hsl_to_srgb([
+            a.hue()?,
+            b.number(100.0)? / 100.0,
+            c.number(100.0)? / 100.0,
+        ])),
+        "hwb" => Color::from_unit_rgb(hwb_to_srgb([
+            a.hue()?,
+            b.number(100.0)? / 100.0,
+            c.number(100.0)? / 100.0,
+        ])),
+        "lab" => linear_to_color(lab_to_linear([
+            a.number(100.0)?,
+            b.number(125.0)?,
+            c.number(125.0)?,
+        ])),
+        "lch" => linear_to_color(lab_to_linear(polar_to_rect([
+            a.number(100.0)?,
+            b.number(150.0)?,
+            c.hue()?,
+        ]))),
+        "oklab" => linear_to_color(oklab_to_linear([
+            a.number(1.0)?,
+            b.number(0.4)?,
+            c.number(0.4)?,
+        ])),
+        "oklch" => linear_to_color(oklab_to_linear(polar_to_rect([
+            a.number(1.0)?,
+            b.number(0.4)?,
+            c.hue()?,
+        ]))),
+        _ => unreachable!("checked above"),
+    };
+
+    let alpha = match alpha {
+        Some(alpha) => alpha.number(1.0)?.clamp(0.0, 1.0),
+        None => 1.0,
+    };
+    Ok((color, alpha))
+}
+
+/// Parses a color along with its alpha (`1.0` if not given), see [`Color::parse`].
+pub(crate) fn parse_color(input: &str) -> Result<(Color, f64), ColorParseError> {
+    let trimmed = input.trim_start();
+    let offset = input.len() - trimmed.len();
+    let trimmed = trimmed.trim_end();
+    let shift = |err: ColorParseError| match err {
+        ColorParseError::Invalid { position, expected } => ColorParseError::Invalid {
+            position: position + offset,
+            expected,
+        },
+        other => other,
+    };
+
+    if let Some(digits) = trimmed.strip_prefix('#') {
+        return parse_hex(digits, offset + 1);
+    }
+    if let Some(digits) = trimmed
+        .strip_prefix("0x")
+        .or_else(|| trimmed.strip_prefix("0X"))
+    {
+        return match digits.len() {
+            6 | 8 => parse_hex(digits, offset + 2),
+            _ => Err(invalid(offset + 2, "6 or 8 hex digits")),
+        };
+    }
+    if trimmed
+        .get(..4)
+        .is_some_and(|s| s.eq_ignore_ascii_case("rgb:"))
+    {
+        return parse_x11(&trimmed[4..])
+            .map(|color| (color, 1.0))
+            .ok_or_else(|| invalid(offset + 4, "`R/G/B` with 1 to 4 hex digits each"));
+    }
+    if let Some(paren) = trimmed.find('(') {
+        if trimmed[..paren].bytes().all(|b| b.is_ascii_alphabetic()) {
+            return parse_function(trimmed, paren).map_err(shift);
+        }
+    }
+    if let Some(named) = Colors::from_name_ignore_case(trimmed) {
+        return Ok((named.into_color(), 1.0));
+    }
+    if matches!(trimmed.len(), 3 | 6 | 8) && trimmed.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return parse_hex(trimmed, offset);
+    }
+
+    Err(invalid(offset, ANY_COLOR))
+}
+
+impl Color {
+    /// Parses a color from any of the common ways of writing one:
+    /// - Hex colors: `#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`, `0xRRGGBB` and, for backwards
+    ///   compatibility with [`Color::from_hex`], `rrggbb` without the `#`.
+    /// - CSS color functions: `rgb()`, `rgba()`, `hsl()`, `hsla()`, `hwb()`, `lab()`,
+    ///   `lch()`, `oklab()` and `oklch()`, with either the modern (space separated,
+    ///   `/ alpha`) or legacy (comma separated) syntax.
+    /// - X11 colors: `rgb:R/G/B`, with one to four hex digits for each channel.
+    /// - Named colors, see [`Colors`], ignoring case and spaces.
+    ///
+    /// Alpha is accepted but ignored, and colors outside of the sRGB gamut are clamped.
+    ///
+    /// ```
+    /// # use ansirs::{Color, ColorParseError};
+    /// let red = Color::from_rgb(255, 0, 0);
+    /// for input in [
+    ///     "#f00",
+    ///     "#ff0000cc",
+    ///     "0xFF0000",
+    ///     "rgb(255 0 0)",
+    ///     "rgb(100%, 0%, 0%)",
+    ///     "rgba(255, 0, 0, 0.5)",
+    ///     "hsl(0deg 100% 50% / 50%)",
+    ///     "hwb(0 0% 0%)",
+    ///     "oklch(62.8% 0.2577 29.23)",
+    ///     "rgb:ff/00/00",
+    ///     "Red",
+    /// ] {
+    ///     assert_eq!(Color::parse(input), Ok(red), "{input}");
+    /// }
+    ///
+    /// let err = Color::parse("rgb(255 0 zero)").unwrap_err();
+    /// assert_eq!(err.position(), Some(10));
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "Invalid color at position 10, expected a number or percentage"
+    /// );
+    /// ```
+    ///
+    /// ## Errors
+    /// - [`ColorParseError::Invalid`] with the position of the problem and what was expected.
+    pub fn parse(input: &str) -> Result<Self, ColorParseError> {
+        parse_color(input).map(|(color, _)| color)
+    }
+}
+
+impl FromStr for Color {
+    type Err = ColorParseError;
+
+    /// Parses a color, see [`Color::parse`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn err(input: &str) -> (usize, &'static str) {
+        match Color::parse(input) {
+            Err(ColorParseError::Invalid { position, expected }) => (position, expected),
+            other => panic!("expected an error for {input:?}, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn forms() {
+        let cases = [
+            ("#1e90ff", (30, 144, 255)),
+            ("1E90FF", (30, 144, 255)),
+            ("  #abcd ", (0xaa, 0xbb, 0xcc)),
+            ("rgb(30, 144, 255)", (30, 144, 255)),
+            ("RGB( 30 144 255 / 1 )", (30, 144, 255)),
+            ("rgb(none 50% 300)", (0, 128, 255)),
+            ("hsl(0.5turn, 100%, 25%)", (0, 128, 128)),
+            ("hsl(120 50 50)", (64, 191, 64)),
+            ("hwb(240 20% 40%)", (51, 51, 153)),
+            ("lab(50% 0 0)", (119, 119, 119)),
+            ("lch(54.29 106.84 40.85)", (255, 0, 0)),
+            ("oklab(1 0 0)", (255, 255, 255)),
+            ("oklch(0 0 none)", (0, 0, 0)),
+            ("rgb:ffff/8/00", (255, 136, 0)),
+            ("rgb(1e2 0 0)", (100, 0, 0)),
+            ("rgb(2.55E2 +1e-1% .5e1)", (255, 0, 5)),
+            ("hsl(0.5E3deg 100% 50%)", (0, 255, 85)),
+            ("dodger blue", (30, 144, 255)),
+        ];
+        for (input, rgb) in cases {
+            assert_eq!(Color::parse(input), Ok(Color::from(rgb)), "{input}");
+        }
+        assert_eq!("#1e90ff".parse::<Color>(), Ok(Color::from((30, 144, 255))));
+    }
+
+    #[test]
+    fn alpha() {
+        assert_eq!(parse_color("#0000").map(|(_, a)| a), Ok(0.0));
+        assert_eq!(parse_color("rgb(1 2 3 / 25%)").map(|(_, a)| a), Ok(0.25));
+        assert_eq!(parse_color("rgba(1, 2, 3, 2)").map(|(_, a)| a), Ok(1.0));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(err(""), (0, ANY_COLOR));
+        assert_eq!(err("  nope"), (2, ANY_COLOR));
+        assert_eq!(err("#12345"), (1, "3, 4, 6 or 8 hex digits"));
+        assert_eq!(err("#12g"), (3, "3, 4, 6 or 8 hex digits"));
+        assert_eq!(err("0x123"), (2, "6 or 8 hex digits"));
+        assert_eq!(err("rgb:ff/ff"), (4, "`R/G/B` with 1 to 4 hex digits each"));
+        assert_eq!(err("rgb(1 2)"), (7, "3 components"));
+        assert_eq!(err("rgb(1 2,3)"), (7, "consistent separators"));
+        assert_eq!(err("rgb(1 2 x)"), (8, NUMBER));
+        assert_eq!(err("rgb(1, 2 3)"), (9, "consistent separators"));
+        assert_eq!(err("rgb(1 2 3"), (9, "`)`"));
+        assert_eq!(err("rgb(1 2 3) x"), (11, "the end of the input"));
+        assert_eq!(err("hsl(10% 1 1)"), (4, HUE));
+        assert_eq!(err("rgb(1 2 3deg)"), (8, NUMBER));
+        assert_eq!(err("rgb(1 2 3px)"), (8, NUMBER));
+        assert_eq!(err("rgb(1 2 3e)"), (8, NUMBER));
+        assert_eq!(err("rgb(1 2 1e999)"), (8, NUMBER));
+        assert_eq!(err("hsl(1px 2 3)"), (4, HUE));
+        assert_eq!(err("oklch(1 2 3px)"), (10, HUE));
+        assert_eq!(err(" color(srgb 1 0 0)").0, 1);
+    }
+}
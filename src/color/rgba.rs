@@ -0,0 +1,240 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::str::FromStr;
+
+use super::{parse::parse_color, space::unit_to_u8};
+use crate::{Color, ColorParseError};
+
+/// A color with an alpha channel, 8 bits per channel. The alpha is straight (not
+/// premultiplied), `0` is fully transparent and `255` fully opaque.
+///
+/// Terminals can't show translucent colors, so they have to be composited onto an opaque
+/// background first, see [`Rgba::over`].
+///
+/// ## Example
+/// ```
+/// # use ansirs::{Color, Rgba};
+/// let selection: Rgba = "#3399ff80".parse().unwrap();
+/// assert_eq!(selection.over(Color::from_rgb(0, 0, 0)), Color::from_rgb(26, 77, 128));
+/// assert_eq!(selection.over(Color::from_rgb(255, 255, 255)), Color::from_rgb(153, 204, 255));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rgba {
+    /// The red channel.
+    pub r: u8,
+    /// The green channel.
+    pub g: u8,
+    /// The blue channel.
+    pub b: u8,
+    /// The alpha channel.
+    pub a: u8,
+}
+
+/// A color with an alpha channel, with `f64` channels in `0.0..=1.0`. The alpha is straight
+/// (not premultiplied).
+///
+/// Use this instead of [`Rgba`] when compositing several layers, to avoid rounding after
+/// each one.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RgbaF {
+    /// The red channel.
+    pub r: f64,
+    /// The green channel.
+    pub g: f64,
+    /// The blue channel.
+    pub b: f64,
+    /// The alpha channel.
+    pub a: f64,
+}
+
+impl Rgba {
+    /// A fully transparent black.
+    pub const TRANSPARENT: Self = Self::new(0, 0, 0, 0);
+
+    /// Creates a new [`Rgba`].
+    #[must_use]
+    pub const fn new(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// Creates a new [`Rgba`] from a [`Color`] and an alpha value.
+    #[must_use]
+    pub const fn from_color(color: Color, a: u8) -> Self {
+        Self::new(color.r(), color.g(), color.b(), a)
+    }
+
+    /// Parses a color with an optional alpha, accepting everything [`Color::parse`] does,
+    /// e.g. `#rrggbbaa` or `rgb(255 0 0 / 50%)`.
+    ///
+    /// ## Errors
+    /// - [`ColorParseError::Invalid`] if the input is not a valid color.
+    pub fn parse(input: &str) -> Result<Self, ColorParseError> {
+        let (color, alpha) = parse_color(input)?;
+        Ok(RgbaF::from_color(color, alpha).to_rgba())
+    }
+
+    /// Gets the color without its alpha.
+    #[must_use]
+    pub const fn color(&self) -> Color {
+        Color::from_rgb(self.r, self.g, self.b)
+    }
+
+    /// Returns `true` if this color is fully opaque.
+    #[must_use]
+    pub const fn is_opaque(&self) -> bool {
+        self.a == u8::MAX
+    }
+
+    /// Create a hex string (`#RRGGBBAA`) from this color.
+    #[must_use]
+    pub fn as_hex(&self) -> String {
+        format!("#{:02X}{:02X}{:02X}{:02X}", self.r, self.g, self.b, self.a)
+    }
+
+    /// Converts this color to floating point channels.
+    #[must_use]
+    pub fn to_float(&self) -> RgbaF {
+        let [r, g, b, a] = [self.r, self.g, self.b, self.a].map(|c| f64::from(c) / 255.0);
+        RgbaF { r, g, b, a }
+    }
+
+    /// Composites this color over an opaque `background` (Porter-Duff "over").
+    #[must_use]
+    pub fn over(&self, background: Color) -> Color {
+        self.to_float().over(background)
+    }
+
+    /// Composites this color over a (possibly translucent) `background` (Porter-Duff "over").
+    ///
+    /// ```
+    /// # use ansirs::Rgba;
+    /// let half_red = Rgba::new(255, 0, 0, 128);
+    /// assert_eq!(half_red.over_rgba(half_red), Rgba::new(255, 0, 0, 192));
+    /// assert_eq!(Rgba::TRANSPARENT.over_rgba(half_red), half_red);
+    /// ```
+    #[must_use]
+    pub fn over_rgba(&self, background: Rgba) -> Rgba {
+        self.to_float().over_rgba(background.to_float()).to_rgba()
+    }
+}
+
+impl RgbaF {
+    /// Creates a new [`RgbaF`] from a [`Color`] and an alpha value in `0.0..=1.0`.
+    #[must_use]
+    pub fn from_color(color: Color, a: f64) -> Self {
+        let [r, g, b] = color.to_unit_rgb();
+        Self { r, g, b, a }
+    }
+
+    /// Converts this color to 8 bit channels, clamping and rounding each channel.
+    #[must_use]
+    pub fn to_rgba(&self) -> Rgba {
+        let [r, g, b, a] = [self.r, self.g, self.b, self.a].map(unit_to_u8);
+        Rgba::new(r, g, b, a)
+    }
+
+    /// Composites this color over an opaque `background` (Porter-Duff "over").
+    #[must_use]
+    pub fn over(&self, background: Color) -> Color {
+        let a = self.a.clamp(0.0, 1.0);
+        let [br, bg, bb] = background.to_unit_rgb();
+        Color::from_unit_rgb([
+            self.r * a + br * (1.0 - a),
+            self.g * a + bg * (1.0 - a),
+            self.b * a + bb * (1.0 - a),
+        ])
+    }
+
+    /// Composites this color over a (possibly translucent) `background` (Porter-Duff "over").
+    #[must_use]
+    pub fn over_rgba(&self, background: RgbaF) -> RgbaF {
+        let (sa, ba) = (self.a.clamp(0.0, 1.0), background.a.clamp(0.0, 1.0));
+        let a = sa + ba * (1.0 - sa);
+        if a <= 0.0 {
+            return RgbaF::default();
+        }
+        let channel = |s: f64, b: f64| (s * sa + b * ba * (1.0 - sa)) / a;
+        RgbaF {
+            r: channel(self.r, background.r),
+            g: channel(self.g, background.g),
+            b: channel(self.b, background.b),
+            a,
+        }
+    }
+}
+
+impl From<Color> for Rgba {
+    /// Converts an opaque [`Color`].
+    fn from(color: Color) -> Self {
+        Self::from_color(color, u8::MAX)
+    }
+}
+
+impl From<Rgba> for RgbaF {
+    fn from(rgba: Rgba) -> Self {
+        rgba.to_float()
+    }
+}
+
+impl From<(u8, u8, u8, u8)> for Rgba {
+    fn from((r, g, b, a): (u8, u8, u8, u8)) -> Self {
+        Self::new(r, g, b, a)
+    }
+}
+
+impl FromStr for Rgba {
+    type Err = ColorParseError;
+
+    /// Parses a color with an optional alpha, see [`Rgba::parse`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parsing() {
+        assert_eq!(
+            Rgba::parse("#11223344"),
+            Ok(Rgba::new(0x11, 0x22, 0x33, 0x44))
+        );
+        assert_eq!(Rgba::parse("red"), Ok(Rgba::new(255, 0, 0, 255)));
+        assert_eq!(
+            Rgba::parse("hsl(120 100% 50% / 0.2)"),
+            Ok(Rgba::new(0, 255, 0, 51))
+        );
+        assert!(Rgba::parse("#1234567").is_err());
+        assert_eq!(Rgba::new(1, 2, 3, 4).as_hex(), "#01020304");
+    }
+
+    #[test]
+    fn compositing() {
+        let bg = Color::from_rgb(10, 20, 30);
+        assert_eq!(
+            Rgba::from(Color::from_rgb(1, 2, 3)).over(bg),
+            Color::from_rgb(1, 2, 3)
+        );
+        assert_eq!(Rgba::TRANSPARENT.over(bg), bg);
+        assert_eq!(
+            Rgba::TRANSPARENT.over_rgba(Rgba::TRANSPARENT),
+            Rgba::TRANSPARENT
+        );
+
+        // Compositing layers one by one is the same as compositing their composite.
+        let top = RgbaF::from(Rgba::new(200, 0, 0, 100));
+        let middle = RgbaF::from(Rgba::new(0, 0, 200, 150));
+        let stacked = top.over(middle.over(bg));
+        let combined = top.over_rgba(middle).over(bg);
+        assert_eq!(stacked, combined);
+    }
+}
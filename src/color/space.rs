@@ -0,0 +1,264 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Conversions between color spaces. All values are `f64`s, with sRGB channels in `0.0..=1.0`
+//! and hues in degrees. Results are not clamped, so out of gamut colors survive round trips.
+
+use super::Color;
+
+impl Color {
+    /// Creates a color from sRGB channels in `0.0..=1.0`, clamping out of gamut values.
+    pub(crate) fn from_unit_rgb([r, g, b]: [f64; 3]) -> Self {
+        Self::from_rgb(unit_to_u8(r), unit_to_u8(g), unit_to_u8(b))
+    }
+
+    /// Gets the sRGB channels of this color in `0.0..=1.0`.
+    pub(crate) fn to_unit_rgb(self) -> [f64; 3] {
+        [self.r(), self.g(), self.b()].map(|c| f64::from(c) / 255.0)
+    }
+}
+
+/// Converts a channel in `0.0..=1.0` to `0..=255`, clamping out of range values.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub(crate) fn unit_to_u8(c: f64) -> u8 {
+    // NaN (e.g. from an achromatic hue) becomes 0.
+    (c.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Converts a gamma encoded sRGB channel to linear light.
+pub(crate) fn srgb_to_linear(c: f64) -> f64 {
+    if c.abs() <= 0.040_45 {
+        c / 12.92
+    } else {
+        ((c.abs() + 0.055) / 1.055).powf(2.4).copysign(c)
+    }
+}
+
+/// Converts a linear light channel to gamma encoded sRGB.
+pub(crate) fn linear_to_srgb(c: f64) -> f64 {
+    if c.abs() <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        (1.055 * c.abs().powf(1.0 / 2.4) - 0.055).copysign(c)
+    }
+}
+
+fn mul(m: [[f64; 3]; 3], v: [f64; 3]) -> [f64; 3] {
+    m.map(|row| row[0] * v[0] + row[1] * v[1] + row[2] * v[2])
+}
+
+/// Converts linear sRGB to `OKLab`.
+pub(crate) fn linear_to_oklab(rgb: [f64; 3]) -> [f64; 3] {
+    let lms = mul(
+        [
+            [0.412_221_470_8, 0.536_332_536_3, 0.051_445_992_9],
+            [0.211_903_498_2, 0.680_699_545_1, 0.107_396_956_6],
+            [0.088_302_461_9, 0.281_718_837_6, 0.629_978_700_5],
+        ],
+        rgb,
+    );
+    mul(
+        [
+            [0.210_454_255_3, 0.793_617_785_0, -0.004_072_046_8],
+            [1.977_998_495_1, -2.428_592_205_0, 0.450_593_709_9],
+            [0.025_904_037_1, 0.782_771_766_2, -0.808_675_766_0],
+        ],
+        lms.map(f64::cbrt),
+    )
+}
+
+/// Converts `OKLab` to linear sRGB.
+pub(crate) fn oklab_to_linear(lab: [f64; 3]) -> [f64; 3] {
+    let lms = mul(
+        [
+            [1.0, 0.396_337_777_4, 0.215_803_757_3],
+            [1.0, -0.105_561_345_8, -0.063_854_172_8],
+            [1.0, -0.089_484_177_5, -1.291_485_548_0],
+        ],
+        lab,
+    );
+    mul(
+        [
+            [4.076_741_662_1, -3.307_711_591_3, 0.230_969_929_2],
+            [-1.268_438_004_6, 2.609_757_401_1, -0.341_319_396_5],
+            [-0.004_196_086_3, -0.703_418_614_7, 1.707_614_701_0],
+        ],
+        lms.map(|c| c * c * c),
+    )
+}
+
+/// Converts CIE Lab (D50 white point, as used by CSS) to linear sRGB.
+pub(crate) fn lab_to_linear([l, a, b]: [f64; 3]) -> [f64; 3] {
+    const KAPPA: f64 = 24389.0 / 27.0;
+    const EPSILON: f64 = 216.0 / 24389.0;
+    const WHITE: [f64; 3] = [0.3457 / 0.3585, 1.0, (1.0 - 0.3457 - 0.3585) / 0.3585];
+
+    let fy = (l + 16.0) / 116.0;
+    let fx = fy + a / 500.0;
+    let fz = fy - b / 200.0;
+    let inverse = |f: f64| {
+        let cubed = f * f * f;
+        if cubed > EPSILON {
+            cubed
+        } else {
+            (116.0 * f - 16.0) / KAPPA
+        }
+    };
+    let y = if l > KAPPA * EPSILON {
+        fy * fy * fy
+    } else {
+        l / KAPPA
+    };
+    let xyz_d50 = [inverse(fx) * WHITE[0], y * WHITE[1], inverse(fz) * WHITE[2]];
+
+    // Bradford adaptation from D50 to D65, then XYZ to linear sRGB.
+    let xyz_d65 = mul(
+        [
+            [
+                0.955_473_421_488_075,
+                -0.023_098_454_948_764_71,
+                0.063_259_243_200_570_72,
+            ],
+            [
+                -0.028_369_709_333_863_7,
+                1.009_995_398_081_304_1,
+                0.021_041_441_191_917_323,
+            ],
+            [
+                0.012_314_014_864_481_998,
+                -0.020_507_649_298_898_964,
+                1.330_365_926_242_124,
+            ],
+        ],
+        xyz_d50,
+    );
+    mul(
+        [
+            [
+                3.240_969_941_904_522_6,
+                -1.537_383_177_570_094,
+                -0.498_610_760_293_003_4,
+            ],
+            [
+                -0.969_243_636_280_879_6,
+                1.875_967_501_507_720_2,
+                0.041_555_057_407_175_59,
+            ],
+            [
+                0.055_630_079_696_993_66,
+                -0.203_976_958_888_976_52,
+                1.056_971_514_242_878_6,
+            ],
+        ],
+        xyz_d65,
+    )
+}
+
+/// Converts cylindrical coordinates (lightness, chroma, hue) to rectangular ones.
+pub(crate) fn polar_to_rect([l, c, h]: [f64; 3]) -> [f64; 3] {
+    let (sin, cos) = h.to_radians().sin_cos();
+    [l, c * cos, c * sin]
+}
+
+/// Converts rectangular coordinates to cylindrical ones (lightness, chroma, hue). The hue of
+/// an achromatic color is `NaN`.
+#[allow(clippy::many_single_char_names)]
+pub(crate) fn rect_to_polar([l, a, b]: [f64; 3]) -> [f64; 3] {
+    let c = a.hypot(b);
+    let h = if c < 1e-9 {
+        f64::NAN
+    } else {
+        b.atan2(a).to_degrees().rem_euclid(360.0)
+    };
+    [l, c, h]
+}
+
+/// Converts HSL (saturation and lightness in `0.0..=1.0`) to sRGB.
+#[allow(clippy::many_single_char_names)]
+pub(crate) fn hsl_to_srgb([h, s, l]: [f64; 3]) -> [f64; 3] {
+    let h = if h.is_nan() { 0.0 } else { h.rem_euclid(360.0) };
+    let a = s * l.min(1.0 - l);
+    let f = |n: f64| {
+        let k = (n + h / 30.0) % 12.0;
+        l - a * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0)
+    };
+    [f(0.0), f(8.0), f(4.0)]
+}
+
+/// Converts sRGB to HSL (saturation and lightness in `0.0..=1.0`). The hue of an achromatic
+/// color is `NaN`.
+#[allow(clippy::many_single_char_names)]
+pub(crate) fn srgb_to_hsl([r, g, b]: [f64; 3]) -> [f64; 3] {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let l = f64::midpoint(max, min);
+    let d = max - min;
+    if d < 1e-9 {
+        return [f64::NAN, 0.0, l];
+    }
+
+    let s = if l <= 0.0 || l >= 1.0 {
+        0.0
+    } else {
+        (max - l) / l.min(1.0 - l)
+    };
+    let h = if (max - r).abs() < f64::EPSILON {
+        (g - b) / d + if g < b { 6.0 } else { 0.0 }
+    } else if (max - g).abs() < f64::EPSILON {
+        (b - r) / d + 2.0
+    } else {
+        (r - g) / d + 4.0
+    };
+    [h * 60.0, s, l]
+}
+
+/// Converts HWB (whiteness and blackness in `0.0..=1.0`) to sRGB.
+pub(crate) fn hwb_to_srgb([h, w, b]: [f64; 3]) -> [f64; 3] {
+    if w + b >= 1.0 {
+        let gray = w / (w + b);
+        return [gray; 3];
+    }
+    hsl_to_srgb([h, 1.0, 0.5]).map(|c| c * (1.0 - w - b) + w)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn close(a: [f64; 3], b: [f64; 3]) -> bool {
+        a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-4)
+    }
+
+    #[test]
+    fn round_trips() {
+        for color in [(255, 0, 0), (12, 200, 99), (128, 128, 128), (0, 0, 0)] {
+            let color = Color::from(color);
+            let rgb = color.to_unit_rgb();
+            let linear = rgb.map(srgb_to_linear);
+            assert!(close(linear.map(linear_to_srgb), rgb));
+            assert!(close(oklab_to_linear(linear_to_oklab(linear)), linear));
+            assert_eq!(Color::from_unit_rgb(hsl_to_srgb(srgb_to_hsl(rgb))), color);
+        }
+    }
+
+    #[test]
+    fn known_values() {
+        let white = linear_to_oklab([1.0; 3]);
+        assert!(close(white, [1.0, 0.0, 0.0]));
+        assert!(close(lab_to_linear([100.0, 0.0, 0.0]), [1.0; 3]));
+        assert!(close(srgb_to_hsl([0.0, 0.5, 1.0]), [210.0, 1.0, 0.5]));
+        assert_eq!(
+            Color::from_unit_rgb(hwb_to_srgb([0.0, 0.6, 0.6])),
+            Color::from_rgb(128, 128, 128)
+        );
+        assert!(rect_to_polar([0.5, 0.0, 0.0])[2].is_nan());
+        assert!(close(
+            polar_to_rect(rect_to_polar([0.5, 0.1, -0.1])),
+            [0.5, 0.1, -0.1]
+        ));
+    }
+}
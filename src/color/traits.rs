@@ -75,8 +75,8 @@ impl ToColor for (u8, u8, u8) {
 impl TryFrom<&str> for Color {
     type Error = ColorParseError;
 
-    /// Attempts to parse the given string as a hex string into a [`Color`].
+    /// Attempts to parse the given string into a [`Color`], see [`Color::parse`].
     fn try_from(input: &str) -> Result<Self, ColorParseError> {
-        Color::from_hex(input)
+        Color::parse(input)
     }
 }
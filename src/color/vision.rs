@@ -0,0 +1,215 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use super::space::{linear_to_srgb, srgb_to_linear};
+use crate::{Ansi, Color, StyledText};
+
+/// A color vision deficiency, see [`Color::simulate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ColorDeficiency {
+    /// Missing or weak red cones ("red-blindness").
+    Protanopia,
+    /// Missing or weak green cones ("green-blindness"), the most common deficiency.
+    Deuteranopia,
+    /// Missing or weak blue cones ("blue-blindness").
+    Tritanopia,
+    /// No color vision at all, only lightness is seen.
+    Achromatopsia,
+}
+
+impl ColorDeficiency {
+    /// All deficiencies, e.g. to preview output for each of them.
+    pub const ALL: [Self; 4] = [
+        Self::Protanopia,
+        Self::Deuteranopia,
+        Self::Tritanopia,
+        Self::Achromatopsia,
+    ];
+
+    /// The simulation matrix for linear sRGB at full severity. The dichromacy matrices are
+    /// from Machado, Oliveira and Fernandes (2009).
+    fn matrix(self) -> [[f64; 3]; 3] {
+        match self {
+            ColorDeficiency::Protanopia => [
+                [0.152_286, 1.052_583, -0.204_868],
+                [0.114_503, 0.786_281, 0.099_216],
+                [-0.003_882, -0.048_116, 1.051_998],
+            ],
+            ColorDeficiency::Deuteranopia => [
+                [0.367_322, 0.860_646, -0.227_968],
+                [0.280_085, 0.672_501, 0.047_413],
+                [-0.011_820, 0.042_940, 0.968_881],
+            ],
+            ColorDeficiency::Tritanopia => [
+                [1.255_528, -0.076_749, -0.178_779],
+                [-0.078_411, 0.930_809, 0.147_602],
+                [0.004_733, 0.691_367, 0.303_900],
+            ],
+            ColorDeficiency::Achromatopsia => [[0.2126, 0.7152, 0.0722]; 3],
+        }
+    }
+}
+
+impl Color {
+    /// Simulates how this color looks with the given color vision deficiency. `severity`
+    /// goes from `0.0` (normal vision) to `1.0` (complete loss of the affected cones), and is
+    /// clamped to that range.
+    ///
+    /// ```
+    /// # use ansirs::{Color, ColorDeficiency};
+    /// let red = Color::from_rgb(255, 0, 0);
+    /// let green = Color::from_rgb(0, 255, 0);
+    /// assert_eq!(red.simulate(ColorDeficiency::Deuteranopia, 0.0), red);
+    /// assert_eq!(red.simulate(ColorDeficiency::Achromatopsia, 1.0), Color::from_rgb(127, 127, 127));
+    ///
+    /// // With deuteranopia red and green are both seen as shades of yellow.
+    /// let red = red.simulate(ColorDeficiency::Deuteranopia, 1.0);
+    /// let green = green.simulate(ColorDeficiency::Deuteranopia, 1.0);
+    /// assert!(red.r() > red.b() && green.r() > green.b());
+    /// ```
+    #[must_use]
+    pub fn simulate(&self, deficiency: ColorDeficiency, severity: f64) -> Color {
+        Color::from_unit_rgb(simulate_linear(*self, deficiency, severity).map(linear_to_srgb))
+    }
+
+    /// Corrects this color so it is easier to tell apart for people with the given color
+    /// vision deficiency ("daltonization", after Fidaner et al.). The information lost in the
+    /// [simulation](Color::simulate) is shifted into channels that are still seen. Colors that
+    /// look the same either way are returned unchanged.
+    ///
+    /// ```
+    /// # use ansirs::{Color, ColorDeficiency};
+    /// let gray = Color::from_rgb(128, 128, 128);
+    /// assert_eq!(gray.daltonize(ColorDeficiency::Protanopia, 1.0), gray);
+    ///
+    /// // Red and green become easier to tell apart.
+    /// let red = Color::from_rgb(200, 60, 40);
+    /// let green = Color::from_rgb(60, 160, 40);
+    /// let distance = |a: Color, b: Color| (i32::from(a.b()) - i32::from(b.b())).abs();
+    /// let seen = |c: Color| c.simulate(ColorDeficiency::Deuteranopia, 1.0);
+    /// let fixed = |c: Color| seen(c.daltonize(ColorDeficiency::Deuteranopia, 1.0));
+    /// assert!(distance(fixed(red), fixed(green)) > distance(seen(red), seen(green)));
+    /// ```
+    #[must_use]
+    pub fn daltonize(&self, deficiency: ColorDeficiency, severity: f64) -> Color {
+        let original = self.to_unit_rgb().map(srgb_to_linear);
+        let simulated = simulate_linear(*self, deficiency, severity);
+        let [er, eg, eb] = [
+            original[0] - simulated[0],
+            original[1] - simulated[1],
+            original[2] - simulated[2],
+        ];
+        let shifted = [0.0, 0.7 * er + eg, 0.7 * er + eb];
+        Color::from_unit_rgb(
+            [
+                original[0] + shifted[0],
+                original[1] + shifted[1],
+                original[2] + shifted[2],
+            ]
+            .map(linear_to_srgb),
+        )
+    }
+}
+
+/// Simulates a deficiency, returning linear sRGB.
+fn simulate_linear(color: Color, deficiency: ColorDeficiency, severity: f64) -> [f64; 3] {
+    let severity = if severity.is_nan() {
+        0.0
+    } else {
+        severity.clamp(0.0, 1.0)
+    };
+    let rgb = color.to_unit_rgb().map(srgb_to_linear);
+    let simulated = deficiency
+        .matrix()
+        .map(|row| row[0] * rgb[0] + row[1] * rgb[1] + row[2] * rgb[2]);
+    [0, 1, 2].map(|i| rgb[i] + (simulated[i] - rgb[i]) * severity)
+}
+
+impl Ansi {
+    /// Simulates the foreground and background colors of this style, see [`Color::simulate`].
+    #[must_use]
+    pub fn simulate(self, deficiency: ColorDeficiency, severity: f64) -> Self {
+        self.map_fg(|c| c.simulate(deficiency, severity))
+            .map_bg(|c| c.simulate(deficiency, severity))
+    }
+
+    /// Daltonizes the foreground and background colors of this style, see
+    /// [`Color::daltonize`].
+    #[must_use]
+    pub fn daltonize(self, deficiency: ColorDeficiency, severity: f64) -> Self {
+        self.map_fg(|c| c.daltonize(deficiency, severity))
+            .map_bg(|c| c.daltonize(deficiency, severity))
+    }
+}
+
+impl StyledText {
+    /// Simulates the colors of every span, see [`Color::simulate`]. Together with
+    /// [`StyledText::parse`] this previews any terminal output.
+    ///
+    /// ```
+    /// # use ansirs::{ColorDeficiency, StyledText};
+    /// let output = StyledText::parse("\x1b[32mPASS\x1b[0m \x1b[31mFAIL\x1b[0m");
+    /// let preview = output.simulate(ColorDeficiency::Achromatopsia, 1.0);
+    /// assert_eq!(preview.plain_text(), "PASS FAIL");
+    /// assert_eq!(
+    ///     preview.to_string(),
+    ///     "\x1b[38;2;109;109;109mPASS\x1b[0m \x1b[38;2;60;60;60mFAIL\x1b[0m"
+    /// );
+    /// ```
+    #[must_use]
+    pub fn simulate(&self, deficiency: ColorDeficiency, severity: f64) -> Self {
+        self.map_styles(|style| style.simulate(deficiency, severity))
+    }
+
+    /// Daltonizes the colors of every span, see [`Color::daltonize`].
+    #[must_use]
+    pub fn daltonize(&self, deficiency: ColorDeficiency, severity: f64) -> Self {
+        self.map_styles(|style| style.daltonize(deficiency, severity))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn neutral_colors_are_unchanged() {
+        let grays = [(0, 0, 0), (255, 255, 255), (100, 100, 100)].map(Color::from);
+        for deficiency in ColorDeficiency::ALL {
+            for gray in grays {
+                assert_eq!(gray.simulate(deficiency, 1.0), gray, "{deficiency:?}");
+                assert_eq!(gray.daltonize(deficiency, 1.0), gray, "{deficiency:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn severity() {
+        let orange = Color::from_rgb(255, 128, 0);
+        let full = orange.simulate(ColorDeficiency::Protanopia, 1.0);
+        let half = orange.simulate(ColorDeficiency::Protanopia, 0.5);
+        assert_eq!(orange.simulate(ColorDeficiency::Protanopia, 2.0), full);
+        assert_eq!(orange.simulate(ColorDeficiency::Protanopia, -1.0), orange);
+        assert!(half != orange && half != full);
+    }
+
+    #[test]
+    fn styles() {
+        let ansi = Ansi::new().bold().fg((255, 0, 0)).bg((0, 0, 255));
+        let simulated = ansi.simulate(ColorDeficiency::Achromatopsia, 1.0);
+        assert_eq!(simulated.flags(), ansi.flags());
+        let fg = simulated.foreground().unwrap();
+        let bg = simulated.background().unwrap();
+        assert!(fg.r() == fg.g() && fg.g() == fg.b());
+        assert!(bg.r() == bg.g() && bg.g() == bg.b());
+        assert_eq!(
+            Ansi::new().simulate(ColorDeficiency::Tritanopia, 1.0),
+            Ansi::new()
+        );
+    }
+}
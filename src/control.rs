@@ -0,0 +1,361 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Typed cursor movement and screen control sequences.
+//!
+//! Every type here implements [`Display`](std::fmt::Display), writing its escape sequence
+//! straight to the formatter without allocating, so they can be used directly in
+//! `print!`/`write!`. Positions are zero-based, the conversion to the one-based positions
+//! terminals expect is done when writing.
+//!
+//! ## Example
+//! ```
+//! # use ansirs::control::{CursorUp, EraseLine, HideCursor, ResetLine};
+//! let mut out = String::new();
+//! for percent in [0, 50, 100] {
+//!     out += &format!("{ResetLine}{HideCursor}Progress: {percent}%");
+//! }
+//! assert!(out.starts_with("\r\x1b[2K\x1b[?25lProgress: 0%"));
+//!
+//! assert_eq!(CursorUp(2).to_string(), "\x1b[2A");
+//! assert_eq!(EraseLine::ToEnd.to_string(), "\x1b[K");
+//! ```
+
+use std::fmt;
+
+macro_rules! fixed_sequence {
+    ($(#[$meta:meta])* $name:ident => $seq:literal) => {
+        $(#[$meta])*
+        #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+        pub struct $name;
+
+        impl $name {
+            /// Gets the escape sequence.
+            #[must_use]
+            pub const fn as_str(&self) -> &'static str {
+                $seq
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str($seq)
+            }
+        }
+    };
+}
+
+macro_rules! counted_sequence {
+    ($(#[$meta:meta])* $name:ident => $final:literal) => {
+        $(#[$meta])*
+        ///
+        /// A count of zero writes nothing, since terminals treat `0` the same as `1`.
+        #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+        pub struct $name(pub u16);
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                if self.0 == 0 {
+                    Ok(())
+                } else {
+                    write!(f, concat!("\x1b[{}", $final), self.0)
+                }
+            }
+        }
+    };
+}
+
+counted_sequence!(
+    /// Moves the cursor up the given number of rows (`CUU`).
+    CursorUp => "A"
+);
+counted_sequence!(
+    /// Moves the cursor down the given number of rows (`CUD`).
+    CursorDown => "B"
+);
+counted_sequence!(
+    /// Moves the cursor right the given number of columns (`CUF`).
+    CursorForward => "C"
+);
+counted_sequence!(
+    /// Moves the cursor left the given number of columns (`CUB`).
+    CursorBack => "D"
+);
+counted_sequence!(
+    /// Moves the cursor to the start of the line the given number of rows down (`CNL`).
+    CursorNextLine => "E"
+);
+counted_sequence!(
+    /// Moves the cursor to the start of the line the given number of rows up (`CPL`).
+    CursorPrevLine => "F"
+);
+counted_sequence!(
+    /// Scrolls the contents of the scroll region up the given number of rows (`SU`).
+    ScrollUp => "S"
+);
+counted_sequence!(
+    /// Scrolls the contents of the scroll region down the given number of rows (`SD`).
+    ScrollDown => "T"
+);
+
+/// Moves the cursor to the given (zero-based) column of the current row (`CHA`).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CursorColumn(pub u16);
+
+impl fmt::Display for CursorColumn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "\x1b[{}G", u32::from(self.0) + 1)
+    }
+}
+
+/// Moves the cursor to the given (zero-based) row and column (`CUP`).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CursorTo {
+    /// The row to move to, starting at zero.
+    pub row: u16,
+    /// The column to move to, starting at zero.
+    pub col: u16,
+}
+
+impl CursorTo {
+    /// Creates a new [`CursorTo`].
+    #[must_use]
+    pub const fn new(row: u16, col: u16) -> Self {
+        Self { row, col }
+    }
+}
+
+impl fmt::Display for CursorTo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "\x1b[{};{}H",
+            u32::from(self.row) + 1,
+            u32::from(self.col) + 1
+        )
+    }
+}
+
+fixed_sequence!(
+    /// Moves the cursor to the top left corner.
+    CursorHome => "\x1b[H"
+);
+fixed_sequence!(
+    /// Saves the cursor position and style (`DECSC`).
+    SaveCursor => "\x1b7"
+);
+fixed_sequence!(
+    /// Restores the cursor position and style saved with [`SaveCursor`] (`DECRC`).
+    RestoreCursor => "\x1b8"
+);
+fixed_sequence!(
+    /// Shows the cursor (`DECTCEM`).
+    ShowCursor => "\x1b[?25h"
+);
+fixed_sequence!(
+    /// Hides the cursor (`DECTCEM`).
+    HideCursor => "\x1b[?25l"
+);
+fixed_sequence!(
+    /// Switches to the alternate screen buffer, saving the cursor and the main screen.
+    EnterAlternateScreen => "\x1b[?1049h"
+);
+fixed_sequence!(
+    /// Switches back to the main screen buffer, restoring the cursor.
+    LeaveAlternateScreen => "\x1b[?1049l"
+);
+fixed_sequence!(
+    /// Resets the scroll region to the whole screen.
+    ResetScrollRegion => "\x1b[r"
+);
+fixed_sequence!(
+    /// Moves the cursor to the start of the line and erases it, ready to redraw a
+    /// progress bar or status line.
+    ResetLine => "\r\x1b[2K"
+);
+
+/// Which part of the current line to erase (`EL`). The cursor does not move.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EraseLine {
+    /// From the cursor to the end of the line.
+    #[default]
+    ToEnd,
+    /// From the start of the line to the cursor.
+    ToStart,
+    /// The whole line.
+    All,
+}
+
+impl EraseLine {
+    /// Gets the escape sequence.
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::ToEnd => "\x1b[K",
+            Self::ToStart => "\x1b[1K",
+            Self::All => "\x1b[2K",
+        }
+    }
+}
+
+impl fmt::Display for EraseLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Which part of the screen to erase (`ED`). The cursor does not move.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EraseScreen {
+    /// From the cursor to the end of the screen.
+    #[default]
+    ToEnd,
+    /// From the start of the screen to the cursor.
+    ToStart,
+    /// The whole screen.
+    All,
+    /// The scrollback buffer (supported by most, but not all, terminals).
+    Scrollback,
+}
+
+impl EraseScreen {
+    /// Gets the escape sequence.
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::ToEnd => "\x1b[J",
+            Self::ToStart => "\x1b[1J",
+            Self::All => "\x1b[2J",
+            Self::Scrollback => "\x1b[3J",
+        }
+    }
+}
+
+impl fmt::Display for EraseScreen {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The cursor shape, set with `DECSCUSR`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CursorShape {
+    /// The terminal's (or user's) default shape.
+    #[default]
+    Default,
+    /// A blinking block.
+    BlinkingBlock,
+    /// A steady block.
+    SteadyBlock,
+    /// A blinking underline.
+    BlinkingUnderline,
+    /// A steady underline.
+    SteadyUnderline,
+    /// A blinking vertical bar.
+    BlinkingBar,
+    /// A steady vertical bar.
+    SteadyBar,
+}
+
+impl CursorShape {
+    /// Gets the escape sequence.
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Default => "\x1b[0 q",
+            Self::BlinkingBlock => "\x1b[1 q",
+            Self::SteadyBlock => "\x1b[2 q",
+            Self::BlinkingUnderline => "\x1b[3 q",
+            Self::SteadyUnderline => "\x1b[4 q",
+            Self::BlinkingBar => "\x1b[5 q",
+            Self::SteadyBar => "\x1b[6 q",
+        }
+    }
+}
+
+impl fmt::Display for CursorShape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Restricts scrolling to the given (zero-based, inclusive) rows (`DECSTBM`). Note that
+/// terminals move the cursor to the top left corner when the region is set.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ScrollRegion {
+    /// The first row of the region, starting at zero.
+    pub top: u16,
+    /// The last row of the region, starting at zero.
+    pub bottom: u16,
+}
+
+impl ScrollRegion {
+    /// Creates a new [`ScrollRegion`].
+    #[must_use]
+    pub const fn new(top: u16, bottom: u16) -> Self {
+        Self { top, bottom }
+    }
+}
+
+impl fmt::Display for ScrollRegion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "\x1b[{};{}r",
+            u32::from(self.top) + 1,
+            u32::from(self.bottom) + 1
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Screen;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn sequences() {
+        assert_eq!(CursorDown(1).to_string(), "\x1b[1B");
+        assert_eq!(CursorBack(0).to_string(), "");
+        assert_eq!(CursorColumn(0).to_string(), "\x1b[1G");
+        assert_eq!(CursorTo::new(u16::MAX, 4).to_string(), "\x1b[65536;5H");
+        assert_eq!(ScrollRegion::new(1, 9).to_string(), "\x1b[2;10r");
+        assert_eq!(CursorShape::SteadyBar.to_string(), "\x1b[6 q");
+        assert_eq!(EraseScreen::All.as_str(), "\x1b[2J");
+        assert_eq!(LeaveAlternateScreen.as_str(), "\x1b[?1049l");
+    }
+
+    #[test]
+    fn interpreted_by_screen() {
+        let mut screen = Screen::new(10, 4);
+        screen.feed(format!(
+            "hello{}{}X{}{}Y{}{}Z",
+            CursorTo::new(2, 3),
+            SaveCursor,
+            CursorHome,
+            CursorForward(1),
+            RestoreCursor,
+            CursorUp(1),
+        ));
+        assert_eq!(screen.text(), "hYllo\n   Z\n   X\n");
+
+        screen.feed(format!(
+            "{}{ResetLine}{}",
+            CursorTo::new(0, 4),
+            EraseScreen::ToEnd
+        ));
+        assert_eq!(screen.text(), "");
+
+        screen.feed(format!("{}a\r\nb\r\nc\r\nd", ScrollRegion::new(1, 2)));
+        assert_eq!(screen.text(), "a\nc\nd\n");
+        assert_eq!(screen.scroll_region(), (1, 2));
+
+        screen.feed(format!("{}{}", ScrollDown(1), CursorColumn(7)));
+        assert_eq!(screen.text(), "a\n\nc\n");
+        assert_eq!(screen.cursor(), (2, 7));
+    }
+}
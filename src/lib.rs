@@ -21,10 +21,20 @@
 )]
 #![allow(dead_code, clippy::module_name_repetitions)]
 
+#[macro_use]
+mod macros;
+
 mod ansi;
 mod color;
 mod styled;
 
+pub mod control;
+pub mod osc;
+pub mod probe;
+pub mod query;
+#[cfg(feature = "testing")]
+pub mod testing;
+
 /// Contains code for iterating over named colors.
 pub mod iter {
     pub use crate::color::iter::*;
@@ -0,0 +1,217 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+/// Renders the escape sequence of a `const` [`Ansi`](crate::Ansi) expression at compile time,
+/// producing a `&'static str`.
+///
+/// ## Example
+/// ```
+/// # use ansirs::{ansi_str, Ansi, Color, Colors};
+/// const WARNING: &str = ansi_str!(Ansi::new().fg_color(Colors::Orange.into_color()).bold());
+/// const RESET: &str = Ansi::reset();
+///
+/// assert_eq!(WARNING, "\x1b[1;38;2;255;165;0m");
+/// println!("{WARNING}Careful!{RESET}");
+/// ```
+#[macro_export]
+macro_rules! ansi_str {
+    ($style:expr) => {{
+        const __ANSIRS_BYTES: $crate::AnsiBytes = $crate::Ansi::encode(&$style);
+        const __ANSIRS_STR: &str = __ANSIRS_BYTES.as_str();
+        __ANSIRS_STR
+    }};
+}
+
+/// Creates a [`Color`](crate::Color) from a hex string literal, a [`Colors`](crate::Colors) name
+/// or an `(r, g, b)` tuple.
+///
+/// Hex strings are validated at compile time, so a typo is a build error instead of a
+/// runtime [`ColorParseError`](crate::ColorParseError). The result is a constant and can
+/// be used in `const` items.
+///
+/// ## Example
+/// ```
+/// # use ansirs::{color, Color};
+/// const DODGER_BLUE: Color = color!("#1e90ff");
+///
+/// assert_eq!(DODGER_BLUE, color!(DodgerBlue));
+/// assert_eq!(color!("#222"), color!((0x22, 0x22, 0x22)));
+/// ```
+///
+/// Invalid hex strings and unknown names fail to compile:
+/// ```compile_fail
+/// # use ansirs::color;
+/// let oops = color!("#1e90fg");
+/// ```
+/// ```compile_fail
+/// # use ansirs::color;
+/// let oops = color!(DodgerBlu);
+/// ```
+#[macro_export]
+macro_rules! color {
+    (($r:expr, $g:expr, $b:expr $(,)?)) => {{
+        const __ANSIRS_COLOR: $crate::Color = $crate::Color::from_rgb($r, $g, $b);
+        __ANSIRS_COLOR
+    }};
+    ($r:expr, $g:expr, $b:expr $(,)?) => {
+        $crate::color!(($r, $g, $b))
+    };
+    ($name:ident) => {{
+        const __ANSIRS_COLOR: $crate::Color = $crate::Colors::$name.into_color();
+        __ANSIRS_COLOR
+    }};
+    ($hex:literal) => {{
+        const __ANSIRS_COLOR: $crate::Color = $crate::Color::hex($hex);
+        __ANSIRS_COLOR
+    }};
+}
+
+/// Creates an [`Ansi`](crate::Ansi) style from a list of attributes and colors.
+///
+/// Attributes (`bold`, `dim`, `italic`, `underline`, `blink`, `reverse`, `strike`) can be listed
+/// in any order, colors are given as `fg = <color>` and `bg = <color>` where `<color>` is
+/// anything accepted by [`color!`](crate::color). Entries may be separated by whitespace
+/// or commas. Everything is validated at compile time and the result is a constant, so it
+/// can be used in `const` items.
+///
+/// Unlike the [`Ansi`](crate::Ansi) builder functions, attributes are always *set*, so
+/// listing one twice does not toggle it back off.
+///
+/// ## Example
+/// ```
+/// # use ansirs::{style, Ansi, Colors};
+/// const HEADER: Ansi = style!(bold underline fg = DodgerBlue, bg = "#222");
+///
+/// assert_eq!(
+///     HEADER,
+///     Ansi::new().bold().underline().fg(Colors::DodgerBlue).bg((0x22, 0x22, 0x22))
+/// );
+/// assert_eq!(style!(), Ansi::new());
+/// ```
+///
+/// Unknown attributes fail to compile:
+/// ```compile_fail
+/// # use ansirs::style;
+/// let oops = style!(bold wobbly);
+/// ```
+#[macro_export]
+macro_rules! style {
+    (@acc [$acc:expr]) => { $acc };
+    (@acc [$acc:expr] , $($rest:tt)*) => {
+        $crate::style!(@acc [$acc] $($rest)*)
+    };
+    (@acc [$acc:expr] fg = $color:tt $($rest:tt)*) => {
+        $crate::style!(@acc [$acc.fg_color($crate::color!($color))] $($rest)*)
+    };
+    (@acc [$acc:expr] bg = $color:tt $($rest:tt)*) => {
+        $crate::style!(@acc [$acc.bg_color($crate::color!($color))] $($rest)*)
+    };
+    (@acc [$acc:expr] bold $($rest:tt)*) => {
+        $crate::style!(@acc [$acc.with_flags($crate::AnsiFlags::BOLD)] $($rest)*)
+    };
+    (@acc [$acc:expr] dim $($rest:tt)*) => {
+        $crate::style!(@acc [$acc.with_flags($crate::AnsiFlags::DIM)] $($rest)*)
+    };
+    (@acc [$acc:expr] italic $($rest:tt)*) => {
+        $crate::style!(@acc [$acc.with_flags($crate::AnsiFlags::ITALIC)] $($rest)*)
+    };
+    (@acc [$acc:expr] underline $($rest:tt)*) => {
+        $crate::style!(@acc [$acc.with_flags($crate::AnsiFlags::UNDERLINE)] $($rest)*)
+    };
+    (@acc [$acc:expr] blink $($rest:tt)*) => {
+        $crate::style!(@acc [$acc.with_flags($crate::AnsiFlags::BLINK)] $($rest)*)
+    };
+    (@acc [$acc:expr] reverse $($rest:tt)*) => {
+        $crate::style!(@acc [$acc.with_flags($crate::AnsiFlags::REVERSE)] $($rest)*)
+    };
+    (@acc [$acc:expr] strike $($rest:tt)*) => {
+        $crate::style!(@acc [$acc.with_flags($crate::AnsiFlags::STRIKE)] $($rest)*)
+    };
+    (@acc [$acc:expr] $other:tt $($rest:tt)*) => {
+        ::core::compile_error!(::core::concat!(
+            "unknown style attribute `",
+            ::core::stringify!($other),
+            "`"
+        ))
+    };
+    ($($tokens:tt)*) => {{
+        const __ANSIRS_STYLE: $crate::Ansi = $crate::style!(@acc [$crate::Ansi::new()] $($tokens)*);
+        __ANSIRS_STYLE
+    }};
+}
+
+/// Asserts that two strings containing escape sequences look the same in a terminal,
+/// regardless of how the escape sequences were emitted (see [`semantically_eq`](crate::semantically_eq)).
+/// On failure both sides are shown in the readable semantic form, see
+/// [`testing::snapshot`](crate::testing::snapshot).
+///
+/// Requires the `testing` feature.
+///
+/// ## Example
+/// ```
+/// # use ansirs::{assert_styled_eq, Ansi};
+/// let output = ansirs::style_text("done", Ansi::new().bold().underline());
+/// assert_styled_eq!(output, "\x1b[4m\x1b[1mdone\x1b[0m");
+/// assert_styled_eq!(output, "\x1b[1;4mdone", "bold and underlined");
+/// ```
+#[cfg(feature = "testing")]
+#[macro_export]
+macro_rules! assert_styled_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::testing::assert_styled_eq_impl(
+            ::core::convert::AsRef::<str>::as_ref(&$left),
+            ::core::convert::AsRef::<str>::as_ref(&$right),
+            ::core::option::Option::None,
+        )
+    };
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        $crate::testing::assert_styled_eq_impl(
+            ::core::convert::AsRef::<str>::as_ref(&$left),
+            ::core::convert::AsRef::<str>::as_ref(&$right),
+            ::core::option::Option::Some(::core::format_args!($($arg)+)),
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Ansi, AnsiFlags, Color, Colors};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn color_macro() {
+        const HEX: Color = color!("#1e90ff");
+        assert_eq!(HEX, Colors::DodgerBlue.into_color());
+        assert_eq!(color!(DodgerBlue), HEX);
+        assert_eq!(color!("1E90FF"), HEX);
+        assert_eq!(color!((30, 144, 255)), HEX);
+        assert_eq!(color!(30, 144, 255), HEX);
+    }
+
+    #[test]
+    fn style_macro() {
+        const HEADER: Ansi = style!(bold underline fg = DodgerBlue, bg = "#222");
+        assert_eq!(
+            HEADER,
+            Ansi::new()
+                .bold()
+                .underline()
+                .fg(Colors::DodgerBlue)
+                .bg((0x22, 0x22, 0x22))
+        );
+        assert_eq!(style!(), Ansi::new());
+        assert_eq!(style!(bold, bold), Ansi::new().bold());
+        assert_eq!(
+            style!(italic blink reverse strike fg = (1, 2, 3)),
+            Ansi::from_parts(
+                Some(Color::from_rgb(1, 2, 3)),
+                None,
+                AnsiFlags::ITALIC | AnsiFlags::BLINK | AnsiFlags::REVERSE | AnsiFlags::STRIKE
+            )
+        );
+        assert_eq!(crate::ansi_str!(style!(bg = Black)), "\u{1b}[48;2;0;0;0m");
+    }
+}
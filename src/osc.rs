@@ -0,0 +1,615 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Typed operating system commands (`OSC`), e.g. setting the window title, sending desktop
+//! notifications or writing to the clipboard.
+//!
+//! Every command implements [`Display`](std::fmt::Display), writing the sequence terminated
+//! by `ST` (`ESC \`). Use [`OscCommand::terminator`] to use `BEL` instead (some older
+//! terminals only understand `BEL`), and [`OscCommand::passthrough`] to wrap the sequence so
+//! it makes it through `tmux` or GNU `screen` to the outer terminal.
+//!
+//! ## Example
+//! ```
+//! # use ansirs::osc::{OscCommand, Passthrough, SetTitle, Terminator};
+//! assert_eq!(SetTitle::new("build").to_string(), "\x1b]2;build\x1b\\");
+//! assert_eq!(
+//!     SetTitle::new("build").terminator(Terminator::Bel).to_string(),
+//!     "\x1b]2;build\x07"
+//! );
+//! assert_eq!(
+//!     SetTitle::new("build").passthrough(Passthrough::Tmux).to_string(),
+//!     "\x1bPtmux;\x1b\x1b]2;build\x1b\x1b\\\x1b\\"
+//! );
+//! ```
+
+use std::fmt::{self, Write};
+
+use crate::Color;
+
+/// How an `OSC` sequence is terminated.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Terminator {
+    /// The string terminator, `ESC \`.
+    #[default]
+    St,
+    /// The bell character, `BEL`. Understood by more (older) terminals, but not standard.
+    Bel,
+}
+
+impl Terminator {
+    /// Gets the terminator as a string.
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::St => "\x1b\\",
+            Self::Bel => "\x07",
+        }
+    }
+}
+
+/// How a sequence is wrapped so a terminal multiplexer passes it on to the outer terminal.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Passthrough {
+    /// The sequence is written as is.
+    #[default]
+    None,
+    /// Wrapped in `ESC P tmux; ... ESC \`, with every `ESC` doubled. Requires
+    /// `allow-passthrough` to be enabled in tmux.
+    Tmux,
+    /// Wrapped in a `DCS` string (`ESC P ... ESC \`) for GNU `screen`. The inner sequence is
+    /// always terminated by `BEL`, since an `ESC \` would end the `DCS` string early. Screen
+    /// limits the length of a `DCS` string (to 768 bytes, or 256 in older versions), so long
+    /// sequences such as [clipboard](SetClipboard) contents are split over several of them,
+    /// which the outer terminal receives back to back.
+    Screen,
+}
+
+/// The most bytes written in a single `DCS` string for [`Passthrough::Screen`].
+const SCREEN_CHUNK_LEN: usize = 256;
+
+/// An `OSC` command, i.e. what goes between `ESC ]` and the terminator.
+pub trait OscCommand {
+    /// Writes the command's payload, e.g. `2;title`.
+    ///
+    /// ## Errors
+    /// Returns an error if writing to `f` fails.
+    fn write_payload(&self, f: &mut dyn Write) -> fmt::Result;
+
+    /// Wraps the command in an [`Osc`] using the given terminator.
+    fn terminator(self, terminator: Terminator) -> Osc<Self>
+    where
+        Self: Sized,
+    {
+        Osc::new(self).terminator(terminator)
+    }
+
+    /// Wraps the command in an [`Osc`] using the given passthrough.
+    fn passthrough(self, passthrough: Passthrough) -> Osc<Self>
+    where
+        Self: Sized,
+    {
+        Osc::new(self).passthrough(passthrough)
+    }
+}
+
+impl<C: OscCommand + ?Sized> OscCommand for &C {
+    fn write_payload(&self, f: &mut dyn Write) -> fmt::Result {
+        (**self).write_payload(f)
+    }
+}
+
+/// An [`OscCommand`] along with how it should be written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Osc<C> {
+    command: C,
+    terminator: Terminator,
+    passthrough: Passthrough,
+}
+
+impl<C: OscCommand> Osc<C> {
+    /// Creates a new [`Osc`], terminated by `ST` without any passthrough.
+    pub fn new(command: C) -> Self {
+        Self {
+            command,
+            terminator: Terminator::default(),
+            passthrough: Passthrough::default(),
+        }
+    }
+
+    /// Builder function to set the terminator.
+    #[must_use]
+    pub fn terminator(mut self, terminator: Terminator) -> Self {
+        self.terminator = terminator;
+        self
+    }
+
+    /// Builder function to set the passthrough.
+    #[must_use]
+    pub fn passthrough(mut self, passthrough: Passthrough) -> Self {
+        self.passthrough = passthrough;
+        self
+    }
+
+    /// Gets the wrapped command.
+    #[must_use]
+    pub fn command(&self) -> &C {
+        &self.command
+    }
+}
+
+impl<C: OscCommand> fmt::Display for Osc<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.passthrough == Passthrough::None {
+            f.write_str("\x1b]")?;
+            self.command.write_payload(f)?;
+            return f.write_str(self.terminator.as_str());
+        }
+
+        let mut seq = String::from("\x1b]");
+        self.command.write_payload(&mut seq)?;
+        if self.passthrough == Passthrough::Tmux {
+            seq.push_str(self.terminator.as_str());
+            return write!(f, "\x1bPtmux;{}\x1b\\", seq.replace('\x1b', "\x1b\x1b"));
+        }
+
+        seq.push_str(Terminator::Bel.as_str());
+        let mut rest = seq.as_str();
+        while !rest.is_empty() {
+            let mut len = rest.len().min(SCREEN_CHUNK_LEN);
+            while !rest.is_char_boundary(len) {
+                len -= 1;
+            }
+            let (chunk, after) = rest.split_at(len);
+            write!(f, "\x1bP{chunk}\x1b\\")?;
+            rest = after;
+        }
+        Ok(())
+    }
+}
+
+macro_rules! osc_display {
+    ($($name:ty),+ $(,)?) => {
+        $(
+            impl fmt::Display for $name {
+                fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    Osc::new(self).fmt(f)
+                }
+            }
+        )+
+    };
+}
+
+/// Writes `text` without control characters, which would end or break the sequence.
+fn write_sanitized(f: &mut dyn Write, text: &str) -> fmt::Result {
+    text.chars()
+        .filter(|c| !c.is_control())
+        .try_for_each(|c| f.write_char(c))
+}
+
+/// Writes `color` in the `rgb:rr/gg/bb` form used by the palette commands.
+fn write_rgb(f: &mut dyn Write, color: Color) -> fmt::Result {
+    write!(
+        f,
+        "rgb:{:02x}/{:02x}/{:02x}",
+        color.r(),
+        color.g(),
+        color.b()
+    )
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Writes `data` encoded as (padded) base64.
+fn write_base64(f: &mut dyn Write, data: &[u8]) -> fmt::Result {
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = u32::from(bytes[0]) << 16 | u32::from(bytes[1]) << 8 | u32::from(bytes[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                f.write_char(char::from(BASE64[(n >> (18 - 6 * i)) as usize & 0x3f]))?;
+            } else {
+                f.write_char('=')?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// What [`SetTitle`] changes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TitleTarget {
+    /// The window (or tab) title, `OSC 2`.
+    #[default]
+    Window,
+    /// Both the icon name and the window title, `OSC 0`.
+    IconAndWindow,
+}
+
+/// Sets the window or tab title (`OSC 0` / `OSC 2`). Control characters are removed.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct SetTitle {
+    title: String,
+    target: TitleTarget,
+}
+
+impl SetTitle {
+    /// Creates a command setting the window title.
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            target: TitleTarget::Window,
+        }
+    }
+
+    /// Builder function to set what the title is set on.
+    #[must_use]
+    pub fn target(mut self, target: TitleTarget) -> Self {
+        self.target = target;
+        self
+    }
+}
+
+impl OscCommand for SetTitle {
+    fn write_payload(&self, f: &mut dyn Write) -> fmt::Result {
+        f.write_str(match self.target {
+            TitleTarget::Window => "2;",
+            TitleTarget::IconAndWindow => "0;",
+        })?;
+        write_sanitized(f, &self.title)
+    }
+}
+
+/// Reports the current working directory to the terminal (`OSC 7`), so new tabs or windows
+/// can open in the same directory.
+///
+/// The path is written as a `file://` URL, percent-encoding anything but unreserved
+/// characters and `/`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct WorkingDirectory {
+    host: String,
+    path: String,
+}
+
+impl WorkingDirectory {
+    /// Creates a command reporting the given (absolute) path, with an empty host.
+    pub fn new(path: impl Into<String>) -> Self {
+        Self {
+            host: String::new(),
+            path: path.into(),
+        }
+    }
+
+    /// Builder function to set the host name. Terminals may ignore directories on other hosts.
+    #[must_use]
+    pub fn host(mut self, host: impl Into<String>) -> Self {
+        self.host = host.into();
+        self
+    }
+}
+
+impl OscCommand for WorkingDirectory {
+    fn write_payload(&self, f: &mut dyn Write) -> fmt::Result {
+        f.write_str("7;file://")?;
+        write_sanitized(f, &self.host)?;
+        if !self.path.starts_with('/') {
+            f.write_char('/')?;
+        }
+        for byte in self.path.bytes() {
+            if byte.is_ascii_alphanumeric() || b"-._~/".contains(&byte) {
+                f.write_char(char::from(byte))?;
+            } else {
+                write!(f, "%{byte:02X}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Sends a desktop notification. Control characters are removed.
+///
+/// Terminals disagree on the sequence used: iTerm2, `ConEmu`, Windows Terminal and others
+/// use `OSC 9` (which only has a message), while `urxvt`, foot, Ghostty and others use
+/// `OSC 777` (which also has a title).
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct Notify {
+    title: Option<String>,
+    body: String,
+}
+
+impl Notify {
+    /// Creates an `OSC 9` notification with the given message.
+    pub fn new(body: impl Into<String>) -> Self {
+        Self {
+            title: None,
+            body: body.into(),
+        }
+    }
+
+    /// Creates an `OSC 777` notification with the given title and message.
+    pub fn with_title(title: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            title: Some(title.into()),
+            body: body.into(),
+        }
+    }
+}
+
+impl OscCommand for Notify {
+    fn write_payload(&self, f: &mut dyn Write) -> fmt::Result {
+        match &self.title {
+            Some(title) => {
+                f.write_str("777;notify;")?;
+                // The title can't contain the separator.
+                write_sanitized(f, &title.replace(';', ","))?;
+                f.write_char(';')?;
+            }
+            None => f.write_str("9;")?,
+        }
+        write_sanitized(f, &self.body)
+    }
+}
+
+/// Which clipboard [`SetClipboard`] writes to.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ClipboardTarget {
+    /// The system clipboard.
+    #[default]
+    Clipboard,
+    /// The primary selection (X11 / Wayland).
+    Primary,
+}
+
+/// Writes data to the clipboard (`OSC 52`). The data is sent base64 encoded.
+///
+/// Many terminals disable this by default, or limit the size of the data.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct SetClipboard {
+    data: Vec<u8>,
+    target: ClipboardTarget,
+}
+
+impl SetClipboard {
+    /// Creates a command writing `data` to the system clipboard.
+    pub fn new(data: impl Into<Vec<u8>>) -> Self {
+        Self {
+            data: data.into(),
+            target: ClipboardTarget::Clipboard,
+        }
+    }
+
+    /// Builder function to set the clipboard that is written to.
+    #[must_use]
+    pub fn target(mut self, target: ClipboardTarget) -> Self {
+        self.target = target;
+        self
+    }
+}
+
+impl OscCommand for SetClipboard {
+    fn write_payload(&self, f: &mut dyn Write) -> fmt::Result {
+        f.write_str(match self.target {
+            ClipboardTarget::Clipboard => "52;c;",
+            ClipboardTarget::Primary => "52;p;",
+        })?;
+        write_base64(f, &self.data)
+    }
+}
+
+/// Changes one of the 256 indexed colors (`OSC 4`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SetPaletteColor {
+    /// The index of the color to change.
+    pub index: u8,
+    /// The new color.
+    pub color: Color,
+}
+
+impl SetPaletteColor {
+    /// Creates a new [`SetPaletteColor`].
+    #[must_use]
+    pub const fn new(index: u8, color: Color) -> Self {
+        Self { index, color }
+    }
+}
+
+impl OscCommand for SetPaletteColor {
+    fn write_payload(&self, f: &mut dyn Write) -> fmt::Result {
+        write!(f, "4;{};", self.index)?;
+        write_rgb(f, self.color)
+    }
+}
+
+/// Resets one (or, with `None`, all) of the indexed colors to the terminal's default
+/// (`OSC 104`).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ResetPaletteColor(pub Option<u8>);
+
+impl OscCommand for ResetPaletteColor {
+    fn write_payload(&self, f: &mut dyn Write) -> fmt::Result {
+        match self.0 {
+            Some(index) => write!(f, "104;{index}"),
+            None => f.write_str("104"),
+        }
+    }
+}
+
+/// The colors that are not part of the indexed palette.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DynamicColor {
+    /// The default foreground color, `OSC 10`.
+    Foreground,
+    /// The default background color, `OSC 11`.
+    Background,
+    /// The cursor color, `OSC 12`.
+    Cursor,
+}
+
+impl DynamicColor {
+    /// Gets the `OSC` number used to set this color. Adding 100 gives the number used to
+    /// reset it.
+    #[must_use]
+    pub const fn code(&self) -> u8 {
+        match self {
+            Self::Foreground => 10,
+            Self::Background => 11,
+            Self::Cursor => 12,
+        }
+    }
+}
+
+/// Changes the default foreground, background or cursor color (`OSC 10` / `11` / `12`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SetDynamicColor {
+    /// The color to change.
+    pub target: DynamicColor,
+    /// The new color.
+    pub color: Color,
+}
+
+impl SetDynamicColor {
+    /// Creates a new [`SetDynamicColor`].
+    #[must_use]
+    pub const fn new(target: DynamicColor, color: Color) -> Self {
+        Self { target, color }
+    }
+}
+
+impl OscCommand for SetDynamicColor {
+    fn write_payload(&self, f: &mut dyn Write) -> fmt::Result {
+        write!(f, "{};", self.target.code())?;
+        write_rgb(f, self.color)
+    }
+}
+
+/// Resets the default foreground, background or cursor color (`OSC 110` / `111` / `112`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ResetDynamicColor(pub DynamicColor);
+
+impl OscCommand for ResetDynamicColor {
+    fn write_payload(&self, f: &mut dyn Write) -> fmt::Result {
+        write!(f, "{}", u16::from(self.0.code()) + 100)
+    }
+}
+
+osc_display!(
+    SetTitle,
+    WorkingDirectory,
+    Notify,
+    SetClipboard,
+    SetPaletteColor,
+    ResetPaletteColor,
+    SetDynamicColor,
+    ResetDynamicColor,
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn payload(command: impl OscCommand) -> String {
+        let mut out = String::new();
+        command.write_payload(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn base64() {
+        let cases = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (input, expected) in cases {
+            let mut out = String::new();
+            write_base64(&mut out, input.as_bytes()).unwrap();
+            assert_eq!(out, expected);
+        }
+        assert_eq!(payload(SetClipboard::new(vec![0xff, 0xfe])), "52;c;//4=");
+    }
+
+    #[test]
+    fn commands() {
+        assert_eq!(
+            payload(SetTitle::new("a\x07b\x1b").target(TitleTarget::IconAndWindow)),
+            "0;ab"
+        );
+        assert_eq!(
+            payload(WorkingDirectory::new("/home/me/my dir").host("box")),
+            "7;file://box/home/me/my%20dir"
+        );
+        assert_eq!(payload(Notify::new("done")), "9;done");
+        assert_eq!(
+            payload(Notify::with_title("a;b", "c;d")),
+            "777;notify;a,b;c;d"
+        );
+        assert_eq!(
+            payload(SetClipboard::new("hi").target(ClipboardTarget::Primary)),
+            "52;p;aGk="
+        );
+        assert_eq!(
+            payload(SetPaletteColor::new(1, Color::from_rgb(0xab, 0x0c, 0xff))),
+            "4;1;rgb:ab/0c/ff"
+        );
+        assert_eq!(payload(ResetPaletteColor(None)), "104");
+        assert_eq!(payload(ResetPaletteColor(Some(7))), "104;7");
+        assert_eq!(
+            payload(SetDynamicColor::new(
+                DynamicColor::Background,
+                Color::from_rgb(0, 0, 0)
+            )),
+            "11;rgb:00/00/00"
+        );
+        assert_eq!(payload(ResetDynamicColor(DynamicColor::Cursor)), "112");
+    }
+
+    #[test]
+    fn wrapping() {
+        assert_eq!(Notify::new("x").to_string(), "\x1b]9;x\x1b\\");
+        assert_eq!(
+            ResetPaletteColor(None)
+                .terminator(Terminator::Bel)
+                .passthrough(Passthrough::Tmux)
+                .to_string(),
+            "\x1bPtmux;\x1b\x1b]104\x07\x1b\\"
+        );
+        assert_eq!(
+            Notify::new("x")
+                .passthrough(Passthrough::Screen)
+                .to_string(),
+            "\x1bP\x1b]9;x\x07\x1b\\"
+        );
+    }
+
+    #[test]
+    fn screen_chunks() {
+        let data = "a".repeat(600);
+        let seq = SetClipboard::new(data.as_str())
+            .passthrough(Passthrough::Screen)
+            .to_string();
+        let chunks = seq
+            .strip_prefix("\x1bP")
+            .and_then(|s| s.strip_suffix("\x1b\\"))
+            .unwrap()
+            .split("\x1b\\\x1bP")
+            .collect::<Vec<_>>();
+        assert!(chunks.iter().all(|chunk| chunk.len() <= SCREEN_CHUNK_LEN));
+        assert_eq!(
+            chunks.concat(),
+            SetClipboard::new(data.as_str())
+                .terminator(Terminator::Bel)
+                .to_string()
+        );
+    }
+}
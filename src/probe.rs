@@ -0,0 +1,386 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Detecting what the terminal supports by asking it, rather than trusting environment
+//! variables, which are often wrong over SSH or inside tmux.
+//!
+//! [`probe`] sends a batch of queries through a [`TerminalIo`] and collects the replies into
+//! [`TerminalCapabilities`]:
+//! - Primary and Secondary Device Attributes (`DA1` / `DA2`), for sixel support and the
+//!   terminal type.
+//! - `XTVERSION`, for the terminal's name and version.
+//! - `XTGETTCAP`, for the `RGB`/`Tc` (truecolor) and `Smulx`/`Setulc` (styled underlines)
+//!   terminfo capabilities.
+//! - `DECRQM`, for synchronized output (mode 2026).
+//!
+//! Hyperlink support can't be queried, so it is inferred from the terminal's name. If the
+//! terminal does not reply in time, the capabilities are guessed from the environment.
+//!
+//! ## Example
+//! ```
+//! # use std::{io, time::Duration};
+//! # use ansirs::{probe::{probe_with_env, CapabilitySource}, query::TerminalIo};
+//! struct Kitty(Option<&'static str>);
+//!
+//! impl TerminalIo for Kitty {
+//!     fn send(&mut self, _data: &[u8]) -> io::Result<()> {
+//!         Ok(())
+//!     }
+//!
+//!     fn recv(&mut self, _timeout: Duration) -> io::Result<Option<Vec<u8>>> {
+//!         Ok(self.0.take().map(|s| s.as_bytes().to_vec()))
+//!     }
+//! }
+//!
+//! let mut io = Kitty(Some("\x1bP>|kitty(0.31.0)\x1b\\\x1b[?2026;2$y\x1b[?62;c"));
+//! let caps = probe_with_env(&mut io, Duration::from_millis(100), |_| None);
+//! assert_eq!(caps.source, CapabilitySource::Probe);
+//! assert_eq!(caps.name.as_deref(), Some("kitty(0.31.0)"));
+//! assert!(caps.truecolor && caps.hyperlinks && caps.synchronized_output);
+//! ```
+
+use std::time::Duration;
+
+use crate::{
+    query::{exchange, TerminalIo},
+    Token,
+};
+
+/// The `XTGETTCAP` capabilities that are queried, hex encoded (`RGB`, `Tc`, `Smulx`, `Setulc`).
+const TCAP_QUERY: &str = "\x1bP+q524742;5463;536d756c78;536574756c63\x1b\\";
+
+/// The `DECRQM` mode for synchronized output.
+const SYNCHRONIZED_OUTPUT_MODE: u16 = 2026;
+
+/// Where a [`TerminalCapabilities`] came from.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CapabilitySource {
+    /// Guessed from environment variables.
+    #[default]
+    Environment,
+    /// Replies from the terminal.
+    Probe,
+}
+
+/// What a terminal supports, see the [module docs](self).
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct TerminalCapabilities {
+    /// Whether 24-bit colors are supported.
+    pub truecolor: bool,
+    /// Whether `OSC 8` [hyperlinks](crate::Hyperlink) are supported.
+    pub hyperlinks: bool,
+    /// Whether sixel graphics are supported.
+    pub sixel: bool,
+    /// Whether styled (curly, dotted, ...) and colored underlines are supported.
+    pub styled_underlines: bool,
+    /// Whether synchronized output (mode 2026) is supported.
+    pub synchronized_output: bool,
+    /// The terminal's name and version, from `XTVERSION` or `TERM_PROGRAM`.
+    pub name: Option<String>,
+    /// The attributes from the Primary Device Attributes reply (e.g. `4` for sixel).
+    pub device_attributes: Vec<u16>,
+    /// The terminal type from the Secondary Device Attributes reply.
+    pub terminal_type: Option<u16>,
+    /// The firmware version from the Secondary Device Attributes reply.
+    pub firmware_version: Option<u16>,
+    /// Where these capabilities came from.
+    pub source: CapabilitySource,
+}
+
+// Features of terminals known by name.
+const TRUECOLOR: u8 = 1;
+const HYPERLINKS: u8 = 1 << 1;
+const SIXEL: u8 = 1 << 2;
+const UNDERLINES: u8 = 1 << 3;
+const SYNC: u8 = 1 << 4;
+
+/// Looks up the features of a terminal by the start of its name.
+fn known_terminal(name: &str) -> u8 {
+    const TABLE: [(&str, u8); 9] = [
+        ("kitty", TRUECOLOR | HYPERLINKS | UNDERLINES | SYNC),
+        (
+            "wezterm",
+            TRUECOLOR | HYPERLINKS | SIXEL | UNDERLINES | SYNC,
+        ),
+        ("foot", TRUECOLOR | HYPERLINKS | SIXEL | UNDERLINES | SYNC),
+        ("ghostty", TRUECOLOR | HYPERLINKS | UNDERLINES | SYNC),
+        (
+            "contour",
+            TRUECOLOR | HYPERLINKS | SIXEL | UNDERLINES | SYNC,
+        ),
+        ("iterm", TRUECOLOR | HYPERLINKS | SIXEL | SYNC),
+        ("alacritty", TRUECOLOR | HYPERLINKS | UNDERLINES | SYNC),
+        ("vscode", TRUECOLOR | HYPERLINKS),
+        ("xterm", TRUECOLOR),
+    ];
+
+    let name = name.to_ascii_lowercase();
+    TABLE
+        .iter()
+        .find(|(prefix, _)| name.starts_with(prefix))
+        .map_or(0, |(_, features)| *features)
+}
+
+impl TerminalCapabilities {
+    /// Guesses the capabilities from the environment variables of this process, see
+    /// [`TerminalCapabilities::from_env_with`].
+    #[must_use]
+    pub fn from_env() -> Self {
+        Self::from_env_with(|name| std::env::var(name).ok())
+    }
+
+    /// Guesses the capabilities from environment variables, looked up with `var`.
+    ///
+    /// This uses `COLORTERM`, `TERM`, `TERM_PROGRAM`, `VTE_VERSION` and `WT_SESSION`.
+    /// Truecolor support is only taken from `COLORTERM` (`truecolor` or `24bit`), since
+    /// `TERM` is often a generic value such as `xterm`, set by SSH or multiplexers.
+    ///
+    /// ```
+    /// # use ansirs::probe::TerminalCapabilities;
+    /// let caps = TerminalCapabilities::from_env_with(|name| match name {
+    ///     "COLORTERM" => Some("truecolor".to_string()),
+    ///     "VTE_VERSION" => Some("7200".to_string()),
+    ///     _ => None,
+    /// });
+    /// assert!(caps.truecolor && caps.hyperlinks);
+    /// assert!(!caps.sixel);
+    /// ```
+    #[must_use]
+    pub fn from_env_with(var: impl Fn(&str) -> Option<String>) -> Self {
+        let term = var("TERM").unwrap_or_default();
+        let program = var("TERM_PROGRAM").filter(|p| !p.is_empty());
+        let known = match program.as_deref().map(known_terminal) {
+            Some(features) if features != 0 => features,
+            _ => known_terminal(term.trim_start_matches("xterm-")),
+        };
+        let vte = var("VTE_VERSION")
+            .and_then(|v| v.parse::<u32>().ok())
+            .unwrap_or(0);
+        let windows_terminal = var("WT_SESSION").is_some();
+
+        let mut caps = Self {
+            truecolor: matches!(var("COLORTERM").as_deref(), Some("truecolor" | "24bit")),
+            hyperlinks: windows_terminal || vte >= 5000,
+            styled_underlines: vte >= 5102,
+            name: program,
+            source: CapabilitySource::Environment,
+            ..Self::default()
+        };
+        caps.add_features(known & !TRUECOLOR);
+        caps
+    }
+
+    /// Turns on the given features from the table of known terminals.
+    fn add_features(&mut self, features: u8) {
+        self.truecolor |= features & TRUECOLOR != 0;
+        self.hyperlinks |= features & HYPERLINKS != 0;
+        self.sixel |= features & SIXEL != 0;
+        self.styled_underlines |= features & UNDERLINES != 0;
+        self.synchronized_output |= features & SYNC != 0;
+    }
+
+    /// Applies a single reply token.
+    fn apply_reply(&mut self, token: Token<'_>) {
+        match token {
+            Token::Csi(csi) if csi.final_byte() == 'c' && csi.intermediates().is_empty() => {
+                match csi.private_marker() {
+                    Some('?') => {
+                        self.device_attributes = csi.numeric_params().flatten().collect();
+                        self.sixel |= self.device_attributes.contains(&4);
+                    }
+                    Some('>') => {
+                        self.terminal_type = csi.param(0);
+                        self.firmware_version = csi.param(1);
+                    }
+                    _ => {}
+                }
+            }
+            Token::Csi(csi)
+                if csi.final_byte() == 'y'
+                    && csi.intermediates() == "$"
+                    && csi.private_marker() == Some('?')
+                    && csi.param(0) == Some(SYNCHRONIZED_OUTPUT_MODE) =>
+            {
+                // 0 is "not recognized", 1-4 are set, reset, permanently set and permanently reset.
+                self.synchronized_output = matches!(csi.param(1), Some(1..=3));
+            }
+            Token::Dcs(data) => {
+                if let Some(name) = data.strip_prefix(">|") {
+                    self.name = Some(name.to_string());
+                } else if let Some(caps) = data.strip_prefix("1+r") {
+                    for cap in caps.split(';') {
+                        let name = cap.split('=').next().unwrap_or_default();
+                        match name.to_ascii_uppercase().as_str() {
+                            "524742" | "5463" => self.truecolor = true,
+                            "536D756C78" | "536574756C63" => self.styled_underlines = true,
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Probes the terminal, falling back to the environment of this process, see
+/// [`probe_with_env`].
+pub fn probe(io: &mut impl TerminalIo, timeout: Duration) -> TerminalCapabilities {
+    probe_with_env(io, timeout, |name| std::env::var(name).ok())
+}
+
+/// Probes the terminal, see the [module docs](self).
+///
+/// If the terminal doesn't reply in time (or I/O fails), this returns
+/// [`TerminalCapabilities::from_env_with`]. Otherwise the replies are used, filling in what
+/// the terminal didn't report from what is known about it by name. Truecolor support is
+/// also taken from `COLORTERM`, since many terminals don't answer `XTGETTCAP`.
+pub fn probe_with_env(
+    io: &mut impl TerminalIo,
+    timeout: Duration,
+    var: impl Fn(&str) -> Option<String>,
+) -> TerminalCapabilities {
+    let queries = format!("\x1b[>c\x1b[>0q{TCAP_QUERY}\x1b[?{SYNCHRONIZED_OUTPUT_MODE}$p");
+    let replies = match exchange(io, &queries, timeout) {
+        Ok(replies) if replies.complete => replies,
+        _ => return TerminalCapabilities::from_env_with(var),
+    };
+
+    let mut caps = TerminalCapabilities {
+        source: CapabilitySource::Probe,
+        ..TerminalCapabilities::default()
+    };
+    for token in replies.tokens() {
+        caps.apply_reply(token);
+    }
+
+    if let Some(name) = caps.name.as_deref() {
+        caps.add_features(known_terminal(name));
+    }
+    // VTE based terminals report type 65 and their version in `DA2`.
+    if caps.terminal_type == Some(65) {
+        let version = caps.firmware_version.unwrap_or(0);
+        caps.hyperlinks |= version >= 5000;
+        caps.styled_underlines |= version >= 5102;
+    }
+    caps.truecolor |= matches!(var("COLORTERM").as_deref(), Some("truecolor" | "24bit"));
+
+    caps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::io;
+
+    struct MockIo {
+        sent: String,
+        reply: Option<&'static str>,
+    }
+
+    impl TerminalIo for MockIo {
+        fn send(&mut self, data: &[u8]) -> io::Result<()> {
+            self.sent.push_str(&String::from_utf8_lossy(data));
+            Ok(())
+        }
+
+        fn recv(&mut self, _timeout: Duration) -> io::Result<Option<Vec<u8>>> {
+            Ok(self.reply.take().map(|s| s.as_bytes().to_vec()))
+        }
+    }
+
+    fn probe_reply(reply: Option<&'static str>) -> (TerminalCapabilities, String) {
+        let mut io = MockIo {
+            sent: String::new(),
+            reply,
+        };
+        let caps = probe_with_env(&mut io, Duration::from_millis(10), |name| {
+            (name == "TERM").then(|| "xterm-kitty".to_string())
+        });
+        (caps, io.sent)
+    }
+
+    #[test]
+    fn queries_sent() {
+        let (_, sent) = probe_reply(None);
+        assert_eq!(
+            sent,
+            concat!(
+                "\x1b[>c\x1b[>0q\x1bP+q524742;5463;536d756c78;536574756c63\x1b\\",
+                "\x1b[?2026$p\x1b[c"
+            )
+        );
+    }
+
+    #[test]
+    fn replies() {
+        let (caps, _) = probe_reply(Some(concat!(
+            "\x1b[>41;388;0c",
+            "\x1bP>|XTerm(388)\x1b\\",
+            "\x1bP1+r524742=38;536d756c78\x1b\\",
+            "\x1b[?2026;0$y",
+            "\x1b[?63;1;4c",
+        )));
+        assert_eq!(
+            caps,
+            TerminalCapabilities {
+                truecolor: true,
+                hyperlinks: false,
+                sixel: true,
+                styled_underlines: true,
+                synchronized_output: false,
+                name: Some("XTerm(388)".to_string()),
+                device_attributes: vec![63, 1, 4],
+                terminal_type: Some(41),
+                firmware_version: Some(388),
+                source: CapabilitySource::Probe,
+            }
+        );
+
+        let (caps, _) = probe_reply(Some("\x1b[>65;7600;1c\x1bP0+r\x1b\\\x1b[?62;22c"));
+        assert!(!caps.truecolor);
+        assert!(caps.hyperlinks && caps.styled_underlines);
+        assert_eq!(caps.name, None);
+    }
+
+    #[test]
+    fn environment_fallback() {
+        let (caps, _) = probe_reply(Some("\x1b[>1;10;0c"));
+        assert_eq!(caps.source, CapabilitySource::Environment);
+        assert!(caps.hyperlinks && caps.synchronized_output);
+        assert!(!caps.truecolor);
+        assert_eq!(caps.device_attributes, Vec::<u16>::new());
+
+        let caps = TerminalCapabilities::from_env_with(|name| match name {
+            "TERM_PROGRAM" => Some("WezTerm".to_string()),
+            _ => None,
+        });
+        assert!(caps.sixel);
+        assert_eq!(caps.name.as_deref(), Some("WezTerm"));
+
+        let caps = TerminalCapabilities::from_env_with(|_| None);
+        assert_eq!(caps, TerminalCapabilities::default());
+    }
+
+    #[test]
+    fn generic_term_is_not_truecolor() {
+        for term in ["xterm", "xterm-256color", "screen-256color"] {
+            let caps = TerminalCapabilities::from_env_with(|name| {
+                (name == "TERM").then(|| term.to_string())
+            });
+            assert!(!caps.truecolor, "{term}");
+        }
+
+        let caps = TerminalCapabilities::from_env_with(|name| match name {
+            "TERM" => Some("xterm".to_string()),
+            "COLORTERM" => Some("24bit".to_string()),
+            _ => None,
+        });
+        assert!(caps.truecolor);
+    }
+}
@@ -0,0 +1,546 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Querying the terminal for its colors, e.g. to pick a light or dark theme.
+//!
+//! Queries are written to the terminal and the replies are read back through a
+//! [`TerminalIo`], which makes it possible to test code using them against a mock. Use
+//! [`ThreadedIo`] to talk to a real terminal. Note that the terminal must be in raw mode
+//! (no echo, no line buffering) while querying, which is left to the caller.
+//!
+//! Every query is followed by a Primary Device Attributes request, which all terminals
+//! answer. This way an unsupported query fails fast with [`QueryError::Unsupported`] instead
+//! of waiting for the timeout.
+//!
+//! ## Example
+//! ```
+//! # use std::{collections::VecDeque, io, time::Duration};
+//! # use ansirs::query::{detect_theme, TerminalIo, ThemeMode};
+//! /// Replies with a white background.
+//! struct Mock(VecDeque<&'static str>);
+//!
+//! impl TerminalIo for Mock {
+//!     fn send(&mut self, _data: &[u8]) -> io::Result<()> {
+//!         Ok(())
+//!     }
+//!
+//!     fn recv(&mut self, _timeout: Duration) -> io::Result<Option<Vec<u8>>> {
+//!         Ok(self.0.pop_front().map(|s| s.as_bytes().to_vec()))
+//!     }
+//! }
+//!
+//! let mut io = Mock(VecDeque::from(["\x1b]11;rgb:ffff/ffff/ffff\x1b\\", "\x1b[?62c"]));
+//! assert_eq!(detect_theme(&mut io, Duration::from_millis(100)).unwrap(), ThemeMode::Light);
+//! ```
+
+use std::{
+    io,
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{osc::DynamicColor, parse_x11, tokenize, Color, Token};
+
+/// The Primary Device Attributes request, sent after every query.
+const DA1: &str = "\x1b[c";
+
+/// How queries are written to, and replies read from, the terminal.
+pub trait TerminalIo {
+    /// Writes (and flushes) `data` to the terminal.
+    ///
+    /// ## Errors
+    /// Returns any error that occurs while writing.
+    fn send(&mut self, data: &[u8]) -> io::Result<()>;
+
+    /// Waits at most `timeout` for data from the terminal, returning `None` if there was none.
+    ///
+    /// ## Errors
+    /// Returns any error that occurs while reading.
+    fn recv(&mut self, timeout: Duration) -> io::Result<Option<Vec<u8>>>;
+}
+
+impl<T: TerminalIo + ?Sized> TerminalIo for &mut T {
+    fn send(&mut self, data: &[u8]) -> io::Result<()> {
+        (**self).send(data)
+    }
+
+    fn recv(&mut self, timeout: Duration) -> io::Result<Option<Vec<u8>>> {
+        (**self).recv(timeout)
+    }
+}
+
+/// A [`TerminalIo`] that reads on a background thread, so reads can time out.
+///
+/// The thread only reads while [`recv`](TerminalIo::recv) is waiting for a reply, so input
+/// typed after the queries are done is left alone. It exits once the [`ThreadedIo`] is
+/// dropped, or right after the read that is in progress at that point returns. Since every
+/// query is answered by the terminal, no read is usually left waiting.
+#[derive(Debug)]
+pub struct ThreadedIo<W> {
+    writer: W,
+    requests: Sender<()>,
+    replies: Receiver<io::Result<Vec<u8>>>,
+    /// Whether the thread is reading, i.e. a reply is on its way.
+    reading: bool,
+}
+
+impl<W: io::Write> ThreadedIo<W> {
+    /// Creates a new [`ThreadedIo`], spawning the thread that reads from `reader`.
+    pub fn new<R: io::Read + Send + 'static>(mut reader: R, writer: W) -> Self {
+        let (requests, requested) = mpsc::channel();
+        let (sender, replies) = mpsc::channel();
+        thread::spawn(move || {
+            let mut buf = [0; 1024];
+            while requested.recv().is_ok() {
+                let result = loop {
+                    match reader.read(&mut buf) {
+                        Ok(n) => break Ok(buf[..n].to_vec()),
+                        Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                        Err(err) => break Err(err),
+                    }
+                };
+                // Stop at the end of the input, or after the first error.
+                if matches!(result, Ok(ref data) if data.is_empty()) {
+                    break;
+                }
+                let failed = result.is_err();
+                if sender.send(result).is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            writer,
+            requests,
+            replies,
+            reading: false,
+        }
+    }
+}
+
+impl<W: io::Write> TerminalIo for ThreadedIo<W> {
+    fn send(&mut self, data: &[u8]) -> io::Result<()> {
+        self.writer.write_all(data)?;
+        self.writer.flush()
+    }
+
+    fn recv(&mut self, timeout: Duration) -> io::Result<Option<Vec<u8>>> {
+        if !self.reading {
+            if self.requests.send(()).is_err() {
+                // The thread has stopped, the reader is done.
+                return Ok(None);
+            }
+            self.reading = true;
+        }
+        // On a timeout the read stays in progress, its result is returned by the next call.
+        match self.replies.recv_timeout(timeout) {
+            Ok(result) => {
+                self.reading = false;
+                result.map(Some)
+            }
+            Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => Ok(None),
+        }
+    }
+}
+
+/// Error type returned by the terminal queries.
+#[derive(Debug)]
+pub enum QueryError {
+    /// Writing the query or reading the reply failed.
+    Io(io::Error),
+    /// The terminal did not reply in time.
+    Timeout,
+    /// The terminal replied, but not to the query.
+    Unsupported,
+    /// The terminal replied to the query with something that could not be parsed.
+    InvalidReply(String),
+}
+
+impl std::fmt::Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QueryError::Io(err) => write!(f, "Terminal I/O failed: {err}"),
+            QueryError::Timeout => write!(f, "The terminal did not reply in time"),
+            QueryError::Unsupported => write!(f, "The terminal does not support the query"),
+            QueryError::InvalidReply(reply) => {
+                write!(f, "Invalid reply from the terminal: {reply:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for QueryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            QueryError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for QueryError {
+    fn from(err: io::Error) -> Self {
+        QueryError::Io(err)
+    }
+}
+
+/// The replies read after sending a query.
+pub(crate) struct Replies {
+    /// Everything read from the terminal, up to and including the device attributes reply.
+    pub(crate) data: String,
+    /// Whether the device attributes reply was received, i.e. all replies are in `data`.
+    pub(crate) complete: bool,
+}
+
+impl Replies {
+    /// Gets the tokens making up the replies.
+    pub(crate) fn tokens(&self) -> impl Iterator<Item = Token<'_>> {
+        tokenize(&self.data)
+    }
+
+    /// Gets the error to return when the expected reply is missing.
+    pub(crate) fn missing(&self) -> QueryError {
+        if self.complete {
+            QueryError::Unsupported
+        } else {
+            QueryError::Timeout
+        }
+    }
+}
+
+fn is_da1_reply(token: &Token<'_>) -> bool {
+    matches!(token, Token::Csi(csi) if csi.private_marker() == Some('?') && csi.final_byte() == 'c')
+}
+
+/// Sends `queries` followed by a device attributes request, then reads replies until the
+/// device attributes reply arrives or `timeout` passes.
+pub(crate) fn exchange(
+    io: &mut impl TerminalIo,
+    queries: &str,
+    timeout: Duration,
+) -> Result<Replies, QueryError> {
+    io.send(format!("{queries}{DA1}").as_bytes())?;
+
+    let deadline = Instant::now() + timeout;
+    let mut bytes = Vec::new();
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let Some(chunk) = io.recv(remaining)? else {
+            break;
+        };
+        bytes.extend_from_slice(&chunk);
+        if tokenize(&String::from_utf8_lossy(&bytes)).any(|token| is_da1_reply(&token)) {
+            return Ok(Replies {
+                data: String::from_utf8_lossy(&bytes).into_owned(),
+                complete: true,
+            });
+        }
+        if remaining.is_zero() {
+            break;
+        }
+    }
+
+    Ok(Replies {
+        data: String::from_utf8_lossy(&bytes).into_owned(),
+        complete: false,
+    })
+}
+
+/// Parses a color in the form used by `OSC` color replies, `rgb:R/G/B` where each component
+/// has one to four hex digits, or `#RGB` with the same number of digits for each component.
+///
+/// ```
+/// # use ansirs::{query::parse_color_reply, Color};
+/// assert_eq!(parse_color_reply("rgb:ffff/8080/0000"), Some(Color::from_rgb(255, 128, 0)));
+/// assert_eq!(parse_color_reply("rgb:f/8/0"), Some(Color::from_rgb(255, 136, 0)));
+/// assert_eq!(parse_color_reply("#ff8000"), Some(Color::from_rgb(255, 128, 0)));
+/// assert_eq!(parse_color_reply("blue"), None);
+/// ```
+#[must_use]
+pub fn parse_color_reply(reply: &str) -> Option<Color> {
+    let reply = reply.trim();
+    if let Some(channels) = reply.strip_prefix("rgb:") {
+        return parse_x11(channels);
+    }
+
+    let hex = reply.strip_prefix('#')?;
+    if hex.is_empty() || hex.len() % 3 != 0 || !hex.is_ascii() {
+        return None;
+    }
+    let n = hex.len() / 3;
+    parse_x11(&format!(
+        "{}/{}/{}",
+        &hex[..n],
+        &hex[n..2 * n],
+        &hex[2 * n..]
+    ))
+}
+
+/// Sends the `OSC` query `request` and parses the color from the reply starting with `prefix`.
+fn query_color(
+    io: &mut impl TerminalIo,
+    request: &str,
+    prefix: &str,
+    timeout: Duration,
+) -> Result<Color, QueryError> {
+    let replies = exchange(io, request, timeout)?;
+    let reply = replies
+        .tokens()
+        .find_map(|token| match token {
+            Token::Osc(data) => data.strip_prefix(prefix),
+            _ => None,
+        })
+        .ok_or_else(|| replies.missing())?;
+
+    parse_color_reply(reply).ok_or_else(|| QueryError::InvalidReply(reply.to_string()))
+}
+
+/// Queries the default foreground, background or cursor color (`OSC 10` / `11` / `12`).
+///
+/// ## Errors
+/// See [`QueryError`].
+pub fn query_dynamic_color(
+    io: &mut impl TerminalIo,
+    color: DynamicColor,
+    timeout: Duration,
+) -> Result<Color, QueryError> {
+    let code = color.code();
+    query_color(
+        io,
+        &format!("\x1b]{code};?\x1b\\"),
+        &format!("{code};"),
+        timeout,
+    )
+}
+
+/// Queries the default foreground color, see [`query_dynamic_color`].
+///
+/// ## Errors
+/// See [`QueryError`].
+pub fn query_foreground(io: &mut impl TerminalIo, timeout: Duration) -> Result<Color, QueryError> {
+    query_dynamic_color(io, DynamicColor::Foreground, timeout)
+}
+
+/// Queries the default background color, see [`query_dynamic_color`].
+///
+/// ## Errors
+/// See [`QueryError`].
+pub fn query_background(io: &mut impl TerminalIo, timeout: Duration) -> Result<Color, QueryError> {
+    query_dynamic_color(io, DynamicColor::Background, timeout)
+}
+
+/// Queries one of the 256 indexed colors (`OSC 4`).
+///
+/// ## Errors
+/// See [`QueryError`].
+pub fn query_palette_color(
+    io: &mut impl TerminalIo,
+    index: u8,
+    timeout: Duration,
+) -> Result<Color, QueryError> {
+    query_color(
+        io,
+        &format!("\x1b]4;{index};?\x1b\\"),
+        &format!("4;{index};"),
+        timeout,
+    )
+}
+
+/// Whether the terminal has a light or a dark background.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ThemeMode {
+    /// Dark text on a light background.
+    Light,
+    /// Light text on a dark background.
+    Dark,
+}
+
+impl ThemeMode {
+    /// Classifies the given background color.
+    ///
+    /// Backgrounds where black text has more contrast than white text are light, which is
+    /// the case for a [relative luminance](Color::relative_luminance) above about `0.18`.
+    ///
+    /// ```
+    /// # use ansirs::{query::ThemeMode, Color};
+    /// assert_eq!(ThemeMode::from_background(Color::from_rgb(0xfd, 0xf6, 0xe3)), ThemeMode::Light);
+    /// assert_eq!(ThemeMode::from_background(Color::from_rgb(0x00, 0x2b, 0x36)), ThemeMode::Dark);
+    /// ```
+    #[must_use]
+    pub fn from_background(background: Color) -> Self {
+        let luminance = background.relative_luminance();
+        // Contrast against black, (L + 0.05) / 0.05, versus against white, 1.05 / (L + 0.05).
+        if (luminance + 0.05) * (luminance + 0.05) > 1.05 * 0.05 {
+            ThemeMode::Light
+        } else {
+            ThemeMode::Dark
+        }
+    }
+}
+
+/// Queries the background color and classifies it, see [`ThemeMode::from_background`].
+///
+/// ## Errors
+/// See [`QueryError`].
+pub fn detect_theme(io: &mut impl TerminalIo, timeout: Duration) -> Result<ThemeMode, QueryError> {
+    query_background(io, timeout).map(ThemeMode::from_background)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::collections::VecDeque;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    #[derive(Default)]
+    struct MockIo {
+        sent: Vec<u8>,
+        replies: VecDeque<&'static str>,
+    }
+
+    impl MockIo {
+        fn new(replies: &[&'static str]) -> Self {
+            Self {
+                sent: Vec::new(),
+                replies: replies.iter().copied().collect(),
+            }
+        }
+    }
+
+    impl TerminalIo for MockIo {
+        fn send(&mut self, data: &[u8]) -> io::Result<()> {
+            self.sent.extend_from_slice(data);
+            Ok(())
+        }
+
+        fn recv(&mut self, _timeout: Duration) -> io::Result<Option<Vec<u8>>> {
+            Ok(self.replies.pop_front().map(|s| s.as_bytes().to_vec()))
+        }
+    }
+
+    const TIMEOUT: Duration = Duration::from_millis(50);
+
+    #[test]
+    fn color_replies() {
+        assert_eq!(
+            parse_color_reply("rgb:0000/2b2b/3636"),
+            Some(Color::from_rgb(0, 0x2b, 0x36))
+        );
+        assert_eq!(
+            parse_color_reply("rgb:fff/000/800"),
+            Some(Color::from_rgb(255, 0, 128))
+        );
+        assert_eq!(
+            parse_color_reply("#fff000800"),
+            Some(Color::from_rgb(255, 0, 128))
+        );
+        assert_eq!(parse_color_reply("rgb:ff/ff"), None);
+        assert_eq!(parse_color_reply("rgb:ff/ff/ff/ff"), None);
+        assert_eq!(parse_color_reply("rgb:fffff/0/0"), None);
+        assert_eq!(parse_color_reply("#ff00"), None);
+    }
+
+    #[test]
+    fn queries() {
+        let mut io = MockIo::new(&["\x1b]10;rgb:c0c0/c0c0/c0c0\x07\x1b[?6", "2;22c"]);
+        assert_eq!(
+            query_foreground(&mut io, TIMEOUT).unwrap(),
+            Color::from_rgb(0xc0, 0xc0, 0xc0)
+        );
+        assert_eq!(io.sent, b"\x1b]10;?\x1b\\\x1b[c");
+
+        let mut io = MockIo::new(&["\x1b]4;1;rgb:cd/00/00\x1b\\\x1b[?1;2c"]);
+        assert_eq!(
+            query_palette_color(&mut io, 1, TIMEOUT).unwrap(),
+            Color::from_rgb(0xcd, 0, 0)
+        );
+    }
+
+    #[test]
+    fn failures() {
+        let mut io = MockIo::new(&["\x1b[?1;2c"]);
+        assert!(matches!(
+            query_background(&mut io, TIMEOUT),
+            Err(QueryError::Unsupported)
+        ));
+
+        let mut io = MockIo::new(&[]);
+        assert!(matches!(
+            query_background(&mut io, TIMEOUT),
+            Err(QueryError::Timeout)
+        ));
+
+        let mut io = MockIo::new(&["\x1b]11;nope\x1b\\\x1b[?1c"]);
+        assert!(
+            matches!(query_background(&mut io, TIMEOUT), Err(QueryError::InvalidReply(r)) if r == "nope")
+        );
+    }
+
+    #[test]
+    fn themes() {
+        let mut io = MockIo::new(&["\x1b]11;rgb:1e1e/1e1e/2e2e\x1b\\\x1b[?62c"]);
+        assert_eq!(detect_theme(&mut io, TIMEOUT).unwrap(), ThemeMode::Dark);
+        assert_eq!(
+            ThemeMode::from_background(Color::from_rgb(128, 128, 128)),
+            ThemeMode::Light
+        );
+        assert_eq!(
+            ThemeMode::from_background(Color::from_rgb(100, 100, 100)),
+            ThemeMode::Dark
+        );
+    }
+
+    #[test]
+    fn threaded_io() {
+        let reply = b"\x1b]11;rgb:ffff/ffff/ffff\x1b\\\x1b[?62c".to_vec();
+        let mut io = ThreadedIo::new(io::Cursor::new(reply), Vec::new());
+        assert_eq!(detect_theme(&mut io, TIMEOUT).unwrap(), ThemeMode::Light);
+        assert_eq!(io.writer, b"\x1b]11;?\x1b\\\x1b[c");
+        assert_eq!(io.recv(TIMEOUT).unwrap(), None);
+    }
+
+    /// Endless input that counts how often it is read.
+    struct Keyboard(Arc<AtomicUsize>);
+
+    impl io::Read for Keyboard {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            buf[0] = b'k';
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn threaded_io_only_reads_when_asked() {
+        let reads = Arc::new(AtomicUsize::new(0));
+        let mut io = ThreadedIo::new(Keyboard(Arc::clone(&reads)), io::sink());
+        thread::sleep(TIMEOUT);
+        assert_eq!(reads.load(Ordering::SeqCst), 0);
+
+        assert_eq!(io.recv(TIMEOUT).unwrap(), Some(b"k".to_vec()));
+        assert_eq!(io.recv(TIMEOUT).unwrap(), Some(b"k".to_vec()));
+        thread::sleep(TIMEOUT);
+        assert_eq!(reads.load(Ordering::SeqCst), 2);
+
+        // Dropping it stops the thread, which drops the reader.
+        drop(io);
+        let start = Instant::now();
+        while Arc::strong_count(&reads) > 1 {
+            assert!(
+                start.elapsed() < Duration::from_secs(5),
+                "reader thread leaked"
+            );
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(reads.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,242 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{Ansi, ColorSpace, Gradient, HuePath, IntoAnsi, StyledText};
+
+/// Which way a [`GradientText`] runs.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GradientDirection {
+    /// Left to right, across the columns of the text.
+    #[default]
+    Horizontal,
+    /// Top to bottom, across the lines of the text.
+    Vertical,
+}
+
+/// Which color of the style a [`GradientText`] sets.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ColorLayer {
+    /// The foreground (text) color.
+    #[default]
+    Foreground,
+    /// The background color.
+    Background,
+}
+
+/// A text effect that colors each grapheme cluster of a string with a [`Gradient`].
+///
+/// Horizontal gradients are positioned by column, relative to the widest line, so multi-line
+/// banners stay aligned. Newlines are left unstyled so backgrounds don't bleed into the next
+/// line, and neighbouring graphemes that end up with the same color share a single span, so
+/// the rendered output only contains the escapes it needs.
+///
+/// ## Example
+/// ```
+/// # use ansirs::{Ansi, Colors, Gradient, GradientText};
+/// let banner = GradientText::new(Gradient::new(Colors::Red, Colors::Blue))
+///     .style(Ansi::new().bold())
+///     .skip_whitespace(true)
+///     .apply("ab c");
+/// assert_eq!(banner.plain_text(), "ab c");
+/// assert_eq!(banner.spans().len(), 4);
+/// assert_eq!(banner.spans()[2].style(), Ansi::new().bold());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct GradientText {
+    gradient: Gradient,
+    direction: GradientDirection,
+    layer: ColorLayer,
+    skip_whitespace: bool,
+    style: Ansi,
+}
+
+impl GradientText {
+    /// Creates a new horizontal foreground effect using the given gradient.
+    #[must_use]
+    pub fn new(gradient: Gradient) -> Self {
+        Self {
+            gradient,
+            direction: GradientDirection::default(),
+            layer: ColorLayer::default(),
+            skip_whitespace: false,
+            style: Ansi::new(),
+        }
+    }
+
+    /// Creates a new horizontal foreground effect running through all hues.
+    #[must_use]
+    pub fn rainbow() -> Self {
+        let gradient = Gradient::new((255, 0, 0), (255, 0, 255))
+            .with_stop(0.5, (0, 255, 255))
+            .space(ColorSpace::Hsl(HuePath::Increasing));
+        Self::new(gradient)
+    }
+
+    /// Sets the direction the gradient runs in, [`GradientDirection::Horizontal`] by default.
+    #[must_use]
+    pub fn direction(mut self, direction: GradientDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Sets which color the gradient sets, [`ColorLayer::Foreground`] by default.
+    #[must_use]
+    pub fn layer(mut self, layer: ColorLayer) -> Self {
+        self.layer = layer;
+        self
+    }
+
+    /// Sets whether whitespace is left uncolored, `false` by default. Skipped whitespace still
+    /// takes up its position in the gradient.
+    #[must_use]
+    pub fn skip_whitespace(mut self, skip: bool) -> Self {
+        self.skip_whitespace = skip;
+        self
+    }
+
+    /// Sets the base style the gradient color is added to, e.g. to make the text bold.
+    #[must_use]
+    pub fn style(mut self, style: impl IntoAnsi) -> Self {
+        self.style = style.into_ansi();
+        self
+    }
+
+    /// Applies the effect to `text`.
+    #[must_use]
+    pub fn apply(&self, text: &str) -> StyledText {
+        let lines = text.split('\n').collect::<Vec<_>>();
+        let span = match self.direction {
+            GradientDirection::Horizontal => lines
+                .iter()
+                .map(|line| line.graphemes(true).count())
+                .max()
+                .unwrap_or_default(),
+            GradientDirection::Vertical => lines.len(),
+        };
+        #[allow(clippy::cast_precision_loss)]
+        let position = |index: usize| {
+            if span <= 1 {
+                0.0
+            } else {
+                index as f64 / (span - 1) as f64
+            }
+        };
+
+        let mut styled = StyledText::new();
+        for (row, line) in lines.iter().enumerate() {
+            if row > 0 {
+                styled.push("\n", Ansi::new());
+            }
+            for (column, grapheme) in line.graphemes(true).enumerate() {
+                if self.skip_whitespace && grapheme.chars().all(char::is_whitespace) {
+                    styled.push(grapheme, self.style);
+                    continue;
+                }
+                let color = self.gradient.at(position(match self.direction {
+                    GradientDirection::Horizontal => column,
+                    GradientDirection::Vertical => row,
+                }));
+                let style = match self.layer {
+                    ColorLayer::Foreground => self.style.fg_color(color),
+                    ColorLayer::Background => self.style.bg_color(color),
+                };
+                styled.push(grapheme, style);
+            }
+        }
+        styled
+    }
+}
+
+/// Colors `text` with a horizontal foreground `gradient`, see [`GradientText`].
+#[must_use]
+pub fn gradient_text(text: &str, gradient: &Gradient) -> StyledText {
+    GradientText::new(gradient.clone()).apply(text)
+}
+
+/// Colors `text` with a horizontal rainbow, see [`GradientText::rainbow`].
+#[must_use]
+pub fn rainbow_text(text: &str) -> StyledText {
+    GradientText::rainbow().apply(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color, Span};
+    use pretty_assertions::assert_eq;
+
+    fn fg(text: &StyledText) -> Vec<Option<Color>> {
+        text.spans()
+            .iter()
+            .map(|s| s.style().foreground())
+            .collect()
+    }
+
+    #[test]
+    fn horizontal() {
+        let gradient = Gradient::new((0, 0, 0), (255, 255, 255)).space(ColorSpace::Srgb);
+        let text = gradient_text("abc", &gradient);
+        assert_eq!(
+            fg(&text),
+            [(0, 0, 0), (128, 128, 128), (255, 255, 255)].map(|c| Some(Color::from(c)))
+        );
+
+        // Lines are aligned by column, and newlines are unstyled.
+        let text = gradient_text("abc\na", &gradient);
+        assert_eq!(text.spans()[3].text(), "\n");
+        assert_eq!(text.spans()[3].style(), Ansi::new());
+        assert_eq!(
+            text.spans()[4].style().foreground(),
+            Some(Color::from_rgb(0, 0, 0))
+        );
+    }
+
+    #[test]
+    fn graphemes() {
+        // A flag and a family emoji are single graphemes made of several chars.
+        let text = rainbow_text("🇩🇪👨‍👩‍👧e\u{301}");
+        let graphemes = text.spans().iter().map(Span::text).collect::<Vec<_>>();
+        assert_eq!(graphemes, ["🇩🇪", "👨‍👩‍👧", "e\u{301}"]);
+        assert_eq!(
+            fg(&text),
+            [(255, 0, 0), (0, 255, 255), (255, 0, 255)].map(|c| Some(Color::from(c)))
+        );
+    }
+
+    #[test]
+    fn vertical_background() {
+        let effect = GradientText::new(Gradient::new((255, 0, 0), (0, 0, 255)))
+            .direction(GradientDirection::Vertical)
+            .layer(ColorLayer::Background)
+            .skip_whitespace(true);
+        let text = effect.apply("ab c\nde");
+        let spans = text
+            .spans()
+            .iter()
+            .map(|s| (s.text(), s.style().background()))
+            .collect::<Vec<_>>();
+        let red = Some(Color::from_rgb(255, 0, 0));
+        let blue = Some(Color::from_rgb(0, 0, 255));
+        assert_eq!(
+            spans,
+            [
+                ("ab", red),
+                (" ", None),
+                ("c", red),
+                ("\n", None),
+                ("de", blue)
+            ]
+        );
+        assert_eq!(
+            text.to_string(),
+            "\x1b[48;2;255;0;0mab\x1b[0m \x1b[48;2;255;0;0mc\x1b[0m\n\x1b[48;2;0;0;255mde\x1b[0m"
+        );
+    }
+}
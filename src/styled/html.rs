@@ -0,0 +1,452 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::{Ansi, AnsiFlags, Color, Hyperlink, Palette, StyledText};
+
+/// How [`HtmlOptions`] attaches styles to the generated HTML.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HtmlMode {
+    /// Every span gets an inline `style="..."` attribute. The output is self contained.
+    #[default]
+    Inline,
+    /// Every span gets CSS classes, the rules for which are collected in
+    /// [`HtmlOutput::stylesheet`].
+    Classes,
+}
+
+/// Options used when converting styled text to HTML, see [`ansi_to_html`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HtmlOptions {
+    mode: HtmlMode,
+    class_prefix: String,
+    palette: Palette,
+    wrap_pre: bool,
+    link_schemes: Vec<String>,
+}
+
+impl Default for HtmlOptions {
+    fn default() -> Self {
+        Self {
+            mode: HtmlMode::Inline,
+            class_prefix: String::from("ansi-"),
+            palette: Palette::default(),
+            wrap_pre: true,
+            link_schemes: ["http", "https", "file"].map(String::from).to_vec(),
+        }
+    }
+}
+
+impl HtmlOptions {
+    /// Creates the default options: inline styles, the xterm palette, wrapped in a `<pre>`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builder function to set the [`HtmlMode`].
+    #[must_use]
+    pub fn mode(mut self, mode: HtmlMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Builder function to set the prefix used for generated CSS class names. Defaults to `ansi-`.
+    #[must_use]
+    pub fn class_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.class_prefix = prefix.into();
+        self
+    }
+
+    /// Builder function to set the [`Palette`] used to resolve indexed colors, and the
+    /// default colors used for reverse video and the `<pre>` wrapper.
+    #[must_use]
+    pub fn palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    /// Builder function to set whether the output is wrapped in a `<pre>` element using
+    /// the palette's default colors. Defaults to `true`.
+    #[must_use]
+    pub fn wrap_pre(mut self, wrap: bool) -> Self {
+        self.wrap_pre = wrap;
+        self
+    }
+
+    /// Builder function to allow hyperlinks with the given URI scheme (e.g. `mailto`).
+    /// Only `http`, `https` and `file` links are allowed by default, since the output may
+    /// end up in a web page. Text with any other link is rendered without one.
+    #[must_use]
+    pub fn allow_link_scheme(mut self, scheme: impl Into<String>) -> Self {
+        self.link_schemes.push(scheme.into());
+        self
+    }
+
+    /// Gets the [`Palette`] in use.
+    #[must_use]
+    pub fn get_palette(&self) -> &Palette {
+        &self.palette
+    }
+
+    /// Returns `true` if `link` may be turned into an `<a>` element.
+    fn allows_link(&self, link: &Hyperlink) -> bool {
+        link.scheme().is_some_and(|scheme| {
+            self.link_schemes
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(scheme))
+        })
+    }
+}
+
+/// The result of an HTML conversion.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct HtmlOutput {
+    body: String,
+    stylesheet: String,
+}
+
+impl HtmlOutput {
+    /// Gets the generated HTML.
+    #[must_use]
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    /// Gets the generated CSS rules. This is always empty for [`HtmlMode::Inline`].
+    /// Rules are sorted by class name so the output is stable.
+    #[must_use]
+    pub fn stylesheet(&self) -> &str {
+        &self.stylesheet
+    }
+
+    /// Consumes the output, returning the `(body, stylesheet)` pair.
+    #[must_use]
+    pub fn into_parts(self) -> (String, String) {
+        (self.body, self.stylesheet)
+    }
+}
+
+impl std::fmt::Display for HtmlOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.body)
+    }
+}
+
+/// Converts text containing SGR escape sequences into HTML.
+///
+/// Indexed colors are resolved through the [`Palette`] in `options`. Hyperlinks become `<a>`
+/// elements if their scheme is allowed (see [`HtmlOptions::allow_link_scheme`]), any other
+/// escape sequences are dropped.
+///
+/// ## Example
+/// ```
+/// # use ansirs::{ansi_to_html, HtmlMode, HtmlOptions};
+/// let html = ansi_to_html("\x1b[1;31m<error>\x1b[0m & more", &HtmlOptions::new().wrap_pre(false));
+/// assert_eq!(
+///     html.body(),
+///     r#"<span style="color: #800000; font-weight: bold">&lt;error&gt;</span> &amp; more"#
+/// );
+///
+/// let options = HtmlOptions::new().mode(HtmlMode::Classes).wrap_pre(false);
+/// let html = ansi_to_html("\x1b[1mhi", &options);
+/// assert_eq!(html.body(), r#"<span class="ansi-bold">hi</span>"#);
+/// assert_eq!(html.stylesheet(), ".ansi-bold { font-weight: bold; }\n");
+/// ```
+#[must_use]
+pub fn ansi_to_html(input: &str, options: &HtmlOptions) -> HtmlOutput {
+    StyledText::parse_with_palette(input, &options.palette).to_html(options)
+}
+
+impl StyledText {
+    /// Converts this text into HTML, see [`ansi_to_html`].
+    #[must_use]
+    pub fn to_html(&self, options: &HtmlOptions) -> HtmlOutput {
+        let mut rules = BTreeMap::new();
+        let mut body = String::new();
+
+        if options.wrap_pre {
+            let (fg, bg) = (options.palette.foreground(), options.palette.background());
+            match options.mode {
+                HtmlMode::Inline => {
+                    let _ = write!(
+                        body,
+                        r#"<pre style="color: {}; background-color: {}">"#,
+                        fg.as_hex_lower(),
+                        bg.as_hex_lower()
+                    );
+                }
+                HtmlMode::Classes => {
+                    let class = format!("{}output", options.class_prefix);
+                    let _ = write!(body, r#"<pre class="{class}">"#);
+                    rules.insert(
+                        class,
+                        format!(
+                            "color: {}; background-color: {}",
+                            fg.as_hex_lower(),
+                            bg.as_hex_lower()
+                        ),
+                    );
+                }
+            }
+        }
+
+        for span in self.spans() {
+            let text = escape_html(span.text());
+            if text.is_empty() {
+                continue;
+            }
+
+            let link = span.link().filter(|link| options.allows_link(link));
+            if let Some(link) = link {
+                let _ = write!(body, r#"<a href="{}">"#, escape_html(link.uri()));
+            }
+
+            let declarations = css_declarations(span.style(), &options.palette);
+            match options.mode {
+                _ if declarations.is_empty() => body.push_str(&text),
+                HtmlMode::Inline => {
+                    let style = declarations
+                        .iter()
+                        .map(|(_, decl)| decl.as_str())
+                        .collect::<Vec<_>>()
+                        .join("; ");
+                    let _ = write!(body, r#"<span style="{style}">{text}</span>"#);
+                }
+                HtmlMode::Classes => {
+                    let classes = declarations
+                        .into_iter()
+                        .map(|(name, decl)| {
+                            let class = format!("{}{}", options.class_prefix, name);
+                            rules.insert(class.clone(), decl);
+                            class
+                        })
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    let _ = write!(body, r#"<span class="{classes}">{text}</span>"#);
+                }
+            }
+
+            if link.is_some() {
+                body.push_str("</a>");
+            }
+        }
+
+        if options.wrap_pre {
+            body.push_str("</pre>");
+        }
+
+        let stylesheet = rules
+            .into_iter()
+            .fold(String::new(), |mut out, (class, decl)| {
+                let _ = writeln!(out, ".{class} {{ {decl}; }}");
+                out
+            });
+
+        HtmlOutput { body, stylesheet }
+    }
+}
+
+/// Gets the foreground and background that `style` is actually displayed with.
+///
+/// Reverse video swaps the foreground and background, falling back to the palette's
+/// defaults for whichever one is unset.
+pub(crate) fn display_colors(style: Ansi, palette: &Palette) -> (Option<Color>, Option<Color>) {
+    if style.flags().contains(AnsiFlags::REVERSE) {
+        (
+            Some(style.background().unwrap_or(palette.background())),
+            Some(style.foreground().unwrap_or(palette.foreground())),
+        )
+    } else {
+        (style.foreground(), style.background())
+    }
+}
+
+/// Builds the CSS for `style` as `(class suffix, declaration)` pairs. Blink has no CSS
+/// equivalent and is ignored.
+fn css_declarations(style: Ansi, palette: &Palette) -> Vec<(String, String)> {
+    let flags = style.flags();
+    let (fg, bg) = display_colors(style, palette);
+
+    let hex = |color: Color| color.as_hex_lower();
+    let mut out = Vec::new();
+
+    if let Some(fg) = fg {
+        out.push((
+            format!("fg-{}", &hex(fg)[1..]),
+            format!("color: {}", hex(fg)),
+        ));
+    }
+    if let Some(bg) = bg {
+        out.push((
+            format!("bg-{}", &hex(bg)[1..]),
+            format!("background-color: {}", hex(bg)),
+        ));
+    }
+    if flags.contains(AnsiFlags::BOLD) {
+        out.push(("bold".into(), "font-weight: bold".into()));
+    }
+    if flags.contains(AnsiFlags::DIM) {
+        out.push(("dim".into(), "opacity: 0.5".into()));
+    }
+    if flags.contains(AnsiFlags::ITALIC) {
+        out.push(("italic".into(), "font-style: italic".into()));
+    }
+    // Both of these use `text-decoration`, so they need a combined rule or one would
+    // override the other.
+    match (
+        flags.contains(AnsiFlags::UNDERLINE),
+        flags.contains(AnsiFlags::STRIKE),
+    ) {
+        (true, true) => out.push((
+            "underline-strike".into(),
+            "text-decoration: underline line-through".into(),
+        )),
+        (true, false) => out.push(("underline".into(), "text-decoration: underline".into())),
+        (false, true) => out.push(("strike".into(), "text-decoration: line-through".into())),
+        (false, false) => {}
+    }
+
+    out
+}
+
+/// Escapes text for use in HTML. Control characters other than newlines and tabs are dropped.
+pub(crate) fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\n' | '\t' => out.push(c),
+            c if c.is_control() => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn no_pre() -> HtmlOptions {
+        HtmlOptions::new().wrap_pre(false)
+    }
+
+    #[test]
+    fn escaping() {
+        let html = ansi_to_html("<a href=\"x\">&</a>\r\n\x07", &no_pre());
+        assert_eq!(html.body(), "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;\n");
+    }
+
+    #[test]
+    fn pre_wrapper() {
+        let html = ansi_to_html("hi", &HtmlOptions::new());
+        assert_eq!(
+            html.body(),
+            r#"<pre style="color: #c0c0c0; background-color: #000000">hi</pre>"#
+        );
+
+        let html = ansi_to_html("hi", &HtmlOptions::new().mode(HtmlMode::Classes));
+        assert_eq!(html.body(), r#"<pre class="ansi-output">hi</pre>"#);
+        assert_eq!(
+            html.stylesheet(),
+            ".ansi-output { color: #c0c0c0; background-color: #000000; }\n"
+        );
+    }
+
+    #[test]
+    fn reverse_video() {
+        let html = ansi_to_html("\x1b[7mrev\x1b[0m \x1b[7;31mred", &no_pre());
+        assert_eq!(
+            html.body(),
+            concat!(
+                r#"<span style="color: #000000; background-color: #c0c0c0">rev</span> "#,
+                r#"<span style="color: #000000; background-color: #800000">red</span>"#
+            )
+        );
+    }
+
+    #[test]
+    fn palette_and_classes() {
+        let mut palette = Palette::default();
+        palette.set(4, Color::from_rgb(0x11, 0x22, 0x33));
+        let options = no_pre()
+            .mode(HtmlMode::Classes)
+            .class_prefix("t-")
+            .palette(palette);
+
+        let html = ansi_to_html("\x1b[34;4;9ma\x1b[1mb\x1b[22;38;5;16mc", &options);
+        assert_eq!(
+            html.body(),
+            concat!(
+                r#"<span class="t-fg-112233 t-underline-strike">a</span>"#,
+                r#"<span class="t-fg-112233 t-bold t-underline-strike">b</span>"#,
+                r#"<span class="t-fg-000000 t-underline-strike">c</span>"#
+            )
+        );
+        assert_eq!(
+            html.stylesheet(),
+            concat!(
+                ".t-bold { font-weight: bold; }\n",
+                ".t-fg-000000 { color: #000000; }\n",
+                ".t-fg-112233 { color: #112233; }\n",
+                ".t-underline-strike { text-decoration: underline line-through; }\n",
+            )
+        );
+    }
+
+    #[test]
+    fn styled_text_input() {
+        let text = StyledText::new()
+            .with("x", Ansi::new().italic().dim())
+            .with("y", Ansi::new());
+        assert_eq!(
+            text.to_html(&no_pre()).body(),
+            r#"<span style="opacity: 0.5; font-style: italic">x</span>y"#
+        );
+    }
+
+    #[test]
+    fn hyperlinks() {
+        let html = ansi_to_html(
+            "\x1b]8;;https://x.y/?a=1&b=\"2\"\x1b\\\x1b[1mlink\x1b[0m!\x1b]8;;\x1b\\.",
+            &no_pre(),
+        );
+        assert_eq!(
+            html.body(),
+            concat!(
+                r#"<a href="https://x.y/?a=1&amp;b=&quot;2&quot;">"#,
+                r#"<span style="font-weight: bold">link</span></a>"#,
+                r#"<a href="https://x.y/?a=1&amp;b=&quot;2&quot;">!</a>."#
+            )
+        );
+    }
+
+    #[test]
+    fn unsafe_hyperlinks() {
+        let input = concat!(
+            "\x1b]8;;javascript:alert(1)\x1b\\a\x1b]8;;\x1b\\",
+            "\x1b]8;;data:text/html,<b>\x1b\\b\x1b]8;;\x1b\\",
+            "\x1b]8;; javascript:alert(1)\x1b\\c\x1b]8;;\x1b\\",
+            "\x1b]8;;mailto:a@b.c\x1b\\d\x1b]8;;\x1b\\",
+            "\x1b]8;;FILE:///tmp\x1b\\e\x1b]8;;\x1b\\",
+        );
+        assert_eq!(
+            ansi_to_html(input, &no_pre()).body(),
+            r#"abcd<a href="FILE:///tmp">e</a>"#
+        );
+        assert_eq!(
+            ansi_to_html(input, &no_pre().allow_link_scheme("mailto")).body(),
+            r#"abc<a href="mailto:a@b.c">d</a><a href="FILE:///tmp">e</a>"#
+        );
+    }
+}
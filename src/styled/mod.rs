@@ -6,6 +6,22 @@
 
 use crate::{Ansi, IntoAnsi};
 
+mod effects;
+mod html;
+mod render;
+mod screen;
+mod svg;
+mod text;
+
+pub use effects::{gradient_text, rainbow_text, ColorLayer, GradientDirection, GradientText};
+pub use html::{ansi_to_html, HtmlMode, HtmlOptions, HtmlOutput};
+pub use render::{
+    contrast_enforcement, disable_contrast_enforcement, enforce_contrast, RenderOptions,
+};
+pub use screen::{Cell, Screen};
+pub use svg::{ansi_to_svg, SvgOptions};
+pub use text::{semantic_ansi, Span, StyledText};
+
 /// `string` Module
 ///
 /// This module contains some experiments with storing a formatted string, i.e. a
@@ -16,6 +32,10 @@ pub mod string;
 /// Styles the given [`Display`](std::fmt::Display) using the style described by `style`.
 /// `S` can be either an [`Ansi`](Ansi) or a closure that returns an [`Ansi`](Ansi). This might
 /// require bringing the [`IntoAnsi`](IntoAnsi) trait into scope.
+///
+/// If [contrast enforcement](enforce_contrast) is on, the foreground color is adjusted to
+/// stay readable on the terminal's background. This and the functions built on it are the
+/// only output that follows that setting without opting in.
 #[cfg_attr(feature = "trace", tracing::instrument(skip(text, style), fields(text = %text, style_ansi)))]
 pub fn style_text<S: IntoAnsi>(text: impl std::fmt::Display, style: S) -> String {
     let actual = format!("{text}");
@@ -23,7 +43,10 @@ pub fn style_text<S: IntoAnsi>(text: impl std::fmt::Display, style: S) -> String
     if actual.is_empty() {
         actual
     } else {
-        let ansi: Ansi = style.into_ansi();
+        let mut ansi: Ansi = style.into_ansi();
+        if let Some((background, target)) = contrast_enforcement() {
+            ansi = ansi.with_min_contrast(background, target);
+        }
         #[cfg(feature = "trace")]
         {
             let style = format!("{ansi:?}");
@@ -0,0 +1,163 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::sync::{PoisonError, RwLock};
+
+use crate::{Ansi, Color, ContrastTarget};
+
+/// The process-wide contrast enforcement, see [`enforce_contrast`].
+static CONTRAST: RwLock<Option<(Color, ContrastTarget)>> = RwLock::new(None);
+
+/// Turns on contrast enforcement for the whole process. From then on [`style_text`] (and
+/// [`styled_print`](crate::styled_print) / [`styled_println`](crate::styled_println)) adjust
+/// any foreground color that doesn't reach `target` on `background`, see
+/// [`Ansi::with_min_contrast`]. Other output opts in by rendering with
+/// [`RenderOptions::from_global`].
+///
+/// No [`Display`](std::fmt::Display) implementation is affected, so the output of
+/// [`Ansi`], [`StyledText`](crate::StyledText) and the other styled types stays the same
+/// regardless of this setting.
+///
+/// `background` is the terminal's background color, e.g. from the configuration or from
+/// [`query_background`](crate::query::query_background).
+///
+/// ```
+/// # use ansirs::{disable_contrast_enforcement, enforce_contrast, style_text, Ansi, Color, ContrastTarget, RenderOptions, StyledText};
+/// let dim = Ansi::from_fg((0x30, 0x30, 0x30));
+/// assert_eq!(style_text("hi", dim), "\x1b[38;2;48;48;48mhi\x1b[0m");
+///
+/// enforce_contrast(Color::from_rgb(0, 0, 0), ContrastTarget::AA);
+/// assert_ne!(style_text("hi", dim), "\x1b[38;2;48;48;48mhi\x1b[0m");
+/// let text = StyledText::new().with("hi", dim);
+/// assert_eq!(text.to_string(), "\x1b[38;2;48;48;48mhi\x1b[0m");
+/// assert_eq!(text.render(&RenderOptions::from_global()), style_text("hi", dim));
+///
+/// disable_contrast_enforcement();
+/// assert_eq!(style_text("hi", dim), "\x1b[38;2;48;48;48mhi\x1b[0m");
+/// ```
+pub fn enforce_contrast(background: Color, target: ContrastTarget) {
+    *CONTRAST.write().unwrap_or_else(PoisonError::into_inner) = Some((background, target));
+}
+
+/// Turns off contrast enforcement for the whole process, see [`enforce_contrast`].
+pub fn disable_contrast_enforcement() {
+    *CONTRAST.write().unwrap_or_else(PoisonError::into_inner) = None;
+}
+
+/// Gets the terminal background and contrast target set with [`enforce_contrast`], if any.
+#[must_use]
+pub fn contrast_enforcement() -> Option<(Color, ContrastTarget)> {
+    *CONTRAST.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Options used when rendering a [`StyledText`](crate::StyledText) to escape sequences,
+/// see [`StyledText::render`](crate::StyledText::render).
+///
+/// ## Example
+/// ```
+/// # use ansirs::RenderOptions;
+/// let options = RenderOptions::new().hyperlinks(false);
+/// assert!(!options.hyperlinks_enabled());
+/// assert!(RenderOptions::default().hyperlinks_enabled());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderOptions {
+    hyperlinks: bool,
+    background: Option<Color>,
+    min_contrast: Option<ContrastTarget>,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            hyperlinks: true,
+            background: None,
+            min_contrast: None,
+        }
+    }
+}
+
+impl RenderOptions {
+    /// Creates the default [`RenderOptions`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates the default [`RenderOptions`], with the background and minimum contrast set
+    /// with [`enforce_contrast`], if any. This is the way for output other than
+    /// [`style_text`](crate::style_text) to follow the process-wide setting, e.g.
+    /// `text.render(&RenderOptions::from_global())`.
+    #[must_use]
+    pub fn from_global() -> Self {
+        match contrast_enforcement() {
+            Some((background, target)) => Self::new().background(background).min_contrast(target),
+            None => Self::new(),
+        }
+    }
+
+    /// Sets whether [hyperlinks](crate::Hyperlink) are written. When disabled, linked text is
+    /// written as plain text, which is useful for terminals that print the `OSC 8` sequences.
+    /// Defaults to `true`.
+    #[must_use]
+    pub fn hyperlinks(mut self, enabled: bool) -> Self {
+        self.hyperlinks = enabled;
+        self
+    }
+
+    /// Gets whether hyperlinks are written.
+    #[must_use]
+    pub fn hyperlinks_enabled(&self) -> bool {
+        self.hyperlinks
+    }
+
+    /// Sets the terminal's background color, used to resolve translucent colors (see
+    /// [`TranslucentAnsi`](crate::TranslucentAnsi)). Defaults to unknown.
+    #[must_use]
+    pub fn background(mut self, background: Color) -> Self {
+        self.background = Some(background);
+        self
+    }
+
+    /// Gets the terminal's background color, if it was set.
+    #[must_use]
+    pub fn get_background(&self) -> Option<Color> {
+        self.background
+    }
+
+    /// Sets the minimum contrast foreground colors must reach on the
+    /// [background](RenderOptions::background), see [`Ansi::with_min_contrast`]. Has no effect
+    /// without a background. Defaults to no minimum.
+    ///
+    /// ```
+    /// # use ansirs::{Ansi, Color, ContrastTarget, RenderOptions, StyledText};
+    /// let text = StyledText::new().with("dim", Ansi::from_fg((0x30, 0x30, 0x30)));
+    /// let options = RenderOptions::new()
+    ///     .background(Color::from_rgb(0, 0, 0))
+    ///     .min_contrast(ContrastTarget::AA);
+    /// assert_eq!(text.render(&RenderOptions::new()), "\x1b[38;2;48;48;48mdim\x1b[0m");
+    /// assert_ne!(text.render(&options), "\x1b[38;2;48;48;48mdim\x1b[0m");
+    /// ```
+    #[must_use]
+    pub fn min_contrast(mut self, target: ContrastTarget) -> Self {
+        self.min_contrast = Some(target);
+        self
+    }
+
+    /// Gets the minimum contrast, if it was set.
+    #[must_use]
+    pub fn get_min_contrast(&self) -> Option<ContrastTarget> {
+        self.min_contrast
+    }
+
+    /// Applies the minimum contrast to `style`, if both it and the background are set.
+    pub(crate) fn adjust(&self, style: Ansi) -> Ansi {
+        match (self.background, self.min_contrast) {
+            (Some(background), Some(target)) => style.with_min_contrast(background, target),
+            _ => style,
+        }
+    }
+}
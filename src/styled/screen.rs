@@ -0,0 +1,809 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use unicode_width::UnicodeWidthChar;
+
+use crate::{tokenize, Ansi, Csi, Palette, StyledText, Token};
+
+const TAB_WIDTH: usize = 8;
+/// The longest incomplete escape sequence kept between feeds.
+const MAX_PENDING: usize = 256;
+
+/// A single character cell of a [`Screen`].
+///
+/// A wide character, like most CJK characters and emoji, takes two cells: the first holds
+/// the character and the second is a placeholder, see [`Cell::is_continuation`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Cell {
+    ch: char,
+    /// Zero-width characters following `ch`, such as combining marks.
+    combining: String,
+    style: Ansi,
+    /// The number of columns taken, zero for the second half of a wide character.
+    width: u8,
+}
+
+impl Cell {
+    /// Creates a new [`Cell`], taking one or two columns depending on the width of `ch`.
+    #[must_use]
+    pub fn new(ch: char, style: Ansi) -> Self {
+        let width = if ch.width() == Some(2) { 2 } else { 1 };
+        Self {
+            ch,
+            combining: String::new(),
+            style,
+            width,
+        }
+    }
+
+    /// The placeholder for the second column of a wide character.
+    fn continuation(style: Ansi) -> Self {
+        Self {
+            ch: ' ',
+            combining: String::new(),
+            style,
+            width: 0,
+        }
+    }
+
+    /// Gets the character displayed in this cell, without any combining characters.
+    #[must_use]
+    pub const fn ch(&self) -> char {
+        self.ch
+    }
+
+    /// Gets the text displayed in this cell, i.e. the character followed by any combining
+    /// characters. This is empty for the second half of a wide character.
+    #[must_use]
+    pub fn text(&self) -> String {
+        if self.is_continuation() {
+            return String::new();
+        }
+        let mut text = String::with_capacity(self.ch.len_utf8() + self.combining.len());
+        text.push(self.ch);
+        text.push_str(&self.combining);
+        text
+    }
+
+    /// Gets the style of this cell.
+    #[must_use]
+    pub const fn style(&self) -> Ansi {
+        self.style
+    }
+
+    /// Gets the number of columns taken by this cell's character: 2 for a wide character,
+    /// 0 for the placeholder after one, and 1 otherwise.
+    #[must_use]
+    pub const fn width(&self) -> usize {
+        self.width as usize
+    }
+
+    /// Returns `true` if this is the placeholder in the second column of a wide character.
+    #[must_use]
+    pub const fn is_continuation(&self) -> bool {
+        self.width == 0
+    }
+
+    /// Returns `true` if this is an unstyled space, i.e. the cell is empty.
+    #[must_use]
+    pub fn is_blank(&self) -> bool {
+        self.width == 1 && self.ch == ' ' && self.combining.is_empty() && self.style.is_default()
+    }
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self::new(' ', Ansi::new())
+    }
+}
+
+/// Cursor position and style saved by `ESC 7` / `CSI s`.
+#[derive(Debug, Clone, Copy)]
+struct SavedCursor {
+    row: usize,
+    col: usize,
+    style: Ansi,
+}
+
+/// An in-memory terminal, interpreting a stream of text and escape sequences into a grid
+/// of styled [`Cell`]s.
+///
+/// This supports enough to check the output of most command line tools and simple TUIs:
+/// - printable text, with line wrapping (`CSI ? 7 h/l`), wide characters taking two cells
+///   and combining characters joining the previous cell
+/// - `\r`, `\n`, backspace and tabs
+/// - SGR styling, with indexed colors resolved through a [`Palette`]
+/// - cursor movement (`CSI A/B/C/D/E/F/G/H/f/d`), saving and restoring (`ESC 7/8`, `CSI s/u`)
+/// - erasing (`CSI J/K`), using the current background color
+/// - scroll regions (`CSI r`), scrolling (`CSI S/T`, `ESC D/M`) and inserting or deleting lines (`CSI L/M`)
+///
+/// Anything else (OSC, DCS, unknown sequences) is ignored.
+///
+/// ## Example
+/// ```
+/// # use ansirs::{Ansi, Screen};
+/// let mut screen = Screen::new(20, 3);
+/// screen.feed("Loading... 10%\r\x1b[1mDone!\x1b[K\r\n");
+/// screen.feed("\x1b[31mbye");
+///
+/// assert_eq!(screen.text(), "Done!\nbye\n");
+/// assert_eq!(screen.cell(0, 0).unwrap().style(), Ansi::new().bold());
+/// assert_eq!(screen.cursor(), (1, 3));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Screen {
+    width: usize,
+    height: usize,
+    grid: Vec<Vec<Cell>>,
+    row: usize,
+    col: usize,
+    /// Set after printing in the last column, the next printed char wraps first.
+    pending_wrap: bool,
+    auto_wrap: bool,
+    style: Ansi,
+    scroll_top: usize,
+    scroll_bottom: usize,
+    saved: Option<SavedCursor>,
+    palette: Palette,
+    /// Bytes at the end of the last feed that could not be processed yet, i.e. an
+    /// incomplete UTF-8 character or escape sequence.
+    pending: Vec<u8>,
+}
+
+impl Screen {
+    /// Creates a new, empty screen with the given size, using the default [`Palette`].
+    ///
+    /// # Panics
+    /// Panics if `width` or `height` is zero.
+    #[must_use]
+    pub fn new(width: usize, height: usize) -> Self {
+        assert!(width > 0 && height > 0, "screen size must be non-zero");
+        Self {
+            width,
+            height,
+            grid: vec![vec![Cell::default(); width]; height],
+            row: 0,
+            col: 0,
+            pending_wrap: false,
+            auto_wrap: true,
+            style: Ansi::new(),
+            scroll_top: 0,
+            scroll_bottom: height - 1,
+            saved: None,
+            palette: Palette::default(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Builder function to set the [`Palette`] used to resolve indexed colors.
+    #[must_use]
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    /// Gets the width of the screen in cells.
+    #[must_use]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Gets the height of the screen in cells.
+    #[must_use]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Gets the cursor position as `(row, column)`, both starting at zero.
+    #[must_use]
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.row, self.col)
+    }
+
+    /// Gets the current scroll region as `(top, bottom)` rows, inclusive and starting at zero.
+    #[must_use]
+    pub fn scroll_region(&self) -> (usize, usize) {
+        (self.scroll_top, self.scroll_bottom)
+    }
+
+    /// Gets the style that will be used for the next printed character.
+    #[must_use]
+    pub fn style(&self) -> Ansi {
+        self.style
+    }
+
+    /// Gets the cell at the given position, or `None` if it is outside the screen.
+    #[must_use]
+    pub fn cell(&self, row: usize, col: usize) -> Option<&Cell> {
+        self.grid.get(row).and_then(|r| r.get(col))
+    }
+
+    /// Gets the given row as [`StyledText`], without any trailing blank cells.
+    /// Returns `None` if `row` is outside the screen.
+    #[must_use]
+    pub fn line(&self, row: usize) -> Option<StyledText> {
+        let cells = self.grid.get(row)?;
+        let len = cells
+            .iter()
+            .rposition(|c| !c.is_blank())
+            .map_or(0, |i| i + 1);
+        let mut text = StyledText::new();
+        for (i, cell) in cells[..len].iter().enumerate() {
+            if !cell.is_continuation() {
+                text.push(cell.text(), cell.style);
+            } else if i == 0 || cells[i - 1].width() != 2 {
+                // What is left of a wide character that was partly overwritten.
+                text.push(" ", cell.style);
+            }
+        }
+        Some(text)
+    }
+
+    /// Gets every row of the screen as [`StyledText`], see [`Screen::line`].
+    #[must_use]
+    pub fn lines(&self) -> Vec<StyledText> {
+        (0..self.height).filter_map(|row| self.line(row)).collect()
+    }
+
+    /// Gets the plain text content of the screen, one line per row with trailing blanks
+    /// removed, and without any trailing empty rows.
+    #[must_use]
+    pub fn text(&self) -> String {
+        let lines = self
+            .lines()
+            .iter()
+            .map(StyledText::plain_text)
+            .collect::<Vec<_>>();
+        let len = lines
+            .iter()
+            .rposition(|l| !l.is_empty())
+            .map_or(0, |i| i + 1);
+        lines[..len].iter().fold(String::new(), |mut out, line| {
+            out.push_str(line);
+            out.push('\n');
+            out
+        })
+    }
+
+    /// Feeds output to the screen. Input does not have to end on a character or escape
+    /// sequence boundary, anything incomplete is buffered until the next call. Invalid
+    /// UTF-8 is displayed as `U+FFFD`.
+    pub fn feed(&mut self, bytes: impl AsRef<[u8]>) {
+        let mut data = std::mem::take(&mut self.pending);
+        data.extend_from_slice(bytes.as_ref());
+
+        let mut rest = data.as_slice();
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(s) => {
+                    self.feed_str(s, true);
+                    break;
+                }
+                Err(err) => {
+                    let (valid, after) = rest.split_at(err.valid_up_to());
+                    let valid = std::str::from_utf8(valid).unwrap_or_default();
+                    let Some(len) = err.error_len() else {
+                        // An incomplete character, wait for the rest of it.
+                        self.feed_str(valid, true);
+                        self.pending.extend_from_slice(after);
+                        break;
+                    };
+                    self.feed_str(valid, false);
+                    self.print('\u{FFFD}');
+                    rest = &after[len..];
+                }
+            }
+        }
+    }
+
+    /// Processes a chunk of valid UTF-8. An incomplete escape sequence at the end is kept
+    /// for the next feed if `at_end` is set, otherwise it is dropped.
+    fn feed_str(&mut self, input: &str, at_end: bool) {
+        for token in tokenize(input) {
+            match token {
+                Token::Text(text) => text.chars().for_each(|c| self.print(c)),
+                Token::Control(c) => self.control(c),
+                Token::Csi(csi) => self.csi(&csi),
+                Token::Escape(c) => self.escape(c),
+                Token::Incomplete(raw) if at_end => self.keep_incomplete(raw),
+                Token::Osc(_) | Token::Dcs(_) | Token::Invalid(_) | Token::Incomplete(_) => {}
+            }
+        }
+    }
+
+    /// Keeps an incomplete escape sequence for the next feed. The contents of OSC and DCS
+    /// strings are ignored, so only enough to find their end is kept, and other sequences
+    /// that grow past [`MAX_PENDING`] bytes are dropped.
+    fn keep_incomplete(&mut self, raw: &str) {
+        if raw.starts_with("\x1b]") || raw.starts_with("\x1bP") {
+            self.pending.extend_from_slice(&raw.as_bytes()[..2]);
+            // The first half of an `ESC \` terminator.
+            if raw.len() > 2 && raw.ends_with('\x1b') {
+                self.pending.push(0x1b);
+            }
+        } else if raw.len() <= MAX_PENDING {
+            self.pending.extend_from_slice(raw.as_bytes());
+        }
+    }
+
+    /// The cell used when erasing, which keeps the current background color.
+    fn blank(&self) -> Cell {
+        Cell::new(
+            ' ',
+            Ansi::from_parts(None, self.style.background(), crate::AnsiFlags::empty()),
+        )
+    }
+
+    fn print(&mut self, c: char) {
+        let width = match c.width() {
+            Some(0) => return self.combine(c),
+            Some(width) => width,
+            None => return,
+        };
+
+        if self.pending_wrap {
+            self.pending_wrap = false;
+            self.col = 0;
+            self.linefeed();
+        }
+        if width == 2 && self.col + 1 == self.width {
+            // A wide character doesn't fit in the last column.
+            if self.width == 1 {
+                return;
+            }
+            if self.auto_wrap {
+                self.col = 0;
+                self.linefeed();
+            } else {
+                self.col -= 1;
+            }
+        }
+
+        self.split_wide(self.col);
+        self.grid[self.row][self.col] = Cell::new(c, self.style);
+        if width == 2 {
+            self.split_wide(self.col + 1);
+            self.grid[self.row][self.col + 1] = Cell::continuation(self.style);
+        }
+        if self.col + width < self.width {
+            self.col += width;
+        } else {
+            self.col = self.width - 1;
+            self.pending_wrap = self.auto_wrap;
+        }
+    }
+
+    /// Appends a zero-width character to the last printed cell, if there is one.
+    fn combine(&mut self, c: char) {
+        let col = if self.pending_wrap {
+            self.col
+        } else if self.col > 0 {
+            self.col - 1
+        } else {
+            return;
+        };
+        let row = &mut self.grid[self.row];
+        let col = if row[col].is_continuation() && col > 0 {
+            col - 1
+        } else {
+            col
+        };
+        if !row[col].is_continuation() {
+            row[col].combining.push(c);
+        }
+    }
+
+    /// Blanks the other half of a wide character at `col`, before part of it is replaced.
+    fn split_wide(&mut self, col: usize) {
+        let row = &mut self.grid[self.row];
+        let other = match row[col].width {
+            0 if col > 0 => col - 1,
+            2 if col + 1 < row.len() => col + 1,
+            _ => return,
+        };
+        row[other] = Cell::new(' ', row[other].style);
+    }
+
+    fn control(&mut self, c: char) {
+        match c {
+            '\r' => self.set_col(0),
+            '\n' | '\x0b' | '\x0c' => self.linefeed(),
+            '\x08' => self.set_col(self.col.saturating_sub(1)),
+            '\t' => self.set_col((self.col / TAB_WIDTH + 1) * TAB_WIDTH),
+            _ => {}
+        }
+    }
+
+    fn escape(&mut self, c: char) {
+        match c {
+            '7' => self.save_cursor(),
+            '8' => self.restore_cursor(),
+            'D' => self.linefeed(),
+            'E' => {
+                self.set_col(0);
+                self.linefeed();
+            }
+            'M' => self.reverse_index(),
+            'c' => *self = Self::new(self.width, self.height).with_palette(self.palette.clone()),
+            _ => {}
+        }
+    }
+
+    fn csi(&mut self, csi: &Csi<'_>) {
+        let n = csi.param(0).map_or(1, |n| usize::from(n).max(1));
+
+        if csi.private_marker() == Some('?') {
+            if csi.params() == "7" {
+                match csi.final_byte() {
+                    'h' => self.auto_wrap = true,
+                    'l' => self.auto_wrap = false,
+                    _ => {}
+                }
+            }
+            return;
+        }
+        if csi.private_marker().is_some() || !csi.intermediates().is_empty() {
+            return;
+        }
+
+        match csi.final_byte() {
+            'm' => self.style = self.style.apply_sgr(csi.params(), &self.palette),
+            'A' => {
+                let top = if self.row >= self.scroll_top {
+                    self.scroll_top
+                } else {
+                    0
+                };
+                self.set_row(self.row.saturating_sub(n).max(top));
+            }
+            'B' => {
+                let bottom = if self.row <= self.scroll_bottom {
+                    self.scroll_bottom
+                } else {
+                    self.height - 1
+                };
+                self.set_row((self.row + n).min(bottom));
+            }
+            'C' => self.set_col(self.col + n),
+            'D' => self.set_col(self.col.saturating_sub(n)),
+            'E' => {
+                self.set_row(self.row + n);
+                self.set_col(0);
+            }
+            'F' => {
+                self.set_row(self.row.saturating_sub(n));
+                self.set_col(0);
+            }
+            'G' | '`' => self.set_col(n - 1),
+            'd' => self.set_row(n - 1),
+            'H' | 'f' => {
+                let col = csi.param(1).map_or(1, |n| usize::from(n).max(1));
+                self.set_row(n - 1);
+                self.set_col(col - 1);
+            }
+            'J' => self.erase_display(csi.param(0).unwrap_or(0)),
+            'K' => self.erase_line(csi.param(0).unwrap_or(0)),
+            'r' => {
+                let top = csi.param(0).map_or(1, |n| usize::from(n).max(1)) - 1;
+                let bottom = csi
+                    .param(1)
+                    .filter(|&n| n != 0)
+                    .map_or(self.height, |n| usize::from(n).min(self.height))
+                    - 1;
+                if top < bottom {
+                    self.scroll_top = top;
+                    self.scroll_bottom = bottom;
+                    self.set_row(0);
+                    self.set_col(0);
+                }
+            }
+            'S' => self.scroll_up(self.scroll_top, n),
+            'T' => self.scroll_down(self.scroll_top, n),
+            'L' if (self.scroll_top..=self.scroll_bottom).contains(&self.row) => {
+                self.scroll_down(self.row, n);
+                self.set_col(0);
+            }
+            'M' if (self.scroll_top..=self.scroll_bottom).contains(&self.row) => {
+                self.scroll_up(self.row, n);
+                self.set_col(0);
+            }
+            's' => self.save_cursor(),
+            'u' => self.restore_cursor(),
+            _ => {}
+        }
+    }
+
+    fn set_row(&mut self, row: usize) {
+        self.row = row.min(self.height - 1);
+        self.pending_wrap = false;
+    }
+
+    fn set_col(&mut self, col: usize) {
+        self.col = col.min(self.width - 1);
+        self.pending_wrap = false;
+    }
+
+    fn linefeed(&mut self) {
+        if self.row == self.scroll_bottom {
+            self.scroll_up(self.scroll_top, 1);
+        } else if self.row + 1 < self.height {
+            self.row += 1;
+        }
+        self.pending_wrap = false;
+    }
+
+    fn reverse_index(&mut self) {
+        if self.row == self.scroll_top {
+            self.scroll_down(self.scroll_top, 1);
+        } else if self.row > 0 {
+            self.row -= 1;
+        }
+        self.pending_wrap = false;
+    }
+
+    /// Moves the rows from `top` to the bottom of the scroll region up by `n`, filling the
+    /// bottom with blank rows.
+    fn scroll_up(&mut self, top: usize, n: usize) {
+        let blank = vec![self.blank(); self.width];
+        let region = &mut self.grid[top..=self.scroll_bottom];
+        let n = n.min(region.len());
+        region.rotate_left(n);
+        let len = region.len();
+        region[len - n..].fill(blank);
+    }
+
+    /// Moves the rows from `top` to the bottom of the scroll region down by `n`, filling the
+    /// top with blank rows.
+    fn scroll_down(&mut self, top: usize, n: usize) {
+        let blank = vec![self.blank(); self.width];
+        let region = &mut self.grid[top..=self.scroll_bottom];
+        let n = n.min(region.len());
+        region.rotate_right(n);
+        region[..n].fill(blank);
+    }
+
+    fn erase_line(&mut self, mode: u16) {
+        self.split_wide(self.col);
+        let blank = self.blank();
+        let row = &mut self.grid[self.row];
+        match mode {
+            0 => row[self.col..].fill(blank),
+            1 => row[..=self.col].fill(blank),
+            2 => row.fill(blank),
+            _ => {}
+        }
+    }
+
+    fn erase_display(&mut self, mode: u16) {
+        let blank = self.blank();
+        match mode {
+            0 => {
+                self.erase_line(0);
+                self.grid[self.row + 1..]
+                    .iter_mut()
+                    .for_each(|r| r.fill(blank.clone()));
+            }
+            1 => {
+                self.erase_line(1);
+                self.grid[..self.row]
+                    .iter_mut()
+                    .for_each(|r| r.fill(blank.clone()));
+            }
+            2 | 3 => self.grid.iter_mut().for_each(|r| r.fill(blank.clone())),
+            _ => {}
+        }
+    }
+
+    fn save_cursor(&mut self) {
+        self.saved = Some(SavedCursor {
+            row: self.row,
+            col: self.col,
+            style: self.style,
+        });
+    }
+
+    fn restore_cursor(&mut self) {
+        let saved = self.saved.unwrap_or(SavedCursor {
+            row: 0,
+            col: 0,
+            style: Ansi::new(),
+        });
+        self.set_row(saved.row);
+        self.set_col(saved.col);
+        self.style = saved.style;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn screen(width: usize, height: usize, input: &str) -> Screen {
+        let mut screen = Screen::new(width, height);
+        screen.feed(input);
+        screen
+    }
+
+    #[test]
+    fn wrapping() {
+        let s = screen(4, 3, "abcdefgh");
+        assert_eq!(s.text(), "abcd\nefgh\n");
+        // The cursor stays on the last column until the next char is printed.
+        assert_eq!(s.cursor(), (1, 3));
+
+        let s = screen(4, 3, "abcd\r\nx");
+        assert_eq!(s.text(), "abcd\nx\n");
+
+        let s = screen(4, 3, "\x1b[?7labcdefgh");
+        assert_eq!(s.text(), "abch\n");
+    }
+
+    #[test]
+    fn wide_characters() {
+        let s = screen(6, 2, "日本語");
+        assert_eq!(s.text(), "日本語\n");
+        assert_eq!(s.cell(0, 2).map(Cell::width), Some(2));
+        assert!(s.cell(0, 3).unwrap().is_continuation());
+        assert_eq!(s.cursor(), (0, 5));
+        let s = screen(6, 2, "日本語x👍");
+        assert_eq!(s.text(), "日本語\nx👍\n");
+        assert_eq!(s.cursor(), (1, 3));
+
+        // With a single column left, a wide character wraps early.
+        let s = screen(5, 2, "abcd日x");
+        assert_eq!(s.text(), "abcd\n日x\n");
+        assert_eq!(s.cursor(), (1, 3));
+        let s = screen(5, 1, "\x1b[?7labcd日");
+        assert_eq!(s.text(), "abc日\n");
+
+        // Overwriting either half of a wide character blanks the other half.
+        let s = screen(6, 1, "日本\x1b[2Gx");
+        assert_eq!(s.text(), " x本\n");
+        let s = screen(6, 1, "日本\x1b[3Gx");
+        assert_eq!(s.text(), "日x\n");
+        let s = screen(6, 1, "日本\x1b[4G\x1b[K");
+        assert_eq!(s.text(), "日\n");
+    }
+
+    #[test]
+    fn combining_characters() {
+        let s = screen(5, 1, "e\u{301}x");
+        assert_eq!(s.text(), "e\u{301}x\n");
+        assert_eq!(s.cell(0, 0).unwrap().text(), "e\u{301}");
+        assert_eq!(s.cell(0, 0).unwrap().ch(), 'e');
+        assert_eq!(s.cursor(), (0, 2));
+
+        // At the right margin, the mark joins the last cell instead of wrapping.
+        let s = screen(3, 2, "abe\u{301}");
+        assert_eq!(s.text(), "abe\u{301}\n");
+        assert_eq!(s.cursor(), (0, 2));
+        let s = screen(3, 2, "abe\u{301}x");
+        assert_eq!(s.text(), "abe\u{301}\nx\n");
+
+        // Marks after a wide character join the character, not its placeholder.
+        let s = screen(4, 1, "日\u{301}\u{fe0f}");
+        assert_eq!(s.cell(0, 0).unwrap().text(), "日\u{301}\u{fe0f}");
+        assert_eq!(s.text(), "日\u{301}\u{fe0f}\n");
+
+        // There is nothing to combine with at the start of a line.
+        let s = screen(4, 1, "\u{301}a");
+        assert_eq!(s.text(), "a\n");
+    }
+
+    #[test]
+    fn scrolling() {
+        let s = screen(5, 2, "1\r\n2\r\n3");
+        assert_eq!(s.text(), "2\n3\n");
+        // A bare line feed keeps the column.
+        let s = screen(5, 2, "1\n2");
+        assert_eq!(s.text(), "1\n 2\n");
+
+        // Only the scroll region moves.
+        let s = screen(5, 4, "head\x1b[2;3r\x1b[2;1Ha\r\nb\r\nc\x1b[4;1Hfoot");
+        assert_eq!(s.text(), "head\nb\nc\nfoot\n");
+        assert_eq!(s.scroll_region(), (1, 2));
+
+        let s = screen(5, 3, "a\r\nb\r\nc\x1b[H\x1bMz");
+        assert_eq!(s.text(), "z\na\nb\n");
+
+        let s = screen(5, 3, "a\r\nb\r\nc\x1b[2;1H\x1b[Mx");
+        assert_eq!(s.text(), "a\nx\n");
+        let s = screen(5, 3, "a\r\nb\r\nc\x1b[2;1H\x1b[Lx");
+        assert_eq!(s.text(), "a\nx\nb\n");
+
+        // A bottom of zero means the last line, like an omitted one.
+        for input in [
+            "\x1b[2;3r\x1b[1;0r",
+            "\x1b[2;3r\x1b[0;0r",
+            "\x1b[2;3r\x1b[r",
+        ] {
+            assert_eq!(screen(5, 3, input).scroll_region(), (0, 2), "{input:?}");
+        }
+    }
+
+    #[test]
+    fn cursor_movement() {
+        let s = screen(10, 5, "\x1b[3;4Hx\x1b[Ay\x1b[2Dz\x1b[Bw\x1b[1G!\x1b[5d?");
+        assert_eq!(s.text(), "\n   zy\n!  xw\n\n ?\n");
+
+        let s = screen(10, 2, "abc\x1b7\x1b[31m\x1b[2;5Hxy\x1b8d");
+        assert_eq!(s.text(), "abcd\n    xy\n");
+        assert_eq!(s.cell(0, 3).unwrap().style(), Ansi::new());
+
+        let s = screen(20, 1, "a\tb\x08\x08c\t\td");
+        assert_eq!(s.text(), "a      cb          d\n");
+    }
+
+    #[test]
+    fn erasing() {
+        let s = screen(5, 3, "aaaaa\r\nbbbbb\r\nccccc\x1b[2;3H\x1b[K");
+        assert_eq!(s.text(), "aaaaa\nbb\nccccc\n");
+        let s = screen(5, 3, "aaaaa\r\nbbbbb\r\nccccc\x1b[2;3H\x1b[1K");
+        assert_eq!(s.text(), "aaaaa\n   bb\nccccc\n");
+        let s = screen(5, 3, "aaaaa\r\nbbbbb\r\nccccc\x1b[2;3H\x1b[J");
+        assert_eq!(s.text(), "aaaaa\nbb\n");
+        let s = screen(5, 3, "aaaaa\r\nbbbbb\r\nccccc\x1b[2;3H\x1b[1J");
+        assert_eq!(s.text(), "\n   bb\nccccc\n");
+
+        // Erasing uses the background color.
+        let s = screen(3, 1, "\x1b[1;44m\x1b[2J");
+        let blue = Ansi::from_bg(Palette::default().get(4));
+        assert_eq!(s.cell(0, 2), Some(&Cell::new(' ', blue)));
+    }
+
+    #[test]
+    fn styled_lines() {
+        let s = screen(10, 2, "\x1b[1mbold\x1b[0m plain\x1b[7m");
+        assert_eq!(
+            s.line(0),
+            Some(
+                StyledText::new()
+                    .with("bold", Ansi::new().bold())
+                    .with(" plain", Ansi::new())
+            )
+        );
+        assert_eq!(s.style(), Ansi::new().reverse());
+        assert_eq!(s.lines().len(), 2);
+        assert_eq!(s.line(2), None);
+    }
+
+    #[test]
+    fn split_feeds() {
+        let input = "\x1b[1;31mhé\x1b]0;title\x07llo\x1b[0m";
+        let expected = screen(10, 1, input);
+
+        for split in 1..input.len() {
+            let mut s = Screen::new(10, 1);
+            s.feed(&input.as_bytes()[..split]);
+            s.feed(&input.as_bytes()[split..]);
+            assert_eq!(s.lines(), expected.lines(), "split at {split}");
+        }
+
+        let mut s = Screen::new(10, 1);
+        s.feed(b"a\xffb");
+        assert_eq!(s.text(), "a\u{FFFD}b\n");
+    }
+
+    #[test]
+    fn long_incomplete_sequences() {
+        // Unterminated strings don't keep growing the buffer, but still hide their contents.
+        let mut s = Screen::new(10, 1);
+        s.feed("a\x1b]52;c;");
+        for _ in 0..1000 {
+            s.feed("QUJD".repeat(100));
+            assert!(s.pending.len() <= 3);
+        }
+        s.feed("\x1b");
+        s.feed("\\b\x1bPdata");
+        s.feed("more\x1b\\c");
+        assert_eq!(s.text(), "abc\n");
+
+        let mut s = Screen::new(10, 1);
+        s.feed(format!("\x1b[{}", "1;".repeat(1000)));
+        assert!(s.pending.is_empty());
+    }
+}
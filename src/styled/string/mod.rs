@@ -47,6 +47,7 @@ mod tests {
 
     #[test]
     #[ignore]
+    #[allow(clippy::ignore_without_reason)]
     fn compare_all() {
         sizeof();
         println!();
@@ -55,6 +56,7 @@ mod tests {
 
     #[test]
     #[ignore]
+    #[allow(clippy::ignore_without_reason)]
     fn sizeof() {
         println!("Sizeof Comparison:");
         println!(
@@ -81,6 +83,7 @@ mod tests {
 
     #[test]
     #[ignore]
+    #[allow(clippy::ignore_without_reason)]
     fn performance() {
         const ANSI_STR: &str = "\u{1b}[3;4;9;38;2;200;100;200;48;2;255;255;255m";
         let text = "Hello there world, glad to meet ya!";
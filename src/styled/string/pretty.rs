@@ -1,4 +1,4 @@
-use crate::{Ansi, IntoAnsi};
+use crate::{Ansi, Hyperlink, IntoAnsi, RenderOptions, StyledText};
 
 /// A string and some styling.
 ///
@@ -25,13 +25,13 @@ use crate::{Ansi, IntoAnsi};
 /// This would require an Ansi function that can parse a string of ansi codes.
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-pub struct PrettyString(String, Option<Ansi>);
+pub struct PrettyString(String, Option<Ansi>, Option<Hyperlink>);
 
 impl PrettyString {
     /// Create a [`PrettyString`] with no styling.
     #[must_use]
     pub fn plain(s: impl Into<String>) -> Self {
-        Self(s.into(), None)
+        Self(s.into(), None, None)
     }
 
     /// Takes a plain string `text` and a style `format`.
@@ -46,6 +46,7 @@ impl PrettyString {
             } else {
                 Some(style)
             },
+            None,
         )
     }
 
@@ -61,6 +62,29 @@ impl PrettyString {
         self.1.as_ref()
     }
 
+    /// Builder function to turn this text into a [`Hyperlink`].
+    ///
+    /// ```
+    /// # use ansirs::{string::PrettyString, Ansi};
+    /// let pretty = PrettyString::new("docs", Ansi::new().underline()).with_link("https://docs.rs");
+    /// assert_eq!(pretty.link().map(|link| link.uri()), Some("https://docs.rs"));
+    /// assert_eq!(
+    ///     pretty.to_string(),
+    ///     "\x1b[4m\x1b]8;;https://docs.rs\x1b\\docs\x1b]8;;\x1b\\\x1b[0m"
+    /// );
+    /// ```
+    #[must_use]
+    pub fn with_link(mut self, link: impl Into<Hyperlink>) -> Self {
+        self.2 = Some(link.into());
+        self
+    }
+
+    /// Get the [`Hyperlink`] attached to this text, if any.
+    #[must_use]
+    pub fn link(&self) -> Option<&Hyperlink> {
+        self.2.as_ref()
+    }
+
     /// Modify the styling applied to this text using the given closure.
     pub fn modify_style<F: FnMut(Option<&Ansi>) -> Option<Ansi>>(&mut self, mut f: F) {
         self.1 = f(self.1.as_ref());
@@ -83,14 +107,56 @@ impl PrettyString {
     pub fn is_empty(&self) -> bool {
         self.0.is_empty()
     }
+
+    /// Get the formatted value with the escape sequences made visible, e.g. `\e[1mtext\e[0m`.
+    /// This is the same as formatting with the alternate flag (`{:#}`).
+    #[must_use]
+    pub fn escaped(&self) -> String {
+        crate::escape_ansi(&self.to_string())
+    }
+
+    /// Get the text wrapped in a markup-like tag describing the style, e.g.
+    /// `<bold fg=#FF0000>text</>`. Unstyled text is returned as is.
+    #[must_use]
+    pub fn to_semantic(&self) -> String {
+        match self.1 {
+            Some(ref style) => format!("{}{}</>", style.to_semantic(), self.0),
+            None => self.0.clone(),
+        }
+    }
+
+    /// Renders the styled text using the given [`RenderOptions`], e.g. to leave out the
+    /// hyperlink or to follow [contrast enforcement](crate::enforce_contrast) with
+    /// [`RenderOptions::from_global`]. Displaying a [`PrettyString`] uses the default options.
+    ///
+    /// ```
+    /// # use ansirs::{string::PrettyString, Ansi, RenderOptions};
+    /// let pretty = PrettyString::new("docs", Ansi::new().underline()).with_link("https://docs.rs");
+    /// let options = RenderOptions::new().hyperlinks(false);
+    /// assert_eq!(pretty.render(&options), "\x1b[4mdocs\x1b[0m");
+    /// ```
+    #[must_use]
+    pub fn render(&self, options: &RenderOptions) -> String {
+        let mut out = String::new();
+        // Writing to a `String` can't fail.
+        let _ = self.write_raw(&mut out, *options);
+        out
+    }
+
+    /// Writes the styled text the same way as the equivalent [`StyledText`].
+    fn write_raw(&self, f: &mut impl std::fmt::Write, options: RenderOptions) -> std::fmt::Result {
+        StyledText::from(self).write_raw(f, options)
+    }
 }
 
 impl std::fmt::Display for PrettyString {
+    /// Writes the styled text using the default [`RenderOptions`]. The alternate flag (`{:#}`)
+    /// makes the escape sequences visible, see [`PrettyString::escaped`].
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self.1 {
-            Some(ref style) => write!(f, "{}", style.paint_text(self.0.as_str())),
-            None => write!(f, "{}", self.0),
+        if f.alternate() {
+            return f.write_str(&self.escaped());
         }
+        self.write_raw(f, RenderOptions::new())
     }
 }
 
@@ -145,6 +211,16 @@ mod tests {
         );
     }
 
+    #[test]
+    fn debug_forms() {
+        let pretty = PrettyString::new("hi", Ansi::new().bold().fg((255, 0, 0)));
+        assert_eq!(pretty.escaped(), "\\e[1;38;2;255;0;0mhi\\e[0m");
+        assert_eq!(format!("{pretty:#}"), pretty.escaped());
+        assert_eq!(pretty.to_semantic(), "<bold fg=#FF0000>hi</>");
+        assert_eq!(PrettyString::plain("hi").to_semantic(), "hi");
+        assert_eq!(format!("{:#}", PrettyString::plain("hi")), "hi");
+    }
+
     #[test]
     fn modify_style() {
         let style1 = Ansi::new().fg((25, 100, 250)).bold().underline();
@@ -175,4 +251,49 @@ mod tests {
         let string: String = pretty.borrow().into();
         assert_eq!(string, "Hello");
     }
+
+    #[test]
+    fn render_options() {
+        let pretty = PrettyString::new("dim", Ansi::from_fg((0x30, 0x30, 0x30))).with_link("a:b");
+        assert_eq!(pretty.render(&RenderOptions::new()), pretty.to_string());
+        assert_eq!(
+            pretty.render(&RenderOptions::new().hyperlinks(false)),
+            "\x1b[38;2;48;48;48mdim\x1b[0m"
+        );
+        assert_eq!(PrettyString::plain("a").render(&RenderOptions::new()), "a");
+
+        let options = RenderOptions::new()
+            .hyperlinks(false)
+            .background(crate::Color::from_rgb(0, 0, 0))
+            .min_contrast(crate::ContrastTarget::AA);
+        let black = crate::Color::from_rgb(0, 0, 0);
+        let adjusted =
+            Ansi::from_fg((0x30, 0x30, 0x30)).with_min_contrast(black, crate::ContrastTarget::AA);
+        assert_ne!(adjusted, Ansi::from_fg((0x30, 0x30, 0x30)));
+        assert_eq!(
+            pretty.render(&options),
+            PrettyString::new("dim", adjusted).render(&RenderOptions::new())
+        );
+        assert_eq!(pretty.to_string(), pretty.render(&RenderOptions::new()));
+    }
+
+    #[test]
+    fn same_output_as_styled_text() {
+        let pretty = PrettyString::new("docs", Ansi::new().underline().fg((0x30, 0x30, 0x30)))
+            .with_link("https://docs.rs");
+        let text = StyledText::from(&pretty);
+        for options in [
+            RenderOptions::new(),
+            RenderOptions::new().hyperlinks(false),
+            RenderOptions::new()
+                .background(crate::Color::from_rgb(0, 0, 0))
+                .min_contrast(crate::ContrastTarget::AA),
+        ] {
+            assert_eq!(pretty.render(&options), text.render(&options));
+        }
+        assert_eq!(
+            pretty.render(&RenderOptions::new()),
+            "\x1b[4;38;2;48;48;48m\x1b]8;;https://docs.rs\x1b\\docs\x1b]8;;\x1b\\\x1b[0m"
+        );
+    }
 }
@@ -0,0 +1,439 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::fmt::Write;
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use super::html::{display_colors, escape_html};
+use crate::{Ansi, AnsiFlags, Palette, StyledText};
+
+/// Width of a single cell relative to the font size. This is a good approximation for
+/// most monospace fonts, every span is positioned explicitly so small errors don't add up.
+const CELL_WIDTH_RATIO: f64 = 0.6;
+/// Height of the window title bar drawn when [`SvgOptions::window_chrome`] is enabled.
+const CHROME_HEIGHT: f64 = 36.0;
+/// Colors of the "traffic light" buttons in the title bar.
+const CHROME_BUTTONS: [&str; 3] = ["#ff5f56", "#ffbd2e", "#27c93f"];
+/// Horizontal space taken by the buttons, including the gap before the title. The title is
+/// centered, so the same space is kept free to its right.
+const CHROME_BUTTONS_WIDTH: f64 = 86.0;
+const TAB_WIDTH: usize = 8;
+
+/// Options used when rendering styled text as an SVG "screenshot", see [`ansi_to_svg`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgOptions {
+    font_family: String,
+    font_size: f64,
+    line_height: f64,
+    padding: f64,
+    palette: Palette,
+    window_chrome: bool,
+    title: Option<String>,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self {
+            font_family: String::from("ui-monospace, SFMono-Regular, Menlo, Consolas, monospace"),
+            font_size: 14.0,
+            line_height: 1.4,
+            padding: 16.0,
+            palette: Palette::default(),
+            window_chrome: true,
+            title: None,
+        }
+    }
+}
+
+impl SvgOptions {
+    /// Creates the default options: a 14px system monospace font, the xterm palette and
+    /// window chrome with no title.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builder function to set the CSS `font-family` used for the text.
+    #[must_use]
+    pub fn font_family(mut self, family: impl Into<String>) -> Self {
+        self.font_family = family.into();
+        self
+    }
+
+    /// Builder function to set the font size in pixels.
+    #[must_use]
+    pub fn font_size(mut self, size: f64) -> Self {
+        self.font_size = size;
+        self
+    }
+
+    /// Builder function to set the line height, relative to the font size.
+    #[must_use]
+    pub fn line_height(mut self, line_height: f64) -> Self {
+        self.line_height = line_height;
+        self
+    }
+
+    /// Builder function to set the padding around the text in pixels.
+    #[must_use]
+    pub fn padding(mut self, padding: f64) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Builder function to set the [`Palette`], which provides the theme's background and
+    /// default foreground as well as the indexed colors.
+    #[must_use]
+    pub fn palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    /// Builder function to set whether a window title bar is drawn above the text.
+    #[must_use]
+    pub fn window_chrome(mut self, chrome: bool) -> Self {
+        self.window_chrome = chrome;
+        self
+    }
+
+    /// Builder function to set the title shown in the window title bar.
+    #[must_use]
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Gets the [`Palette`] in use.
+    #[must_use]
+    pub fn get_palette(&self) -> &Palette {
+        &self.palette
+    }
+}
+
+/// Renders text containing SGR escape sequences as an SVG image of a terminal window.
+///
+/// The output only depends on the input and `options`, so it can be checked into a
+/// repository and regenerated (or compared) in tests.
+///
+/// ## Example
+/// ```
+/// # use ansirs::{ansi_to_svg, SvgOptions};
+/// let svg = ansi_to_svg("\x1b[1;32m$\x1b[0m cargo test", &SvgOptions::new().title("demo"));
+/// assert!(svg.starts_with("<svg "));
+/// assert!(svg.contains("> cargo test</tspan>"));
+/// assert!(svg.contains(">demo</text>"));
+/// ```
+#[must_use]
+pub fn ansi_to_svg(input: &str, options: &SvgOptions) -> String {
+    StyledText::parse_with_palette(input, &options.palette).to_svg(options)
+}
+
+/// Formats a number with at most two decimals and no trailing zeros.
+fn num(value: f64) -> String {
+    let s = format!("{value:.2}");
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    if s == "-0" {
+        String::from("0")
+    } else {
+        s.to_string()
+    }
+}
+
+/// Expands tabs and drops other control characters. Returns the text along with the number
+/// of cells it takes up, where wide characters (e.g. CJK or emoji) take up two cells.
+fn cells(text: &str, start_col: usize) -> (String, usize) {
+    let mut out = String::with_capacity(text.len());
+    let mut col = start_col;
+    for grapheme in text.graphemes(true) {
+        if grapheme == "\t" {
+            let next = (col / TAB_WIDTH + 1) * TAB_WIDTH;
+            out.extend(std::iter::repeat_n(' ', next - col));
+            col = next;
+        } else if !grapheme.chars().all(char::is_control) {
+            out.push_str(grapheme);
+            col += grapheme.width();
+        }
+    }
+    (out, col - start_col)
+}
+
+/// Gets the width of the image for text that is `columns` cells wide.
+#[allow(clippy::cast_precision_loss)]
+fn canvas_width(columns: usize, options: &SvgOptions) -> f64 {
+    let cell_width = options.font_size * CELL_WIDTH_RATIO;
+    let width = options.padding * 2.0 + columns as f64 * cell_width;
+    if !options.window_chrome {
+        return width;
+    }
+    // Keep the buttons on the canvas and the centered title clear of them.
+    let title = options.title.as_deref().map_or(0.0, |title| {
+        CHROME_BUTTONS_WIDTH + title.width() as f64 * cell_width
+    });
+    width.max(CHROME_BUTTONS_WIDTH + title)
+}
+
+/// Writes the window title bar: three "traffic light" buttons and the optional title.
+#[allow(clippy::cast_precision_loss)]
+fn write_chrome(out: &mut String, options: &SvgOptions, width: f64) {
+    let palette = &options.palette;
+    for (i, color) in CHROME_BUTTONS.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            r#"<circle cx="{}" cy="{}" r="6" fill="{color}"/>"#,
+            num(20.0 + i as f64 * 20.0),
+            num(CHROME_HEIGHT / 2.0),
+        );
+    }
+    if let Some(title) = &options.title {
+        let _ = writeln!(
+            out,
+            r#"<text x="{}" y="{}" text-anchor="middle" dominant-baseline="middle" font-family="{}" font-size="{}" fill="{}" opacity="0.7">{}</text>"#,
+            num(width / 2.0),
+            num(CHROME_HEIGHT / 2.0),
+            escape_html(&options.font_family),
+            num(options.font_size),
+            palette.foreground().as_hex_lower(),
+            escape_html(title),
+        );
+    }
+}
+
+/// Writes the SVG presentation attributes for `style`. Blink is ignored.
+fn write_text_attributes(out: &mut String, style: Ansi, palette: &Palette) {
+    let flags = style.flags();
+    if let (Some(fg), _) = display_colors(style, palette) {
+        let _ = write!(out, r#" fill="{}""#, fg.as_hex_lower());
+    }
+    if flags.contains(AnsiFlags::BOLD) {
+        out.push_str(r#" font-weight="bold""#);
+    }
+    if flags.contains(AnsiFlags::DIM) {
+        out.push_str(r#" opacity="0.5""#);
+    }
+    if flags.contains(AnsiFlags::ITALIC) {
+        out.push_str(r#" font-style="italic""#);
+    }
+    match (
+        flags.contains(AnsiFlags::UNDERLINE),
+        flags.contains(AnsiFlags::STRIKE),
+    ) {
+        (true, true) => out.push_str(r#" text-decoration="underline line-through""#),
+        (true, false) => out.push_str(r#" text-decoration="underline""#),
+        (false, true) => out.push_str(r#" text-decoration="line-through""#),
+        (false, false) => {}
+    }
+}
+
+impl StyledText {
+    /// Renders this text as an SVG image of a terminal window, see [`ansi_to_svg`].
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn to_svg(&self, options: &SvgOptions) -> String {
+        let palette = &options.palette;
+        let cell_width = options.font_size * CELL_WIDTH_RATIO;
+        let row_height = options.font_size * options.line_height;
+        let chrome = if options.window_chrome {
+            CHROME_HEIGHT
+        } else {
+            0.0
+        };
+
+        // Lay everything out first so the total size is known.
+        let lines = self
+            .lines()
+            .iter()
+            .map(|line| {
+                let mut col = 0;
+                line.spans()
+                    .iter()
+                    .map(|span| {
+                        let (text, width) = cells(span.text(), col);
+                        let start = col;
+                        col += width;
+                        (start, width, text, span.style())
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let columns = lines
+            .iter()
+            .filter_map(|line| line.last().map(|(start, width, ..)| start + width))
+            .max()
+            .unwrap_or(0);
+
+        let width = canvas_width(columns, options);
+        let height = chrome + options.padding * 2.0 + lines.len() as f64 * row_height;
+        let left = options.padding;
+        let top = chrome + options.padding;
+
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+            w = num(width),
+            h = num(height),
+        );
+        let _ = writeln!(
+            out,
+            r#"<rect width="{}" height="{}" rx="6" fill="{}"/>"#,
+            num(width),
+            num(height),
+            palette.background().as_hex_lower()
+        );
+
+        if options.window_chrome {
+            write_chrome(&mut out, options, width);
+        }
+
+        // Backgrounds go first so they never cover any text.
+        for (row, line) in lines.iter().enumerate() {
+            for (start, cols, _, style) in line {
+                if let (_, Some(bg)) = display_colors(*style, palette) {
+                    let _ = writeln!(
+                        out,
+                        r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                        num(left + *start as f64 * cell_width),
+                        num(top + row as f64 * row_height),
+                        num(*cols as f64 * cell_width),
+                        num(row_height),
+                        bg.as_hex_lower()
+                    );
+                }
+            }
+        }
+
+        let _ = writeln!(
+            out,
+            r#"<g font-family="{}" font-size="{}" fill="{}" xml:space="preserve">"#,
+            escape_html(&options.font_family),
+            num(options.font_size),
+            palette.foreground().as_hex_lower()
+        );
+        for (row, line) in lines.iter().enumerate() {
+            if line.is_empty() {
+                continue;
+            }
+            // Roughly centers the glyphs vertically in their row.
+            let baseline =
+                top + row as f64 * row_height + row_height.midpoint(options.font_size * 0.7);
+            let _ = write!(out, r#"<text y="{}">"#, num(baseline));
+            for (start, cols, text, style) in line {
+                let _ = write!(
+                    out,
+                    r#"<tspan x="{}""#,
+                    num(left + *start as f64 * cell_width)
+                );
+                // Fonts rarely draw wide or combined characters exactly on the grid.
+                if *cols != text.chars().count() {
+                    let _ = write!(
+                        out,
+                        r#" textLength="{}" lengthAdjust="spacingAndGlyphs""#,
+                        num(*cols as f64 * cell_width)
+                    );
+                }
+                write_text_attributes(&mut out, *style, palette);
+                let _ = write!(out, ">{}</tspan>", escape_html(text));
+            }
+            out.push_str("</text>\n");
+        }
+        out.push_str("</g>\n</svg>\n");
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn numbers() {
+        assert_eq!(num(1.0), "1");
+        assert_eq!(num(8.4), "8.4");
+        assert_eq!(num(1.0 / 3.0), "0.33");
+        assert_eq!(num(-0.001), "0");
+    }
+
+    #[test]
+    fn tabs_and_controls() {
+        assert_eq!(cells("a\tb", 0), (String::from("a       b"), 9));
+        assert_eq!(cells("\tb", 6), (String::from("  b"), 3));
+        assert_eq!(cells("a\x07b\r\n", 0), (String::from("ab"), 2));
+    }
+
+    #[test]
+    fn wide_and_combined_characters() {
+        assert_eq!(cells("日本\tx", 0), (String::from("日本    x"), 9));
+        assert_eq!(cells("e\u{301}🎉", 0), (String::from("e\u{301}🎉"), 3));
+
+        let svg = ansi_to_svg("日本\x1b[1mx", &SvgOptions::new().window_chrome(false));
+        assert!(svg.contains(
+            r#"<tspan x="16" textLength="33.6" lengthAdjust="spacingAndGlyphs">日本</tspan>"#
+        ));
+        assert!(svg.contains(r#"<tspan x="49.6" font-weight="bold">x</tspan>"#));
+    }
+
+    #[test]
+    fn full_render() {
+        let options = SvgOptions::new()
+            .font_family("mono")
+            .font_size(10.0)
+            .line_height(2.0)
+            .padding(4.0)
+            .title("t<1>");
+        let svg = ansi_to_svg("\x1b[1;31mab\x1b[0m c\n\x1b[7;4mrev", &options);
+        assert_eq!(
+            svg,
+            concat!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="196" height="84" viewBox="0 0 196 84">"#,
+                "\n",
+                r##"<rect width="196" height="84" rx="6" fill="#000000"/>"##,
+                "\n",
+                r##"<circle cx="20" cy="18" r="6" fill="#ff5f56"/>"##,
+                "\n",
+                r##"<circle cx="40" cy="18" r="6" fill="#ffbd2e"/>"##,
+                "\n",
+                r##"<circle cx="60" cy="18" r="6" fill="#27c93f"/>"##,
+                "\n",
+                r##"<text x="98" y="18" text-anchor="middle" dominant-baseline="middle" font-family="mono" font-size="10" fill="#c0c0c0" opacity="0.7">t&lt;1&gt;</text>"##,
+                "\n",
+                r##"<rect x="4" y="60" width="18" height="20" fill="#c0c0c0"/>"##,
+                "\n",
+                r##"<g font-family="mono" font-size="10" fill="#c0c0c0" xml:space="preserve">"##,
+                "\n",
+                r##"<text y="53.5"><tspan x="4" fill="#800000" font-weight="bold">ab</tspan><tspan x="16"> c</tspan></text>"##,
+                "\n",
+                r##"<text y="73.5"><tspan x="4" fill="#000000" text-decoration="underline">rev</tspan></text>"##,
+                "\n",
+                "</g>\n</svg>\n",
+            )
+        );
+    }
+
+    #[test]
+    fn chrome_fits_short_content() {
+        let svg = ansi_to_svg("a", &SvgOptions::new().padding(0.0));
+        assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="86" "#));
+        let svg = ansi_to_svg("a", &SvgOptions::new().padding(0.0).window_chrome(false));
+        assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="8.4" "#));
+    }
+
+    #[test]
+    fn deterministic_and_chromeless() {
+        let text = StyledText::new()
+            .with("hello ", Ansi::new().italic())
+            .with("world", Ansi::from_bg((1, 2, 3)));
+        let options = SvgOptions::new().window_chrome(false);
+        assert_eq!(text.to_svg(&options), text.to_svg(&options));
+        assert!(!text.to_svg(&options).contains("<circle"));
+        assert!(text
+            .to_svg(&options)
+            .contains(r##"<rect x="66.4" y="16" width="42" height="19.6" fill="#010203"/>"##));
+    }
+}
@@ -0,0 +1,512 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::{tokenize, Ansi, Hyperlink, IntoAnsi, LinkAction, Palette, RenderOptions, Token};
+
+/// A run of text that shares a single [`Ansi`] style, and optionally a [`Hyperlink`].
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    text: String,
+    style: Ansi,
+    link: Option<Hyperlink>,
+}
+
+impl Span {
+    /// Creates a new [`Span`] from the given text and style.
+    pub fn new(text: impl Into<String>, style: impl IntoAnsi) -> Self {
+        Self {
+            text: text.into(),
+            style: style.into_ansi(),
+            link: None,
+        }
+    }
+
+    /// Builder function to turn this span into a hyperlink.
+    ///
+    /// ```
+    /// # use ansirs::{Ansi, Hyperlink, Span, StyledText};
+    /// let span = Span::new("src/main.rs:4:2", Ansi::new().underline())
+    ///     .with_link("file:///project/src/main.rs");
+    /// assert_eq!(span.link(), Some(&Hyperlink::new("file:///project/src/main.rs")));
+    ///
+    /// let text = StyledText::from(span);
+    /// assert_eq!(
+    ///     text.to_string(),
+    ///     "\x1b[4m\x1b]8;;file:///project/src/main.rs\x1b\\src/main.rs:4:2\x1b]8;;\x1b\\\x1b[0m"
+    /// );
+    /// ```
+    #[must_use]
+    pub fn with_link(mut self, link: impl Into<Hyperlink>) -> Self {
+        self.link = Some(link.into());
+        self
+    }
+
+    /// Gets the (unstyled) text of this span.
+    #[must_use]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Gets the style of this span.
+    #[must_use]
+    pub fn style(&self) -> Ansi {
+        self.style
+    }
+
+    /// Gets the hyperlink of this span, if any.
+    #[must_use]
+    pub fn link(&self) -> Option<&Hyperlink> {
+        self.link.as_ref()
+    }
+}
+
+/// Text made up of multiple differently styled [`Span`]s.
+///
+/// This is the structured counterpart of a string containing escape sequences, it can be
+/// built up span by span or [parsed](StyledText::parse) out of existing terminal output.
+///
+/// ## Example
+/// ```
+/// # use ansirs::{Ansi, StyledText};
+/// let text = StyledText::parse("\x1b[1mwarning:\x1b[0m disk almost full");
+/// assert_eq!(text.spans().len(), 2);
+/// assert_eq!(text.spans()[0].style(), Ansi::new().bold());
+/// assert_eq!(text.plain_text(), "warning: disk almost full");
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StyledText {
+    spans: Vec<Span>,
+}
+
+impl StyledText {
+    /// Creates a new, empty [`StyledText`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses a string containing SGR escape sequences, resolving indexed colors through
+    /// the default (xterm) [`Palette`].
+    #[must_use]
+    pub fn parse(input: &str) -> Self {
+        Self::parse_with_palette(input, &Palette::default())
+    }
+
+    /// Parses a string containing SGR escape sequences, resolving indexed colors through
+    /// `palette`.
+    ///
+    /// Control characters (newlines, tabs, etc.) are kept as text, and hyperlinks (`OSC 8`)
+    /// are attached to the spans they surround. Any other escape sequence is dropped.
+    #[must_use]
+    pub fn parse_with_palette(input: &str, palette: &Palette) -> Self {
+        let mut text = Self::new();
+        let mut style = Ansi::new();
+        let mut link = None;
+
+        for token in tokenize(input) {
+            let content = match token {
+                Token::Text(s) => s.to_string(),
+                Token::Control(c) => c.to_string(),
+                Token::Csi(csi) if csi.is_sgr() => {
+                    style = style.apply_sgr(csi.params(), palette);
+                    continue;
+                }
+                Token::Osc(data) => {
+                    match Hyperlink::parse_osc(data) {
+                        Some(LinkAction::Open(opened)) => link = Some(opened),
+                        Some(LinkAction::Close) => link = None,
+                        None => {}
+                    }
+                    continue;
+                }
+                _ => continue,
+            };
+            text.push_span(Span {
+                text: content,
+                style,
+                link: link.clone(),
+            });
+        }
+
+        text
+    }
+
+    /// Appends `text` with the given `style`. Empty text is ignored, and text with the
+    /// same style as the last span is merged into it.
+    pub fn push(&mut self, text: impl Into<String>, style: impl IntoAnsi) {
+        self.push_span(Span::new(text, style));
+    }
+
+    /// Appends a [`Span`]. Empty spans are ignored, and spans with the same style and link
+    /// as the last span are merged into it.
+    pub fn push_span(&mut self, span: Span) {
+        if span.text.is_empty() {
+            return;
+        }
+
+        match self.spans.last_mut() {
+            Some(last) if last.style == span.style && last.link == span.link => {
+                last.text.push_str(&span.text);
+            }
+            _ => self.spans.push(span),
+        }
+    }
+
+    /// Builder version of [`StyledText::push`].
+    #[must_use]
+    pub fn with(mut self, text: impl Into<String>, style: impl IntoAnsi) -> Self {
+        self.push(text, style);
+        self
+    }
+
+    /// Builder version of [`StyledText::push_span`].
+    #[must_use]
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.push_span(span);
+        self
+    }
+
+    /// Gets the spans that make up this text.
+    #[must_use]
+    pub fn spans(&self) -> &[Span] {
+        &self.spans
+    }
+
+    /// Gets the text without any styling.
+    #[must_use]
+    pub fn plain_text(&self) -> String {
+        self.spans.iter().map(Span::text).collect()
+    }
+
+    /// Returns `true` if there is no text.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// Formats the text using markup-like tags instead of escape sequences, e.g.
+    /// `<bold fg=#FF0000>error:</> oops`. Unstyled text is written as is.
+    /// See [`Ansi::to_semantic`].
+    #[must_use]
+    pub fn to_semantic(&self) -> String {
+        let mut out = String::new();
+        for span in &self.spans {
+            if span.style.is_default() {
+                out.push_str(&span.text);
+            } else {
+                out.push_str(&span.style.to_semantic());
+                out.push_str(&span.text);
+                out.push_str("</>");
+            }
+        }
+        out
+    }
+
+    /// Splits the text on `\n`, keeping the styles of each piece. The newlines themselves
+    /// (and a `\r` directly preceding them) are not included.
+    #[must_use]
+    pub fn lines(&self) -> Vec<StyledText> {
+        let mut lines = Vec::new();
+        let mut current = Self::new();
+        for span in &self.spans {
+            let mut pieces = span.text.split('\n').peekable();
+            while let Some(piece) = pieces.next() {
+                let piece = if pieces.peek().is_some() {
+                    piece.strip_suffix('\r').unwrap_or(piece)
+                } else {
+                    piece
+                };
+                current.push_span(Span {
+                    text: piece.to_string(),
+                    style: span.style,
+                    link: span.link.clone(),
+                });
+                if pieces.peek().is_some() {
+                    lines.push(std::mem::take(&mut current));
+                }
+            }
+        }
+        lines.push(current);
+        lines
+    }
+
+    /// Creates a copy of this text with `f` applied to the style of every span. Spans that
+    /// end up with the same style are merged.
+    ///
+    /// ```
+    /// # use ansirs::{Ansi, StyledText};
+    /// let text = StyledText::new().with("a", Ansi::new().bold()).with("b", Ansi::new().italic());
+    /// let plain = text.map_styles(|_| Ansi::new());
+    /// assert_eq!(plain.spans().len(), 1);
+    /// assert_eq!(plain.to_string(), "ab");
+    /// ```
+    #[must_use]
+    pub fn map_styles(&self, mut f: impl FnMut(Ansi) -> Ansi) -> Self {
+        self.spans
+            .iter()
+            .map(|span| Span {
+                text: span.text.clone(),
+                style: f(span.style),
+                link: span.link.clone(),
+            })
+            .collect()
+    }
+}
+
+impl std::fmt::Display for StyledText {
+    /// Writes the text with escape sequences using the default [`RenderOptions`], so
+    /// [contrast enforcement](crate::enforce_contrast) doesn't apply, see
+    /// [`StyledText::render`] for that. The alternate flag (`{:#}`) makes the escape
+    /// sequences visible, see [`escape_ansi`](crate::escape_ansi).
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let options = RenderOptions::new();
+        if f.alternate() {
+            let mut raw = String::new();
+            self.write_raw(&mut raw, options)?;
+            return f.write_str(&crate::escape_ansi(&raw));
+        }
+
+        self.write_raw(f, options)
+    }
+}
+
+impl StyledText {
+    /// Renders the text with escape sequences using the given options.
+    ///
+    /// ```
+    /// # use ansirs::{Ansi, RenderOptions, Span, StyledText};
+    /// let text = StyledText::from(Span::new("docs", Ansi::new()).with_link("https://docs.rs"));
+    /// assert_eq!(text.render(&RenderOptions::new().hyperlinks(false)), "docs");
+    /// ```
+    #[must_use]
+    pub fn render(&self, options: &RenderOptions) -> String {
+        let mut out = String::new();
+        // Writing to a `String` can't fail.
+        let _ = self.write_raw(&mut out, *options);
+        out
+    }
+
+    /// Writes the text with escape sequences, using the shortest transition between the
+    /// styles of consecutive spans (see [`Ansi::transition_to`]).
+    pub(crate) fn write_raw(
+        &self,
+        f: &mut impl std::fmt::Write,
+        options: RenderOptions,
+    ) -> std::fmt::Result {
+        let mut current = Ansi::new();
+        let mut current_link = None;
+        for span in &self.spans {
+            let style = options.adjust(span.style);
+            f.write_str(&current.transition_to(&style))?;
+            current = style;
+
+            let link = span.link.as_ref().filter(|_| options.hyperlinks_enabled());
+            if link != current_link {
+                if current_link.is_some() {
+                    f.write_str(Hyperlink::CLOSE)?;
+                }
+                if let Some(link) = link {
+                    write!(f, "{link}")?;
+                }
+                current_link = link;
+            }
+
+            f.write_str(&span.text)?;
+        }
+
+        if current_link.is_some() {
+            f.write_str(Hyperlink::CLOSE)?;
+        }
+        f.write_str(&current.transition_to(&Ansi::new()))
+    }
+}
+
+/// Formats a string containing SGR escape sequences using markup-like tags, which makes
+/// styled output readable in logs and test diffs. See [`StyledText::to_semantic`].
+///
+/// ```
+/// # use ansirs::semantic_ansi;
+/// assert_eq!(
+///     semantic_ansi("\x1b[1;38;2;255;0;0merror:\x1b[0m oops"),
+///     "<bold fg=#FF0000>error:</> oops"
+/// );
+/// ```
+#[must_use]
+pub fn semantic_ansi(input: &str) -> String {
+    StyledText::parse(input).to_semantic()
+}
+
+impl From<Span> for StyledText {
+    fn from(span: Span) -> Self {
+        std::iter::once(span).collect()
+    }
+}
+
+impl FromIterator<Span> for StyledText {
+    fn from_iter<T: IntoIterator<Item = Span>>(iter: T) -> Self {
+        let mut text = Self::new();
+        text.extend(iter);
+        text
+    }
+}
+
+impl Extend<Span> for StyledText {
+    fn extend<T: IntoIterator<Item = Span>>(&mut self, iter: T) {
+        for span in iter {
+            self.push_span(span);
+        }
+    }
+}
+
+#[cfg(feature = "strings")]
+impl From<&crate::string::PrettyString> for StyledText {
+    fn from(value: &crate::string::PrettyString) -> Self {
+        let mut span = Span::new(value.raw(), value.style().copied().unwrap_or_default());
+        span.link = value.link().cloned();
+        Self::from(span)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color, ContrastTarget};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn render_with_min_contrast() {
+        let black = Color::from_rgb(0, 0, 0);
+        let dim = Ansi::from_fg((0x30, 0x30, 0x30));
+        let text = StyledText::new()
+            .with("dim ", dim)
+            .with("on white", dim.bg((255, 255, 255)));
+        let options = RenderOptions::new()
+            .background(black)
+            .min_contrast(ContrastTarget::AA);
+
+        let adjusted = text.map_styles(|style| style.with_min_contrast(black, ContrastTarget::AA));
+        assert_ne!(adjusted, text);
+        assert_eq!(
+            text.render(&options),
+            adjusted.render(&RenderOptions::new())
+        );
+        // Spans with their own background are measured against it, not the terminal's.
+        assert_eq!(adjusted.spans()[1].style, dim.bg((255, 255, 255)));
+
+        // Without a background, the minimum contrast does nothing.
+        let no_background = RenderOptions::new().min_contrast(ContrastTarget::AA);
+        assert_eq!(text.render(&no_background), text.to_string());
+        assert_eq!(text.to_string(), text.render(&RenderOptions::new()));
+    }
+
+    #[test]
+    fn push_merges_and_skips_empty() {
+        let mut text = StyledText::new();
+        text.push("a", Ansi::new().bold());
+        text.push("", Ansi::new().italic());
+        text.push("b", Ansi::new().bold());
+        text.push("c", Ansi::new());
+        assert_eq!(
+            text.spans(),
+            &[
+                Span::new("ab", Ansi::new().bold()),
+                Span::new("c", Ansi::new())
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_round_trip() {
+        let text = StyledText::new()
+            .with("red ", Ansi::red())
+            .with("bold", Ansi::red().bold())
+            .with(" plain\n", Ansi::new())
+            .with("bg", Ansi::from_bg((1, 2, 3)));
+        let rendered = text.to_string();
+        assert_eq!(
+            rendered,
+            "\u{1b}[38;2;255;0;0mred \u{1b}[1mbold\u{1b}[0m plain\n\u{1b}[48;2;1;2;3mbg\u{1b}[0m"
+        );
+        assert_eq!(StyledText::parse(&rendered), text);
+    }
+
+    #[test]
+    fn parse_drops_other_sequences() {
+        let text = StyledText::parse("\x1b[2K\x1b[31mA\x1b]0;title\x07B\x1b[39m\tC");
+        assert_eq!(
+            text.spans(),
+            &[
+                Span::new("AB", Ansi::from_fg(Palette::default().get(1))),
+                Span::new("\tC", Ansi::new())
+            ]
+        );
+    }
+
+    #[test]
+    fn debug_forms() {
+        let text = StyledText::new()
+            .with("a", Ansi::new().italic())
+            .with("b", Ansi::new())
+            .with("c", Ansi::from_fg((0, 0, 255)).underline());
+        assert_eq!(text.to_semantic(), "<italic>a</>b<ul fg=#0000FF>c</>");
+        assert_eq!(
+            format!("{text:#}"),
+            "\\e[3ma\\e[0mb\\e[4;38;2;0;0;255mc\\e[0m"
+        );
+        assert_eq!(semantic_ansi("plain"), "plain");
+    }
+
+    #[test]
+    fn hyperlinks() {
+        let link = Hyperlink::new("https://x.y").with_id("1");
+        let text = StyledText::new()
+            .with("see ", Ansi::new())
+            .with_span(Span::new("here", Ansi::new().bold()).with_link(link.clone()))
+            .with_span(Span::new("!", Ansi::new()).with_link(link.clone()));
+        let rendered = text.to_string();
+        assert_eq!(
+            rendered,
+            "see \x1b[1m\x1b]8;id=1;https://x.y\x1b\\here\x1b[0m!\x1b]8;;\x1b\\"
+        );
+        assert_eq!(StyledText::parse(&rendered), text);
+        assert_eq!(
+            text.render(&RenderOptions::new().hyperlinks(false)),
+            "see \x1b[1mhere\x1b[0m!"
+        );
+
+        // BEL terminated links are parsed too.
+        let parsed = StyledText::parse("\x1b]8;;file:///a\x07a\nb\x1b]8;;\x07c");
+        assert_eq!(parsed.spans().len(), 2);
+        let lines = parsed.lines();
+        assert_eq!(
+            lines[0].spans()[0].link(),
+            Some(&Hyperlink::new("file:///a"))
+        );
+        assert_eq!(
+            lines[1].spans()[0].link(),
+            Some(&Hyperlink::new("file:///a"))
+        );
+        assert_eq!(lines[1].spans()[1].link(), None);
+    }
+
+    #[test]
+    fn lines() {
+        let text = StyledText::parse("a\x1b[1mb\r\nc\n\nd\x1b[0m");
+        let lines = text.lines();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(
+            lines[0],
+            StyledText::new()
+                .with("a", Ansi::new())
+                .with("b", Ansi::new().bold())
+        );
+        assert_eq!(lines[1], StyledText::new().with("c", Ansi::new().bold()));
+        assert!(lines[2].is_empty());
+        assert_eq!(lines[3].plain_text(), "d");
+    }
+}
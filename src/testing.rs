@@ -0,0 +1,182 @@
+// Copyright (c) 2022 Tony Barbitta
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Helpers for testing code that produces styled output.
+//!
+//! Comparing raw strings full of escape sequences is brittle: `\x1b[1;31m` and
+//! `\x1b[31m\x1b[1m` look the same in a terminal but are different bytes. Everything in this
+//! module compares output by what it *displays* instead (see [`semantically_eq`]), and
+//! failures are reported using the readable semantic form (`<bold fg=#FF0000>text</>`).
+//!
+//! Requires the `testing` feature.
+
+use std::path::Path;
+
+use crate::{escape_ansi, normalize_ansi, semantically_eq, StyledText};
+
+/// Name of the environment variable that makes [`assert_snapshot`] (re)write snapshot
+/// files instead of comparing against them.
+pub const UPDATE_SNAPSHOTS_ENV: &str = "ANSIRS_UPDATE_SNAPSHOTS";
+
+/// Returns `true` if `left` and `right` look the same in a terminal, no matter how the
+/// escape sequences were emitted. This is the same as [`semantically_eq`], so escape
+/// sequences other than SGR (e.g. clearing the line) still have to match.
+///
+/// ```
+/// # use ansirs::testing::styled_eq;
+/// assert!(styled_eq("\x1b[1;31mhi\x1b[0m", "\x1b[31m\x1b[1mh\x1b[1mi"));
+/// assert!(!styled_eq("\x1b[1mhi", "hi"));
+/// assert!(!styled_eq("\x1b[2Khi", "hi"));
+/// ```
+#[must_use]
+pub fn styled_eq(left: &str, right: &str) -> bool {
+    semantically_eq(left, right)
+}
+
+/// Renders `input` (a string containing escape sequences) as stable markup, suitable for
+/// golden-file tests. Styles are written in the semantic form (see
+/// [`StyledText::to_semantic`]), escape sequences that don't affect styling are dropped
+/// and control characters other than newlines and tabs are made visible.
+///
+/// ```
+/// # use ansirs::testing::snapshot;
+/// assert_eq!(snapshot("\x1b[2K\x1b[1mdone\x1b[0m\r\n"), "<bold>done</>\\x0D\n");
+/// ```
+#[must_use]
+pub fn snapshot(input: &str) -> String {
+    escape_ansi(&StyledText::parse(input).to_semantic())
+}
+
+/// Implementation of [`assert_styled_eq!`](crate::assert_styled_eq), not public API.
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_styled_eq_impl(left: &str, right: &str, message: Option<std::fmt::Arguments<'_>>) {
+    if styled_eq(left, right) {
+        return;
+    }
+
+    let message = message.map(|m| format!(": {m}")).unwrap_or_default();
+    let (mut shown_left, mut shown_right) = (snapshot(left), snapshot(right));
+    if shown_left == shown_right {
+        // The difference is in sequences the snapshot drops, show those as well.
+        shown_left = escape_ansi(&normalize_ansi(left));
+        shown_right = escape_ansi(&normalize_ansi(right));
+    }
+    panic!(
+        "assertion `left == right` failed (styled){message}\n  left: {shown_left}\n right: {shown_right}"
+    );
+}
+
+/// Compares `actual` (a string containing escape sequences) against the snapshot stored at
+/// `path`, see [`snapshot`].
+///
+/// If the file does not exist, or the `ANSIRS_UPDATE_SNAPSHOTS` environment variable is set
+/// (to anything other than `0`), the snapshot is written instead.
+///
+/// # Panics
+/// Panics if the snapshot does not match, or if the file can not be read or written.
+#[track_caller]
+pub fn assert_snapshot(actual: &str, path: impl AsRef<Path>) {
+    let path = path.as_ref();
+    let actual = snapshot(actual);
+    let update = std::env::var_os(UPDATE_SNAPSHOTS_ENV).is_some_and(|v| v != "0");
+
+    let expected = match std::fs::read_to_string(path) {
+        Ok(expected) if !update => expected,
+        Err(err) if !update && err.kind() != std::io::ErrorKind::NotFound => {
+            panic!("failed to read snapshot `{}`: {err}", path.display())
+        }
+        _ => {
+            if let Some(parent) = path.parent() {
+                if let Err(err) = std::fs::create_dir_all(parent) {
+                    panic!("failed to create `{}`: {err}", parent.display());
+                }
+            }
+            if let Err(err) = std::fs::write(path, &actual) {
+                panic!("failed to write snapshot `{}`: {err}", path.display());
+            }
+            return;
+        }
+    };
+
+    if expected != actual {
+        let line = expected
+            .lines()
+            .zip(actual.lines())
+            .position(|(e, a)| e != a)
+            .unwrap_or_else(|| expected.lines().count().min(actual.lines().count()));
+        panic!(
+            "snapshot `{}` does not match (first difference on line {}), set {UPDATE_SNAPSHOTS_ENV}=1 to update it\n--- expected\n{expected}\n--- actual\n{actual}",
+            path.display(),
+            line + 1,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Ansi;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn emission_order_does_not_matter() {
+        let style = Ansi::new().bold().italic().fg((1, 2, 3));
+        let ours = crate::style_text("hello", style);
+        let theirs = "\x1b[38;2;1;2;3m\x1b[3;1mhel\x1b[1mlo\x1b[m";
+        crate::assert_styled_eq!(ours, theirs);
+        crate::assert_styled_eq!(ours.as_str(), theirs, "with a {}", "message");
+    }
+
+    #[test]
+    #[should_panic(expected = "left: <bold>a</>\n right: a")]
+    fn mismatch_shows_semantic_forms() {
+        crate::assert_styled_eq!("\x1b[1ma", "a");
+    }
+
+    #[test]
+    #[should_panic(expected = "left: \\e[2Ka\n right: a")]
+    fn mismatch_in_other_sequences() {
+        crate::assert_styled_eq!("\x1b[2Ka", "a");
+    }
+
+    #[test]
+    fn agrees_with_semantically_eq() {
+        let pairs = [
+            ("\x1b[2Kab", "ab"),
+            ("\x1b[1mab\x1b[0m", "\x1b[1ma\x1b[1mb"),
+            ("\x1b[31ma\x1b[39m", "\x1b[38;5;1ma"),
+            ("\x1b]8;;https://a.b\x1b\\a\x1b]8;;\x1b\\", "a"),
+        ];
+        for (left, right) in pairs {
+            assert_eq!(
+                styled_eq(left, right),
+                semantically_eq(left, right),
+                "{left:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn snapshots() {
+        let dir = std::env::temp_dir().join(format!("ansirs-snapshots-{}", std::process::id()));
+        let path = dir.join("nested").join("out.snap");
+        let output = "\x1b[4mtitle\x1b[0m\nbody";
+
+        // Missing snapshots are created, then compared.
+        assert_snapshot(output, &path);
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "<ul>title</>\nbody"
+        );
+        assert_snapshot("\x1b[4mti\x1b[4mtle\x1b[m\nbody", &path);
+
+        let result = std::panic::catch_unwind(|| assert_snapshot("title\nbody", &path));
+        assert!(result.is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}